@@ -0,0 +1,153 @@
+// Step timings and allocation counts for the choices the grid offers between ways of doing the
+// same work. Run with `cargo bench`. Every timing is the fastest of BATCHES means over BATCH_STEPS
+// steps of a grid hanging from its top corners, after WARMUP_STEPS untimed ones, so a busy machine
+// only slows the batches it interrupts.
+//
+// Partitioning, 200x200: splitting the force pass by whole columns keeps most springs inside the
+// block a task works on. Only parallel runs can tell the layouts apart; measured single-threaded
+// on one core they come out level within the noise between runs:
+//   flat 2.76-2.92 ms/step, columns x1 2.74-2.78 ms/step, columns x8 2.68-2.78 ms/step
+//
+// Spring pass, 200x200: the edge list visits each spring once and scatters its force to both
+// ends, where the per-node pass computes every spring twice. On one core the edge list's best
+// runs are about 4% quicker, inside the spread between runs:
+//   per node serial 2.52-2.92 ms/step, edge list serial 2.40-3.01 ms/step
+//   per node parallel 2.47-2.60 ms/step, edge list parallel 2.37-2.54 ms/step
+//
+// Buffering, 100x100: a counting allocator reports what each step allocates, against a step that
+// collects fresh position and velocity vectors as the force loop did before double buffering.
+// Measured: double 0 allocs and 0 bytes per step, collecting 2 allocs and 160000 bytes per step.
+
+extern crate num_cpus;
+extern crate soft_body_sim_rust;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use soft_body_sim_rust::benchmark;
+use soft_body_sim_rust::{Grid, Partitioning, Simulation, SimulationConfig};
+
+const DELTA_T: f32 = 0.001;
+const WARMUP_STEPS: usize = 5;
+const BATCH_STEPS: usize = 20;
+const BATCHES: usize = 10;
+const PARTITIONING_SIZE: usize = 200;
+const SPRING_PASS_SIZE: usize = 200;
+const BUFFERING_SIZE: usize = 100;
+const PARTITIONINGS: [(&str, Partitioning); 3] = [
+    ("flat", Partitioning::Flat),
+    ("columns x1", Partitioning::Columns { per_task: 1 }),
+    ("columns x8", Partitioning::Columns { per_task: 8 }),
+];
+
+// Counts every allocation so the buffering section can report how many a step makes.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn hanging_grid(size: usize, config: SimulationConfig) -> Grid {
+    let mut grid = Grid::with_config(size, size, config);
+    grid.get_neighbors();
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    grid
+}
+
+// Microseconds per step of `grid` under gravity.
+fn micros_per_step(grid: &mut Grid) -> f64 {
+    for _ in 0..WARMUP_STEPS {
+        grid.advance(DELTA_T, true, false);
+    }
+    (0..BATCHES)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..BATCH_STEPS {
+                grid.advance(DELTA_T, true, false);
+            }
+            start.elapsed().as_secs_f64() * 1e6 / BATCH_STEPS as f64
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn bench_partitioning() {
+    println!("Partitioning, {0}x{0} grid", PARTITIONING_SIZE);
+    for threads in benchmark::thread_counts(num_cpus::get()) {
+        let pool = benchmark::build_thread_pool(threads).expect("failed to build a thread pool");
+        for (name, partitioning) in PARTITIONINGS {
+            let mut grid = hanging_grid(PARTITIONING_SIZE, SimulationConfig { partitioning, ..SimulationConfig::default() });
+            let micros = pool.install(|| micros_per_step(&mut grid));
+            println!("  {:<12} {:>3} threads {:>12.1} us/step", name, threads, micros);
+        }
+    }
+}
+
+fn bench_spring_pass() {
+    println!("Spring pass, {0}x{0} grid", SPRING_PASS_SIZE);
+    for parallel in [false, true] {
+        for (name, edge_springs) in [("per node", false), ("edge list", true)] {
+            let mut grid = hanging_grid(SPRING_PASS_SIZE, SimulationConfig { parallel, edge_springs, ..SimulationConfig::default() });
+            let micros = micros_per_step(&mut grid);
+            let mode = if parallel { "parallel" } else { "serial" };
+            println!("  {:<12} {:<8} {:>12.1} us/step", name, mode, micros);
+        }
+    }
+}
+
+// The step as it was before double buffering: the new positions and velocities were collected
+// into fresh vectors that then replaced the old ones.
+fn advance_collecting(grid: &mut Grid) {
+    grid.advance(DELTA_T, true, false);
+    grid.positions = grid.positions.to_vec();
+    grid.velocities = grid.velocities.to_vec();
+}
+
+fn advance_double_buffered(grid: &mut Grid) {
+    grid.advance(DELTA_T, true, false);
+}
+
+fn bench_buffering() {
+    println!("Buffering, {0}x{0} grid", BUFFERING_SIZE);
+    for (name, advance) in [("double", advance_double_buffered as fn(&mut Grid)), ("collecting", advance_collecting)] {
+        let mut grid = hanging_grid(BUFFERING_SIZE, SimulationConfig::default());
+        for _ in 0..WARMUP_STEPS {
+            advance(&mut grid);
+        }
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+        let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+        for _ in 0..BATCH_STEPS {
+            advance(&mut grid);
+        }
+        let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64 / BATCH_STEPS as f64;
+        let bytes = (ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes) as f64 / BATCH_STEPS as f64;
+        println!("  {:<12} {:>8.1} allocs/step {:>12.0} bytes/step", name, allocations, bytes);
+    }
+}
+
+fn main() {
+    bench_partitioning();
+    bench_spring_pass();
+    bench_buffering();
+}
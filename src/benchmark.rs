@@ -0,0 +1,132 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+pub struct TimingSummary {
+    pub steps: usize,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p99: Duration,
+}
+
+impl TimingSummary {
+    pub fn from_durations(durations: &[Duration]) -> Option<TimingSummary> {
+        if durations.is_empty() {
+            return None;
+        }
+        let mut sorted = durations.to_vec();
+        sorted.sort();
+        let total: Duration = sorted.iter().sum();
+        Some(TimingSummary {
+            steps: sorted.len(),
+            total,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: total / sorted.len() as u32,
+            p50: percentile(&sorted, 50.0),
+            p99: percentile(&sorted, 99.0),
+        })
+    }
+
+    pub fn to_json(&self, width: usize, height: usize, threads: usize) -> String {
+        format!(
+            "{{\n  \"width\": {},\n  \"height\": {},\n  \"threads\": {},\n  \"steps\": {},\n  \"min_us\": {:.3},\n  \"max_us\": {:.3},\n  \"mean_us\": {:.3},\n  \"p50_us\": {:.3},\n  \"p99_us\": {:.3}\n}}\n",
+            width, height, threads, self.steps,
+            micros(self.min), micros(self.max), micros(self.mean), micros(self.p50), micros(self.p99)
+        )
+    }
+
+    pub fn write_json(&self, path: &Path, width: usize, height: usize, threads: usize) -> io::Result<()> {
+        fs::write(path, self.to_json(width, height, threads))
+    }
+}
+
+// What a windowed run did, printed once it ends, whether it ran out its duration or the window
+// was closed first.
+pub struct RunSummary {
+    // Steps of DELTA_TIME simulated, each of which an update can run several of.
+    pub steps: u64,
+    pub elapsed: Duration,
+    // Timings of the updates, or None if none finished.
+    pub updates: Option<TimingSummary>,
+    // Simulated seconds covered, for simulations that keep a clock.
+    pub sim_time: Option<f32>,
+    // Most memory the process held at once, in bytes, where the platform reports it.
+    pub peak_memory: Option<u64>,
+    // Kinetic plus potential energy at the end, for simulations that track it.
+    pub energy: Option<f32>,
+}
+
+impl RunSummary {
+    pub fn report(&self) -> String {
+        let mut report = format!("Ran {} steps in {:.2}s\n", self.steps, self.elapsed.as_secs_f64());
+        if let Some(updates) = &self.updates {
+            report += &format!("  mean step      {:>12.3} us\n", micros(updates.total) / self.steps.max(1) as f64);
+            report += &format!("  mean update    {:>12.3} us over {} updates\n", micros(updates.mean), updates.steps);
+            report += &format!("  median update  {:>12.3} us\n", micros(updates.p50));
+        }
+        if let Some(sim_time) = self.sim_time {
+            report += &format!("  simulated      {:>12.3} s\n", sim_time);
+        }
+        if let Some(bytes) = self.peak_memory {
+            report += &format!("  peak memory    {:>12.1} MiB\n", bytes as f64 / (1024.0 * 1024.0));
+        }
+        if let Some(energy) = self.energy {
+            report += &format!("  final energy   {:>12.6}\n", energy);
+        }
+        report
+    }
+}
+
+// The process's peak resident set, from the VmHWM line of /proc/self/status. Only Linux has it.
+pub fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+// Each simulation owns its pool rather than configuring rayon's global one, which can only be
+// built once.
+pub fn build_thread_pool(thread_count: usize) -> Result<ThreadPool, ThreadPoolBuildError> {
+    ThreadPoolBuilder::new().num_threads(thread_count).build()
+}
+
+// 1, 2, 4 and so on up to `max`, ending on `max` itself when it isn't a power of two.
+pub fn thread_counts(max: usize) -> Vec<usize> {
+    let max = max.max(1);
+    let mut counts: Vec<usize> = std::iter::successors(Some(1usize), |&count| count.checked_mul(2))
+        .take_while(|&count| count < max)
+        .collect();
+    counts.push(max);
+    counts
+}
+
+// One row per run with its average step time and its speedup over the first run.
+pub fn speedup_table(runs: &[(usize, Duration)]) -> String {
+    let mut table = format!("{:>8} {:>14} {:>8}\n", "threads", "mean step us", "speedup");
+    let Some(&(_, baseline)) = runs.first() else {
+        return table;
+    };
+    for &(threads, mean) in runs {
+        let speedup = baseline.as_secs_f64() / mean.as_secs_f64().max(f64::MIN_POSITIVE);
+        table += &format!("{:>8} {:>14.3} {:>7.2}x\n", threads, micros(mean), speedup);
+    }
+    table
+}
+
+// Nearest-rank percentile of an already sorted, non-empty slice.
+fn percentile(sorted: &[Duration], percent: f64) -> Duration {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
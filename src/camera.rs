@@ -0,0 +1,181 @@
+// World units visible from the centre to the edge of the window at the default zoom.
+const VIEW_SCALE: f32 = 25.0;
+const MIN_SCALE: f32 = 0.001;
+const MAX_SCALE: f32 = 10.0;
+// Fraction of the window left empty around a fitted box.
+const FIT_MARGIN: f32 = 0.1;
+
+// 2D view transform shared by the vertex shader and mouse picking:
+// ndc = (world + offset) * scale
+pub struct Camera {
+    pub offset: (f32, f32),
+    pub scale: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera {
+            offset: (0.0, 0.0),
+            scale: 1.0 / VIEW_SCALE,
+        }
+    }
+}
+
+impl Camera {
+    pub fn screen_to_world(&self, screen: (f32, f32), window_size: (f32, f32)) -> (f32, f32) {
+        screen_to_world(screen, window_size, self)
+    }
+
+    pub fn world_to_screen(&self, world: (f32, f32), window_size: (f32, f32)) -> (f32, f32) {
+        world_to_screen(world, window_size, self)
+    }
+
+    // Moves the view so the world point under `from` ends up under `to`.
+    pub fn pan(&mut self, from: (f32, f32), to: (f32, f32), window_size: (f32, f32)) {
+        let from = screen_to_ndc(from, window_size);
+        let to = screen_to_ndc(to, window_size);
+        self.offset.0 += (to.0 - from.0) / self.scale;
+        self.offset.1 += (to.1 - from.1) / self.scale;
+    }
+
+    // Centres the view on the box between `min` and `max` and zooms so all of it is visible.
+    pub fn fit(&mut self, min: (f32, f32), max: (f32, f32)) {
+        let extent = (max.0 - min.0).max(max.1 - min.1);
+        self.offset = (-0.5 * (min.0 + max.0), -0.5 * (min.1 + max.1));
+        // NDC spans two units across the window.
+        if extent > 0.0 {
+            self.scale = (2.0 * (1.0 - FIT_MARGIN) / extent).clamp(MIN_SCALE, MAX_SCALE);
+        }
+    }
+
+    // Zooms by `factor` while keeping the world point under `screen` fixed.
+    pub fn zoom(&mut self, factor: f32, screen: (f32, f32), window_size: (f32, f32)) {
+        let anchor = self.screen_to_world(screen, window_size);
+        self.scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        let ndc = screen_to_ndc(screen, window_size);
+        self.offset = (ndc.0 / self.scale - anchor.0, ndc.1 / self.scale - anchor.1);
+    }
+}
+
+// Maps a cursor position in window pixels, measured from the top left, to the world point drawn
+// under it. This inverts the vertex shader's transform, so anything picked with it lines up with
+// what's on screen.
+pub fn screen_to_world(screen: (f32, f32), window_size: (f32, f32), camera: &Camera) -> (f32, f32) {
+    let ndc = screen_to_ndc(screen, window_size);
+    (ndc.0 / camera.scale - camera.offset.0, ndc.1 / camera.scale - camera.offset.1)
+}
+
+// The window pixel a world point is drawn at; the inverse of `screen_to_world`.
+pub fn world_to_screen(world: (f32, f32), window_size: (f32, f32), camera: &Camera) -> (f32, f32) {
+    let ndc = ((world.0 + camera.offset.0) * camera.scale, (world.1 + camera.offset.1) * camera.scale);
+    ndc_to_screen(ndc, window_size)
+}
+
+fn screen_to_ndc(screen: (f32, f32), window_size: (f32, f32)) -> (f32, f32) {
+    (2.0 * screen.0 / window_size.0 - 1.0, 1.0 - 2.0 * screen.1 / window_size.1)
+}
+
+fn ndc_to_screen(ndc: (f32, f32), window_size: (f32, f32)) -> (f32, f32) {
+    ((ndc.0 + 1.0) * 0.5 * window_size.0, (1.0 - ndc.1) * 0.5 * window_size.1)
+}
+
+const ORBIT_SPEED: f32 = 0.01;
+const MIN_DISTANCE: f32 = 1.0;
+const MAX_DISTANCE: f32 = 500.0;
+const FIELD_OF_VIEW: f32 = std::f32::consts::FRAC_PI_4;
+const NEAR_PLANE: f32 = 0.1;
+const FAR_PLANE: f32 = 1000.0;
+
+// Perspective camera circling `target`, used by the 3D mode.
+pub struct OrbitCamera {
+    pub target: (f32, f32, f32),
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> OrbitCamera {
+        OrbitCamera {
+            target: (0.0, 0.0, 0.0),
+            yaw: 0.6,
+            pitch: 0.4,
+            distance: 60.0,
+        }
+    }
+}
+
+impl OrbitCamera {
+    pub fn orbit(&mut self, dx: f32, dy: f32) {
+        self.yaw += dx * ORBIT_SPEED;
+        // Stop short of straight up or down, where the view direction is parallel to the up vector.
+        self.pitch = (self.pitch + dy * ORBIT_SPEED).clamp(-1.5, 1.5);
+    }
+
+    pub fn zoom(&mut self, factor: f32) {
+        self.distance = (self.distance / factor).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    pub fn eye(&self) -> (f32, f32, f32) {
+        (
+            self.target.0 + self.distance * self.pitch.cos() * self.yaw.sin(),
+            self.target.1 + self.distance * self.pitch.sin(),
+            self.target.2 + self.distance * self.pitch.cos() * self.yaw.cos(),
+        )
+    }
+
+    // Column-major projection * view matrix, as glium expects for a mat4 uniform.
+    pub fn view_projection(&self, aspect: f32) -> [[f32; 4]; 4] {
+        multiply(&perspective(aspect), &self.view())
+    }
+
+    fn view(&self) -> [[f32; 4]; 4] {
+        let eye = self.eye();
+        let forward = normalize(sub(self.target, eye));
+        let side = normalize(cross(forward, (0.0, 1.0, 0.0)));
+        let up = cross(side, forward);
+        [
+            [side.0, up.0, -forward.0, 0.0],
+            [side.1, up.1, -forward.1, 0.0],
+            [side.2, up.2, -forward.2, 0.0],
+            [-dot(side, eye), -dot(up, eye), dot(forward, eye), 1.0],
+        ]
+    }
+}
+
+fn perspective(aspect: f32) -> [[f32; 4]; 4] {
+    let f = 1.0 / (FIELD_OF_VIEW / 2.0).tan();
+    [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, (FAR_PLANE + NEAR_PLANE) / (NEAR_PLANE - FAR_PLANE), -1.0],
+        [0.0, 0.0, 2.0 * FAR_PLANE * NEAR_PLANE / (NEAR_PLANE - FAR_PLANE), 0.0],
+    ]
+}
+
+fn multiply(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut result = [[0.0; 4]; 4];
+    for (column, result_column) in result.iter_mut().enumerate() {
+        for (row, value) in result_column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b[column][k]).sum();
+        }
+    }
+    result
+}
+
+fn sub(a: (f32, f32, f32), b: (f32, f32, f32)) -> (f32, f32, f32) {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+fn dot(a: (f32, f32, f32), b: (f32, f32, f32)) -> f32 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+fn cross(a: (f32, f32, f32), b: (f32, f32, f32)) -> (f32, f32, f32) {
+    (a.1 * b.2 - a.2 * b.1, a.2 * b.0 - a.0 * b.2, a.0 * b.1 - a.1 * b.0)
+}
+
+fn normalize(v: (f32, f32, f32)) -> (f32, f32, f32) {
+    let length = dot(v, v).sqrt();
+    (v.0 / length, v.1 / length, v.2 / length)
+}
@@ -0,0 +1,407 @@
+#[cfg(feature = "config")]
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::grid::{Falloff, PinPreset, Ripple, SimulationConfig};
+#[cfg(feature = "config")]
+use crate::scenario;
+use crate::run_log::{Format, Level};
+use crate::theme::Theme;
+use crate::vec2::Vec2;
+
+// Where --profile writes its folded stacks when no path is given.
+const DEFAULT_PROFILE: &str = "profile.folded";
+// Reach of the pinning brush when --brush-radius isn't given.
+const DEFAULT_BRUSH_RADIUS: f32 = 1.5;
+// Direction the filled mesh is lit from when --light isn't given: up and to the left, in front.
+const DEFAULT_LIGHT: (f32, f32, f32) = (-0.3, 0.5, 0.8);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Shape {
+    Sheet,
+    // A single row of `width` nodes.
+    Rope,
+    // A loop `width` nodes around and `height` nodes thick.
+    Tube,
+}
+
+// Nodes pinned instead of the shape's usual anchors: a preset named with --pin, or the indices a
+// config file lists. Whichever comes last wins.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pins {
+    Preset(PinPreset),
+    Nodes(Vec<usize>),
+}
+
+// How fresh grids start moving, set with --initial-velocity.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InitialVelocity {
+    Uniform(Vec2),
+    // Speed outwards from the middle of the grid.
+    Radial(f32),
+    // Sideways speed of the top edge, with the bottom edge moving the opposite way.
+    Shear(f32),
+}
+
+pub struct Options {
+    pub width: usize,
+    pub height: usize,
+    // Parameters every grid starts from, before the flags that set them individually.
+    pub simulation: SimulationConfig,
+    pub pins: Option<Pins>,
+    pub threads: usize,
+    pub substeps: usize,
+    // Columns handed to each pool task at a time, or None to let rayon split the nodes.
+    pub columns_per_task: Option<usize>,
+    pub gravity: bool,
+    pub headless: bool,
+    // Runs the headless workload at every thread count from 1 up to the CPU count and compares them.
+    pub bench_threads: bool,
+    pub three_d: bool,
+    pub shape: Shape,
+    // Anticlockwise rotation of each fresh grid about its middle, in degrees.
+    pub tilt: f32,
+    // Copies of the grid placed side by side, each simulated independently.
+    pub patches: usize,
+    // Stacks the patches one above another instead, with only the lowest keeping its anchors so
+    // the rest fall onto it.
+    pub stack_patches: bool,
+    // Makes the patches collide with each other rather than pass through.
+    pub patch_collision: bool,
+    // Snapshot file to start from instead of a fresh grid.
+    pub load: Option<String>,
+    // Image whose white pixels give the shape of a fresh grid.
+    pub mask: Option<String>,
+    // Obstacles as (x, y, radius), one per --sphere flag.
+    pub spheres: Vec<(f32, f32, f32)>,
+    // Box obstacles as opposite corners (x0, y0, x1, y1), one per --box flag.
+    pub boxes: Vec<(f32, f32, f32, f32)>,
+    // Swings the anchors side to side as (amplitude, period), like a flag waved from its pole.
+    pub swing: Option<(f32, f32)>,
+    pub initial_velocity: Option<InitialVelocity>,
+    // Node indices whose trajectories are written to the CSV file at the given path.
+    pub record: Option<(Vec<usize>, String)>,
+    // File the window's input is logged to, step by step, and a log to play back the same way.
+    pub record_input: Option<String>,
+    pub replay: Option<String>,
+    // Seconds a windowed run simulates for before closing the window itself. Without it the
+    // simulation stops after the usual ten seconds and the window stays open.
+    pub duration: Option<f32>,
+    // Steps the window's scene with a compute shader, as long as it only needs what the shader does.
+    pub gpu: bool,
+    // Reach and falloff of the shift-click ripple.
+    pub ripple: Ripple,
+    // Reach of the brush that pins nodes under a ctrl-drag and unpins them under an alt-drag.
+    pub brush_radius: f32,
+    // Direction towards the light the filled mesh is shaded by, with z out of the screen. Unit length.
+    pub light: (f32, f32, f32),
+    // Colours the window starts in; K cycles through the rest.
+    pub theme: Theme,
+    pub log_level: Level,
+    // File each run's parameters and average update time are appended to.
+    pub log_file: Option<String>,
+    pub log_format: Format,
+    // File the per-phase step timings are written to as folded stacks, for flamegraph tools.
+    pub profile: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            width: crate::WIDTH,
+            height: crate::HEIGHT,
+            simulation: SimulationConfig::default(),
+            pins: None,
+            threads: (num_cpus::get() / 2).max(1),
+            substeps: crate::grid::SimulationConfig::default().substeps,
+            columns_per_task: None,
+            gravity: true,
+            headless: false,
+            bench_threads: false,
+            three_d: false,
+            shape: Shape::Sheet,
+            tilt: 0.0,
+            patches: 1,
+            stack_patches: false,
+            patch_collision: false,
+            load: None,
+            mask: None,
+            spheres: vec![],
+            boxes: vec![],
+            swing: None,
+            initial_velocity: None,
+            record: None,
+            record_input: None,
+            replay: None,
+            duration: None,
+            gpu: false,
+            ripple: Ripple::default(),
+            brush_radius: DEFAULT_BRUSH_RADIUS,
+            light: normalized(DEFAULT_LIGHT),
+            theme: Theme::default(),
+            log_level: Level::Info,
+            log_file: None,
+            log_format: Format::default(),
+            profile: None,
+        }
+    }
+}
+
+impl Options {
+    // Invalid or missing values keep their defaults and print a warning rather than aborting.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Options {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            match flag.as_str() {
+                "--headless" => options.headless = true,
+                "--bench-threads" => options.bench_threads = true,
+                "--stack-patches" => options.stack_patches = true,
+                "--patch-collision" => options.patch_collision = true,
+                "--3d" => options.three_d = true,
+                "--edge-springs" => options.simulation.edge_springs = true,
+                "--gpu" => options.gpu = true,
+                // Applied where it appears, so flags after it override the file.
+                "--config" => match inline_value.or_else(|| args.next()) {
+                    Some(path) => options.apply_scenario(&path),
+                    None => eprintln!("Warning: --config expects a file path"),
+                },
+                "--record-input" => match inline_value.or_else(|| args.next()) {
+                    Some(path) => options.record_input = Some(path),
+                    None => eprintln!("Warning: --record-input expects a file path"),
+                },
+                "--replay" => match inline_value.or_else(|| args.next()) {
+                    Some(path) => options.replay = Some(path),
+                    None => eprintln!("Warning: --replay expects a file path"),
+                },
+                "--load" => match inline_value.or_else(|| args.next()) {
+                    Some(path) => options.load = Some(path),
+                    None => eprintln!("Warning: --load expects a file path"),
+                },
+                "--shape" => match inline_value.or_else(|| args.next()).as_deref() {
+                    Some("sheet") => options.shape = Shape::Sheet,
+                    Some("rope") => options.shape = Shape::Rope,
+                    Some("tube") => options.shape = Shape::Tube,
+                    _ => eprintln!("Warning: --shape expects sheet, rope or tube, keeping {:?}", options.shape),
+                },
+                "--pin" => match inline_value.or_else(|| args.next()).as_deref() {
+                    Some("top-corners") => options.pins = Some(Pins::Preset(PinPreset::TopCorners)),
+                    Some("top-edge") => options.pins = Some(Pins::Preset(PinPreset::TopEdge)),
+                    Some("four-corners") => options.pins = Some(Pins::Preset(PinPreset::FourCorners)),
+                    Some("none") => options.pins = Some(Pins::Preset(PinPreset::Free)),
+                    Some("center") => options.pins = Some(Pins::Preset(PinPreset::Center)),
+                    _ => eprintln!("Warning: --pin expects top-corners, top-edge, four-corners, none or center"),
+                },
+                "--mask" => match inline_value.or_else(|| args.next()) {
+                    Some(path) => options.mask = Some(path),
+                    None => eprintln!("Warning: --mask expects a file path"),
+                },
+                "--sphere" => match inline_value.or_else(|| args.next()).as_deref().and_then(parse_sphere) {
+                    Some(sphere) => options.spheres.push(sphere),
+                    None => eprintln!("Warning: --sphere expects X,Y,RADIUS with a positive radius"),
+                },
+                "--light" => match inline_value.or_else(|| args.next()).as_deref().and_then(parse_light) {
+                    Some(light) => options.light = light,
+                    None => eprintln!("Warning: --light expects X,Y,Z not all zero"),
+                },
+                "--box" => match inline_value.or_else(|| args.next()).as_deref().and_then(parse_box) {
+                    Some(bounds) => options.boxes.push(bounds),
+                    None => eprintln!("Warning: --box expects X0,Y0,X1,Y1 with corners apart on both axes"),
+                },
+                "--initial-velocity" => match inline_value.or_else(|| args.next()).as_deref().and_then(parse_initial_velocity) {
+                    Some(pattern) => options.initial_velocity = Some(pattern),
+                    None => eprintln!("Warning: --initial-velocity expects uniform:VX,VY, radial:SPEED or shear:SPEED"),
+                },
+                "--swing" => match inline_value.or_else(|| args.next()).as_deref().and_then(parse_swing) {
+                    Some(swing) => options.swing = Some(swing),
+                    None => eprintln!("Warning: --swing expects AMPLITUDE,PERIOD with a positive period"),
+                },
+                "--record" => {
+                    let nodes = inline_value.or_else(|| args.next()).as_deref().and_then(parse_nodes);
+                    match (nodes, args.next()) {
+                        (Some(nodes), Some(path)) => options.record = Some((nodes, path)),
+                        _ => eprintln!("Warning: --record expects a comma-separated list of node indices and a file path"),
+                    }
+                }
+                // The path is optional, so it can only be given inline.
+                "--profile" => options.profile = Some(inline_value.unwrap_or_else(|| DEFAULT_PROFILE.to_string())),
+                "--log-file" => match inline_value.or_else(|| args.next()) {
+                    Some(path) => options.log_file = Some(path),
+                    None => eprintln!("Warning: --log-file expects a file path"),
+                },
+                "--theme" => match inline_value.or_else(|| args.next()).as_deref().map(Theme::from_str) {
+                    Some(Ok(theme)) => options.theme = theme,
+                    _ => eprintln!("Warning: --theme expects dark, mono, light or blueprint, keeping {:?}", options.theme),
+                },
+                "--log-format" => match inline_value.or_else(|| args.next()).as_deref().map(Format::from_str) {
+                    Some(Ok(format)) => options.log_format = format,
+                    _ => eprintln!("Warning: --log-format expects text or json, keeping {:?}", options.log_format),
+                },
+                "--log-level" => match inline_value.or_else(|| args.next()).as_deref().map(Level::from_str) {
+                    Some(Ok(level)) => options.log_level = level,
+                    _ => eprintln!("Warning: --log-level expects error, warn, info or debug, keeping {:?}", options.log_level),
+                },
+                "--ripple-falloff" => match inline_value.or_else(|| args.next()).as_deref() {
+                    Some("constant") => options.ripple.falloff = Falloff::Constant,
+                    Some("linear") => options.ripple.falloff = Falloff::Linear,
+                    Some("smooth") => options.ripple.falloff = Falloff::Smooth,
+                    _ => eprintln!("Warning: --ripple-falloff expects constant, linear or smooth, keeping {:?}", options.ripple.falloff),
+                },
+                "--width" | "--height" | "--threads" | "--substeps" | "--columns-per-task" | "--patches" | "--ripple-radius" | "--brush-radius" | "--gravity"
+                | "--tilt" | "--duration" | "--stiffness-x" | "--stiffness-y" => {
+                    let value = inline_value.or_else(|| args.next());
+                    options.apply(&flag, value);
+                }
+                _ => eprintln!("Warning: ignoring unknown argument {}", flag),
+            }
+        }
+        options
+    }
+
+    #[cfg(feature = "config")]
+    fn apply_scenario(&mut self, path: &str) {
+        let scenario = match scenario::load(Path::new(path)) {
+            Ok(scenario) => scenario,
+            Err(error) => {
+                eprintln!("Failed to load config {}: {}", path, error);
+                return;
+            }
+        };
+        self.width = scenario.width.unwrap_or(self.width);
+        self.height = scenario.height.unwrap_or(self.height);
+        self.pins = scenario.pins.map(Pins::Nodes).or(self.pins.take());
+        self.substeps = scenario.simulation.substeps.max(1);
+        self.simulation = scenario.simulation;
+    }
+
+    #[cfg(not(feature = "config"))]
+    fn apply_scenario(&mut self, path: &str) {
+        eprintln!("Warning: loading config {} requires the config feature", path);
+    }
+
+    fn apply(&mut self, flag: &str, value: Option<String>) {
+        match flag {
+            "--width" => self.width = parse_count(flag, value, self.width),
+            "--height" => self.height = parse_count(flag, value, self.height),
+            "--threads" => self.threads = parse_count(flag, value, self.threads),
+            "--substeps" => self.substeps = parse_count(flag, value, self.substeps),
+            "--columns-per-task" => self.columns_per_task = Some(parse_count(flag, value, 1)),
+            "--patches" => self.patches = parse_count(flag, value, self.patches),
+            "--ripple-radius" => self.ripple.radius = parse_positive(flag, value, self.ripple.radius),
+            "--brush-radius" => self.brush_radius = parse_positive(flag, value, self.brush_radius),
+            "--tilt" => match value.as_deref().map(f32::from_str) {
+                Some(Ok(degrees)) if degrees.is_finite() => self.tilt = degrees,
+                _ => eprintln!("Warning: --tilt expects an angle in degrees, keeping {}", self.tilt),
+            },
+            "--stiffness-x" => {
+                let default = self.simulation.stiffness_x.unwrap_or(self.simulation.spring_coefficient);
+                self.simulation.stiffness_x = Some(parse_positive(flag, value, default));
+            }
+            "--stiffness-y" => {
+                let default = self.simulation.stiffness_y.unwrap_or(self.simulation.spring_coefficient);
+                self.simulation.stiffness_y = Some(parse_positive(flag, value, default));
+            }
+            "--duration" => match value.as_deref().map(f32::from_str) {
+                Some(Ok(seconds)) if seconds > 0.0 && seconds.is_finite() => self.duration = Some(seconds),
+                _ => eprintln!("Warning: --duration expects a positive number of seconds, ignoring it"),
+            },
+            "--gravity" => match value.as_deref() {
+                Some("on") => self.gravity = true,
+                Some("off") => self.gravity = false,
+                _ => eprintln!("Warning: --gravity expects on or off, keeping {}", if self.gravity { "on" } else { "off" }),
+            },
+            _ => unreachable!("unhandled flag {}", flag),
+        }
+    }
+}
+
+fn parse_sphere(value: &str) -> Option<(f32, f32, f32)> {
+    let parts = value
+        .split(',')
+        .map(|part| f32::from_str(part.trim()).ok())
+        .collect::<Option<Vec<f32>>>()?;
+    match parts[..] {
+        [x, y, radius] if radius > 0.0 => Some((x, y, radius)),
+        _ => None,
+    }
+}
+
+fn parse_initial_velocity(value: &str) -> Option<InitialVelocity> {
+    let (pattern, arguments) = value.split_once(':')?;
+    let parts = arguments
+        .split(',')
+        .map(|part| f32::from_str(part.trim()).ok().filter(|number| number.is_finite()))
+        .collect::<Option<Vec<f32>>>()?;
+    match (pattern, &parts[..]) {
+        ("uniform", &[x, y]) => Some(InitialVelocity::Uniform(Vec2::new(x, y))),
+        ("radial", &[speed]) => Some(InitialVelocity::Radial(speed)),
+        ("shear", &[speed]) => Some(InitialVelocity::Shear(speed)),
+        _ => None,
+    }
+}
+
+fn parse_box(value: &str) -> Option<(f32, f32, f32, f32)> {
+    let parts = value
+        .split(',')
+        .map(|part| f32::from_str(part.trim()).ok())
+        .collect::<Option<Vec<f32>>>()?;
+    match parts[..] {
+        [x0, y0, x1, y1] if x0 != x1 && y0 != y1 && parts.iter().all(|part| part.is_finite()) => Some((x0, y0, x1, y1)),
+        _ => None,
+    }
+}
+
+fn parse_light(value: &str) -> Option<(f32, f32, f32)> {
+    let parts = value
+        .split(',')
+        .map(|part| f32::from_str(part.trim()).ok())
+        .collect::<Option<Vec<f32>>>()?;
+    match parts[..] {
+        [x, y, z] if (x * x + y * y + z * z).is_normal() => Some(normalized((x, y, z))),
+        _ => None,
+    }
+}
+
+fn normalized((x, y, z): (f32, f32, f32)) -> (f32, f32, f32) {
+    let length = (x * x + y * y + z * z).sqrt();
+    (x / length, y / length, z / length)
+}
+
+fn parse_swing(value: &str) -> Option<(f32, f32)> {
+    let parts = value
+        .split(',')
+        .map(|part| f32::from_str(part.trim()).ok())
+        .collect::<Option<Vec<f32>>>()?;
+    match parts[..] {
+        [amplitude, period] if period > 0.0 && amplitude.is_finite() => Some((amplitude, period)),
+        _ => None,
+    }
+}
+
+fn parse_nodes(value: &str) -> Option<Vec<usize>> {
+    value.split(',').map(|part| usize::from_str(part.trim()).ok()).collect()
+}
+
+fn parse_positive(flag: &str, value: Option<String>, default: f32) -> f32 {
+    match value.as_deref().map(f32::from_str) {
+        Some(Ok(number)) if number > 0.0 && number.is_finite() => number,
+        _ => {
+            eprintln!("Warning: {} expects a positive number, using {}", flag, default);
+            default
+        }
+    }
+}
+
+fn parse_count(flag: &str, value: Option<String>, default: usize) -> usize {
+    match value.as_deref().map(usize::from_str) {
+        Some(Ok(count)) if count > 0 => count,
+        _ => {
+            eprintln!("Warning: {} expects a positive integer, using {}", flag, default);
+            default
+        }
+    }
+}
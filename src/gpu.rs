@@ -0,0 +1,291 @@
+use glium::backend::Facade;
+use glium::buffer::{Buffer, BufferMode, BufferType};
+use glium::program::ComputeShader;
+
+use crate::grid::{Grid, Integrator, Simulation, SimulationConfig, SpringModel};
+use crate::vec2::Vec2;
+
+// Nodes each shader invocation group works through, matching `local_size_x` below.
+const GROUP_SIZE: usize = 64;
+// Size of the grid `verify` compares the two steps on, the steps it runs, and how far apart any
+// node is allowed to end up. Sums are taken in a different order on the GPU, so they aren't exact.
+const VERIFY_SIZE: usize = 8;
+const VERIFY_STEPS: usize = 100;
+const VERIFY_DELTA_T: f32 = 0.01;
+const VERIFY_TOLERANCE: f32 = 1e-3;
+
+// One symplectic Euler step per dispatch, the same as `Integrator::SymplecticEuler` on the CPU
+// with linear springs, linear damping, wind, gravity, the speed limit and the floor. Each node
+// reads the last positions and velocities and writes the next, so it needs no synchronisation.
+const STEP_SHADER: &str = r#"
+    #version 430
+    layout(local_size_x = 64) in;
+
+    layout(std430) buffer Positions { vec2 positions[]; };
+    layout(std430) buffer Velocities { vec2 velocities[]; };
+    layout(std430) buffer NextPositions { vec2 next_positions[]; };
+    layout(std430) buffer NextVelocities { vec2 next_velocities[]; };
+    layout(std430) buffer Offsets { uint offsets[]; };
+    layout(std430) buffer Links { uint links[]; };
+    layout(std430) buffer RelaxDistances { float relax_distances[]; };
+    layout(std430) buffer Bending { uint bending[]; };
+    layout(std430) buffer Masses { float masses[]; };
+    layout(std430) buffer Fixed { uint fixed[]; };
+
+    uniform uint node_count;
+    uniform float delta_t;
+    uniform vec2 gravity;
+    uniform vec2 wind;
+    uniform float spring_coefficient;
+    uniform float bending_coefficient;
+    uniform float damping_coefficient;
+    uniform float max_velocity;
+    uniform float floor_y;
+    uniform float restitution;
+
+    // Below this distance two nodes are treated as coincident, as on the CPU.
+    const float MIN_SPRING_DISTANCE = 1e-6;
+
+    void main() {
+        uint index = gl_GlobalInvocationID.x;
+        if (index >= node_count) {
+            return;
+        }
+        vec2 position = positions[index];
+        vec2 velocity = velocities[index];
+        if (fixed[index] != 0u) {
+            next_positions[index] = position;
+            next_velocities[index] = velocity;
+            return;
+        }
+
+        vec2 force = vec2(0.0);
+        for (uint link = offsets[index]; link < offsets[index + 1u]; link++) {
+            vec2 displacement = positions[links[link]] - position;
+            float distance = length(displacement);
+            if (distance < MIN_SPRING_DISTANCE) {
+                continue;
+            }
+            float stiffness = bending[link] != 0u ? bending_coefficient : spring_coefficient;
+            force += displacement * (stiffness * (distance - relax_distances[link]) / distance);
+        }
+        force += wind - velocity * damping_coefficient + gravity * masses[index];
+
+        velocity += force / masses[index] * delta_t;
+        float speed = length(velocity);
+        if (speed > max_velocity) {
+            velocity = isinf(speed) || isnan(speed) ? vec2(0.0) : velocity * (max_velocity / speed);
+        }
+        vec2 next = position + velocity * delta_t;
+        if (next.y < floor_y) {
+            next.y = floor_y;
+            if (velocity.y < 0.0) {
+                velocity.y *= -restitution;
+            }
+        }
+        next_positions[index] = next;
+        next_velocities[index] = velocity;
+    }
+"#;
+
+// Every node's structural springs and, while bending is on, its bending springs, flattened so the
+// links of node `i` are `nodes[offsets[i]..offsets[i + 1]]`, each with its relax distance and
+// whether it's a bending spring alongside. Stiffnesses are left to the config, so changing them
+// doesn't mean sending the links again.
+#[derive(Debug, PartialEq)]
+pub struct SpringLinks {
+    pub offsets: Vec<u32>,
+    pub nodes: Vec<u32>,
+    pub relax_distances: Vec<f32>,
+    pub bending: Vec<u32>,
+}
+
+impl SpringLinks {
+    pub fn from_grid(grid: &Grid) -> SpringLinks {
+        let mut links = SpringLinks { offsets: vec![0], nodes: vec![], relax_distances: vec![], bending: vec![] };
+        for index in 0..grid.positions.len() {
+            let structural = grid.neighbours[index].iter().map(|&link| (link, false));
+            let bending = grid.bending_neighbours.get(index).filter(|_| grid.bending).into_iter().flatten();
+            for ((other, relax_distance), bending) in structural.chain(bending.map(|&link| (link, true))) {
+                links.nodes.push(other as u32);
+                links.relax_distances.push(relax_distance);
+                links.bending.push(bending as u32);
+            }
+            links.offsets.push(links.nodes.len() as u32);
+        }
+        links
+    }
+}
+
+// What the grid uses that the GPU step doesn't do, if anything, so it has to stay on the CPU.
+// `external` is whether the random force is on.
+pub fn unsupported(grid: &Grid, external: bool) -> Option<&'static str> {
+    let config = &grid.config;
+    let reasons = [
+        (config.integrator != Integrator::SymplecticEuler, "an integrator other than symplectic Euler"),
+        (config.spring_model != SpringModel::Linear, "a nonlinear spring model"),
+        (config.stiffness_x.is_some() || config.stiffness_y.is_some(), "anisotropic stiffness"),
+        (config.quadratic_drag, "quadratic drag"),
+        (external, "the random force"),
+        (config.tear_threshold.is_finite() || config.heal, "tearing"),
+        (config.self_collision, "self collision"),
+        (config.hold_center_of_mass, "holding the centre of mass"),
+        (config.pressure_coefficient != 0.0, "pressure"),
+        (!grid.obstacles.is_empty(), "obstacles"),
+        (grid.force_field.is_some(), "a force field"),
+        (grid.kinematic.iter().any(Option::is_some), "kinematic anchors"),
+    ];
+    reasons.into_iter().find(|&(applies, _)| applies).map(|(_, reason)| reason)
+}
+
+fn to_gpu(vectors: &[Vec2]) -> Vec<[f32; 2]> {
+    vectors.iter().map(|vector| [vector.x, vector.y]).collect()
+}
+
+fn from_gpu(vectors: &[[f32; 2]]) -> Vec<Vec2> {
+    vectors.iter().map(|&[x, y]| Vec2::new(x, y)).collect()
+}
+
+// Empty buffers can't be created, so an empty `data` gets a single default entry nothing reads.
+fn storage<T: Copy + Default + 'static, F: Facade + ?Sized>(facade: &F, data: &[T]) -> Result<Buffer<[T]>, String> {
+    let data = if data.is_empty() { &[T::default()][..] } else { data };
+    Buffer::new(facade, data, BufferType::ShaderStorageBuffer, BufferMode::Dynamic).map_err(|error| format!("{:?}", error))
+}
+
+// A grid's nodes and springs held in GPU buffers and stepped there. The grid stays the copy the
+// rest of the program works with: `upload` sends it over, and `download` brings the result back.
+pub struct GpuStepper {
+    shader: ComputeShader,
+    // Two sets of positions and velocities, each step reading one and writing the other.
+    positions: [Buffer<[[f32; 2]]>; 2],
+    velocities: [Buffer<[[f32; 2]]>; 2],
+    current: usize,
+    node_count: usize,
+    offsets: Buffer<[u32]>,
+    links: Buffer<[u32]>,
+    relax_distances: Buffer<[f32]>,
+    bending: Buffer<[u32]>,
+    masses: Buffer<[f32]>,
+    fixed: Buffer<[u32]>,
+    // Steps run since the last upload, and their length.
+    steps: u64,
+    delta_t: f32,
+}
+
+impl GpuStepper {
+    // Fails where the context can't run compute shaders, which needs OpenGL 4.3.
+    pub fn new<F: Facade + ?Sized>(facade: &F, grid: &Grid) -> Result<GpuStepper, String> {
+        let shader = ComputeShader::from_source(facade, STEP_SHADER).map_err(|error| error.to_string())?;
+        let links = SpringLinks::from_grid(grid);
+        let positions = to_gpu(&grid.positions);
+        let velocities = to_gpu(&grid.velocities);
+        let fixed: Vec<u32> = grid.fixed.iter().map(|&fixed| fixed as u32).collect();
+        Ok(GpuStepper {
+            shader,
+            positions: [storage(facade, &positions)?, storage(facade, &positions)?],
+            velocities: [storage(facade, &velocities)?, storage(facade, &velocities)?],
+            current: 0,
+            node_count: grid.positions.len(),
+            offsets: storage(facade, &links.offsets)?,
+            links: storage(facade, &links.nodes)?,
+            relax_distances: storage(facade, &links.relax_distances)?,
+            bending: storage(facade, &links.bending)?,
+            masses: storage(facade, &grid.masses)?,
+            fixed: storage(facade, &fixed)?,
+            steps: 0,
+            delta_t: 0.0,
+        })
+    }
+
+    // Sends the grid's current nodes over, which the CPU may have changed since the last download.
+    // Springs are only sent again given `springs`, since they change far less often.
+    pub fn upload<F: Facade + ?Sized>(&mut self, facade: &F, grid: &Grid, springs: bool) -> Result<(), String> {
+        if springs || grid.positions.len() != self.node_count {
+            *self = GpuStepper::new(facade, grid)?;
+            return Ok(());
+        }
+        if self.node_count == 0 {
+            return Ok(());
+        }
+        self.positions[self.current].write(&to_gpu(&grid.positions));
+        self.velocities[self.current].write(&to_gpu(&grid.velocities));
+        self.fixed.write(&grid.fixed.iter().map(|&fixed| fixed as u32).collect::<Vec<u32>>());
+        self.steps = 0;
+        Ok(())
+    }
+
+    pub fn step(&mut self, config: &SimulationConfig, delta_t: f32, gravity: bool) {
+        if self.node_count == 0 {
+            return;
+        }
+        let (read, write) = (self.current, 1 - self.current);
+        let node_count = self.node_count;
+        let gravity = if gravity { config.gravity } else { Vec2::ZERO };
+        let uniforms = uniform! {
+            Positions: &self.positions[read],
+            Velocities: &self.velocities[read],
+            NextPositions: &self.positions[write],
+            NextVelocities: &self.velocities[write],
+            Offsets: &self.offsets,
+            Links: &self.links,
+            RelaxDistances: &self.relax_distances,
+            Bending: &self.bending,
+            Masses: &self.masses,
+            Fixed: &self.fixed,
+            node_count: node_count as u32,
+            delta_t: delta_t,
+            gravity: [gravity.x, gravity.y],
+            wind: [config.wind.x, config.wind.y],
+            spring_coefficient: config.spring_coefficient,
+            bending_coefficient: config.bending_coefficient,
+            damping_coefficient: config.damping_coefficient,
+            max_velocity: config.max_velocity,
+            floor_y: config.floor_y,
+            restitution: config.restitution.clamp(0.0, 1.0),
+        };
+        self.shader.execute(uniforms, node_count.div_ceil(GROUP_SIZE) as u32, 1, 1);
+        self.current = write;
+        self.steps += 1;
+        self.delta_t = delta_t;
+    }
+
+    // Reads the nodes back into the grid, along with where they were before the last step.
+    pub fn download(&mut self, grid: &mut Grid) -> Result<(), String> {
+        if self.steps == 0 {
+            return Ok(());
+        }
+        let read = |buffer: &Buffer<[[f32; 2]]>| buffer.read().map(|vectors| from_gpu(&vectors)).map_err(|error| format!("{:?}", error));
+        let positions = read(&self.positions[self.current])?;
+        let prev_positions = read(&self.positions[1 - self.current])?;
+        let velocities = read(&self.velocities[self.current])?;
+        grid.load_stepped(&positions, &prev_positions, &velocities, self.steps, self.delta_t);
+        self.steps = 0;
+        Ok(())
+    }
+}
+
+// Steps a small grid both on the CPU and with `GpuStepper`, and fails unless every node ends up
+// within tolerance of where the CPU put it.
+pub fn verify<F: Facade + ?Sized>(facade: &F) -> Result<(), String> {
+    let config = SimulationConfig { parallel: false, wind: Vec2::new(0.5, 0.0), ..SimulationConfig::default() };
+    let mut cpu = Grid::with_config(VERIFY_SIZE, VERIFY_SIZE, config);
+    cpu.get_neighbors();
+    cpu.get_bending_neighbors();
+    cpu.bending = true;
+    if let Some(anchors) = cpu.top_corners() {
+        cpu.set_fixed(&anchors);
+    }
+
+    let mut gpu = GpuStepper::new(facade, &cpu)?;
+    let mut stepped = Grid::with_config(VERIFY_SIZE, VERIFY_SIZE, config);
+    for _ in 0..VERIFY_STEPS {
+        cpu.advance(VERIFY_DELTA_T, true, false);
+        gpu.step(&cpu.config, VERIFY_DELTA_T, true);
+    }
+    gpu.download(&mut stepped)?;
+    let worst = cpu.positions.iter().zip(&stepped.positions).map(|(&a, &b)| (a - b).length()).fold(0.0, f32::max);
+    if worst.is_nan() || worst > VERIFY_TOLERANCE {
+        return Err(format!("GPU and CPU steps ended {} apart", worst));
+    }
+    Ok(())
+}
@@ -1,260 +1,2524 @@
 
 //use std::sync::{Arc, RwLock};
-use rand::Rng;
+use std::f32::consts::{PI, TAU};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
 
+use crate::spatial_hash::SpatialHash;
+use crate::vec2::Vec2;
+#[cfg(feature = "snapshot")]
+use serde::{Deserialize, Serialize};
+
+// Times the rest of the enclosing block as a tracing span named `$name` when built with the profile
+// feature, and does nothing otherwise.
+macro_rules! profile_span {
+    ($name:literal) => {
+        #[cfg(feature = "profile")]
+        let _span = tracing::trace_span!($name).entered();
+    };
+}
+
 // Constants outlined in the specification:
 const MASS: f32 = 0.01;
-const GRAVITY: f32 = -9.81;
+const GRAVITY: Vec2 = Vec2::new(0.0, -9.81);
+
+// Height of the centre of a new grid.
+const Y_OFFSET: f32 = 10.0;
 
 const SPRING_RELAX_DISTANCE: f32 = 1.0;
+// Initial distance between adjacent nodes. Larger than the relax distance pre-stretches the cloth.
+const SPACING: f32 = 1.0;
 const SPRING_COEFFICIENT: f32 = 10.0;
+const BENDING_COEFFICIENT: f32 = 1.0;
 const DAMPING_COEFFICIENT: f32 = 0.03;
+const QUADRATIC_DRAG: bool = false;
+const DRAG_COEFFICIENT: f32 = 0.01;
 const EXTERNAL_MAGNITUDE: f32 = 0.2;
+// Springs stretched beyond this multiple of their relax distance snap. Infinite disables tearing.
+const TEAR_THRESHOLD: f32 = f32::INFINITY;
+const HEAL: bool = false;
+// Torn springs whose ends come back within this multiple of their relax distance are relinked.
+const HEAL_THRESHOLD: f32 = 1.0;
+// The heal threshold is kept below this fraction of the tear threshold, so a spring that has just
+// healed isn't already stretched far enough to tear again on the next step.
+const HEAL_HYSTERESIS: f32 = 0.9;
+const FLOOR_Y: f32 = -20.0;
+// Fraction of the speed into a surface kept when bouncing off the floor or an obstacle: 0 sticks,
+// 1 bounces perfectly.
+const RESTITUTION: f32 = 0.0;
+const WIND: Vec2 = Vec2::ZERO;
+const MAX_VELOCITY: f32 = 100.0;
+const SEED: u64 = 0;
+const SUBSTEPS: usize = 1;
+const SELF_COLLISION: bool = false;
+const HOLD_CENTER_OF_MASS: bool = false;
+const COLLISION_RADIUS: f32 = 0.5;
+// Fraction of the overlap between two colliding nodes removed each step.
+const COLLISION_STIFFNESS: f32 = 0.5;
+// Pressure inside a closed loop per unit of area it's short of the target. Off by default.
+const PRESSURE_COEFFICIENT: f32 = 0.0;
+const TARGET_AREA: f32 = 0.0;
+const CONSTRAINT_STIFFNESS: f32 = 1.0;
+const EDGE_SPRINGS: bool = false;
+
+// Weights of each RK4 stage in the final sum, and how far along the step the next stage is evaluated.
+const RK4_WEIGHTS: [f32; 4] = [1.0 / 6.0, 2.0 / 6.0, 2.0 / 6.0, 1.0 / 6.0];
+const RK4_OFFSETS: [f32; 3] = [0.5, 0.5, 1.0];
+
+// Below this distance two nodes are treated as coincident and the spring has no direction.
+const MIN_SPRING_DISTANCE: f32 = 1e-6;
+
+// Picking only looks this far from the cursor before falling back to scanning every node.
+const PICK_RADIUS: f32 = 2.0;
+
+// Furthest apart, in columns and rows, two nodes joined by a custom connectivity can be.
+const CUSTOM_REACH: usize = 2;
+
+// Reach of a ripple, and the outward speed it gives a node at its centre.
+const RIPPLE_RADIUS: f32 = 3.0;
+const RIPPLE_STRENGTH: f32 = 5.0;
+
+// Average strain, as a fraction of the relax distance, drawn fully red.
+const MAX_DISPLAYED_STRAIN: f32 = 0.5;
+// Strain either side of rest, as a fraction of the relax distance, that `strain_histogram` spreads
+// its bins over. Springs beyond it are counted in the end bins.
+pub const STRAIN_HISTOGRAM_RANGE: f32 = 0.5;
+
+// Positions and velocities are rounded to a multiple of this before being hashed, so noise in the
+// last bits of a float doesn't change the digest.
+const HASH_QUANTUM: f32 = 1e-4;
+// 64-bit FNV-1a parameters.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// Mask pixels brighter than this are present nodes.
+#[cfg(feature = "mask")]
+const MASK_THRESHOLD: u8 = 127;
+
+// Line segments used to draw each obstacle's outline, and the colour it's drawn in.
+const OBSTACLE_SEGMENTS: usize = 32;
+const OBSTACLE_COLOR: (f32, f32, f32) = (0.6, 0.6, 0.6);
+// Colour of the velocity lines, fading from the first towards the second at the moving end.
+const VELOCITY_COLORS: [(f32, f32, f32); 2] = [(1.0, 1.0, 0.0), (1.0, 0.5, 0.0)];
+// Colour pinned nodes are drawn in when they're highlighted, whatever their strain.
+const FIXED_COLOR: (f32, f32, f32) = (1.0, 1.0, 0.0);
+// Normal of everything drawn flat in the plane, facing the viewer.
+const FLAT_NORMAL: (f32, f32, f32) = (0.0, 0.0, 1.0);
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Integrator {
+    #[default]
+    SymplecticEuler,
+    Verlet,
+    Rk4,
+    // Position based dynamics: springs are treated as distance constraints and corrected directly
+    // over the given number of passes, which stays stable however stiff the cloth is.
+    PositionBased { iterations: usize },
+}
+
+// How a structural spring's tension grows with its strain, (distance - relax distance) / relax
+// distance. Bending springs always stay linear, and position based steps, which hold springs at
+// their relax distances directly, don't use it.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum SpringModel {
+    // Tension proportional to extension, whichever way.
+    #[default]
+    Linear,
+    // Linear up to a strain of `threshold`, then `stiffening` times stiffer beyond it, like fabric
+    // whose fibres straighten out and take the load. Compression stays soft.
+    Biphasic { threshold: f32, stiffening: f32 },
+    // Linear plus a cubic term, so the spring stiffens smoothly by a factor of 1 + 3 *
+    // `coefficient` * strain^2 either way.
+    Cubic { coefficient: f32 },
+}
+
+impl SpringModel {
+    // Tension in a spring of stiffness `coefficient` stretched `extension` past its relax
+    // distance, negative when it's compressed.
+    pub fn force(self, coefficient: f32, extension: f32, relax_distance: f32) -> f32 {
+        match self {
+            SpringModel::Linear => coefficient * extension,
+            SpringModel::Biphasic { threshold, stiffening } => {
+                let knee = threshold * relax_distance;
+                if extension <= knee {
+                    coefficient * extension
+                } else {
+                    coefficient * (knee + stiffening * (extension - knee))
+                }
+            }
+            SpringModel::Cubic { coefficient: cubic } => {
+                let strain = extension / relax_distance;
+                coefficient * extension * (1.0 + cubic * strain * strain)
+            }
+        }
+    }
+
+    // Energy stored in the same spring: the integral of `force` from rest.
+    pub fn energy(self, coefficient: f32, extension: f32, relax_distance: f32) -> f32 {
+        match self {
+            SpringModel::Linear => 0.5 * coefficient * extension.powf(2.0),
+            SpringModel::Biphasic { threshold, stiffening } => {
+                let knee = threshold * relax_distance;
+                if extension <= knee {
+                    0.5 * coefficient * extension.powf(2.0)
+                } else {
+                    let beyond = extension - knee;
+                    coefficient * (0.5 * knee * knee + knee * beyond + 0.5 * stiffening * beyond * beyond)
+                }
+            }
+            SpringModel::Cubic { coefficient: cubic } => {
+                let strain = extension / relax_distance;
+                coefficient * extension * extension * (0.5 + 0.25 * cubic * strain * strain)
+            }
+        }
+    }
+}
+
+// How the per-node force passes are split across the rayon pool.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Partitioning {
+    // Leave the split to rayon.
+    #[default]
+    Flat,
+    // Hand out whole columns, `per_task` at a time. Nodes are stored column by column, so most
+    // springs then stay inside the block a task is working on.
+    Columns { per_task: usize },
+}
+
+// Read from scenario files with every field optional, falling back to the defaults below.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "config", derive(serde::Deserialize), serde(default, deny_unknown_fields))]
+pub struct SimulationConfig {
+    pub mass: f32,
+    // Acceleration applied to every node while gravity is switched on.
+    pub gravity: Vec2,
+    pub spring_coefficient: f32,
+    // Stiffness of the structural springs along a row and along a column, like the warp and weft
+    // of woven cloth. Either left unset takes spring_coefficient, which diagonal springs always do.
+    pub stiffness_x: Option<f32>,
+    pub stiffness_y: Option<f32>,
+    pub spring_model: SpringModel,
+    pub bending_coefficient: f32,
+    pub damping_coefficient: f32,
+    // Air resistance proportional to speed squared, applied on top of the linear damping. It
+    // slows fast motion harder and slow motion more gently than the linear damper does.
+    pub quadratic_drag: bool,
+    pub drag_coefficient: f32,
+    pub spring_relax_distance: f32,
+    pub spacing: f32,
+    pub external_magnitude: f32,
+    pub tear_threshold: f32,
+    // Relinks torn springs once their ends drift back within `heal_threshold` of their relax distance.
+    pub heal: bool,
+    pub heal_threshold: f32,
+    pub floor_y: f32,
+    // Clamped to 0..=1 where it's applied, since anything above 1 would add energy on every bounce.
+    pub restitution: f32,
+    pub wind: Vec2,
+    // Speed limit applied after every step so unstable settings degrade instead of blowing up.
+    pub max_velocity: f32,
+    // Seeds the random external force, so equal seeds reproduce equal trajectories.
+    pub seed: u64,
+    // Cancels the net drift of the free nodes every step, keeping the centre of mass where it was.
+    pub hold_center_of_mass: bool,
+    pub self_collision: bool,
+    pub collision_radius: f32,
+    pub collision_stiffness: f32,
+    // Gas pressure inside a wrapped grid's innermost ring: pressure_coefficient * (target_area -
+    // enclosed area), pushing the ring out while it's too small and in while it's too big. Off
+    // while the coefficient is 0.
+    pub pressure_coefficient: f32,
+    pub target_area: f32,
+    pub integrator: Integrator,
+    // Fraction of a spring's error each position based pass corrects, clamped to 0..=1 where it's
+    // applied. At 1 the springs are held rigidly at their relax distances whatever their
+    // coefficients; below it the cloth stretches, and row and column springs are softened further
+    // by their stiffness relative to the stiffest one. The other integrators ignore it.
+    pub constraint_stiffness: f32,
+    // Force calculations each `advance` is split into, each covering an equal share of its time.
+    // More substeps keep stiffer springs and larger frame times stable, at a proportional cost in
+    // force calculations per frame.
+    pub substeps: usize,
+    // Runs the force and integration passes on the rayon pool. When false they use plain iterators
+    // in index order, which is slower but makes runs easy to compare while debugging.
+    pub parallel: bool,
+    pub partitioning: Partitioning,
+    // Works out each spring's force once and applies it to both ends, instead of once from each
+    // end, halving the spring work. Sums come out in a different order, so trajectories differ
+    // from the per-node pass in the last bits, and in parallel they depend on the thread count.
+    pub edge_springs: bool,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> SimulationConfig {
+        SimulationConfig {
+            mass: MASS,
+            gravity: GRAVITY,
+            spring_coefficient: SPRING_COEFFICIENT,
+            stiffness_x: None,
+            stiffness_y: None,
+            spring_model: SpringModel::default(),
+            bending_coefficient: BENDING_COEFFICIENT,
+            damping_coefficient: DAMPING_COEFFICIENT,
+            quadratic_drag: QUADRATIC_DRAG,
+            drag_coefficient: DRAG_COEFFICIENT,
+            spring_relax_distance: SPRING_RELAX_DISTANCE,
+            spacing: SPACING,
+            external_magnitude: EXTERNAL_MAGNITUDE,
+            tear_threshold: TEAR_THRESHOLD,
+            heal: HEAL,
+            heal_threshold: HEAL_THRESHOLD,
+            floor_y: FLOOR_Y,
+            restitution: RESTITUTION,
+            wind: WIND,
+            max_velocity: MAX_VELOCITY,
+            seed: SEED,
+            hold_center_of_mass: HOLD_CENTER_OF_MASS,
+            self_collision: SELF_COLLISION,
+            collision_radius: COLLISION_RADIUS,
+            collision_stiffness: COLLISION_STIFFNESS,
+            pressure_coefficient: PRESSURE_COEFFICIENT,
+            target_area: TARGET_AREA,
+            integrator: Integrator::default(),
+            constraint_stiffness: CONSTRAINT_STIFFNESS,
+            substeps: SUBSTEPS,
+            parallel: true,
+            partitioning: Partitioning::default(),
+            edge_springs: EDGE_SPRINGS,
+        }
+    }
+}
+
+// How a ripple's impulse fades from its centre to the edge of its radius.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Falloff {
+    // Full strength all the way to the edge.
+    Constant,
+    Linear,
+    // Smoothstep, so the impulse eases off towards both the centre and the edge.
+    #[default]
+    Smooth,
+}
+
+impl Falloff {
+    // Weight of the impulse at `t`, the distance from the centre as a fraction of the radius.
+    pub fn weight(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - t,
+            Falloff::Smooth => 1.0 - t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// An outward push on every node near a point.
+#[derive(Copy, Clone, Debug)]
+pub struct Ripple {
+    pub radius: f32,
+    // Speed added to a node at the centre, before the falloff.
+    pub strength: f32,
+    pub falloff: Falloff,
+}
+
+impl Default for Ripple {
+    fn default() -> Ripple {
+        Ripple {
+            radius: RIPPLE_RADIUS,
+            strength: RIPPLE_STRENGTH,
+            falloff: Falloff::default(),
+        }
+    }
+}
+
+// What the colour of the cloth shows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum StrainDisplay {
+    // How far springs are from their relax distance either way, blue to red.
+    #[default]
+    Magnitude,
+    // Tension red, rest green and compression blue. Lines take their own spring's strain, while
+    // points and cells average the springs around each node.
+    Signed,
+}
+
+// Which nodes a fresh grid is hung from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum PinPreset {
+    // The ends of the top row.
+    #[default]
+    TopCorners,
+    // The whole top row, like a curtain.
+    TopEdge,
+    // The ends of the top and bottom rows, holding the sheet out flat.
+    FourCorners,
+    // Nothing, so the grid falls freely.
+    Free,
+    // The middle node, so the sheet drapes from it.
+    Center,
+}
+
+// Offset from a node's origin at a given simulation time.
+pub type KinematicPath = Box<dyn Fn(f32) -> Vec2 + Send + Sync>;
+
+// Force on a node at a given position and simulation time, added to every free node each step.
+pub type ForceField = Box<dyn Fn(Vec2, f32) -> Vec2 + Send + Sync>;
+
+// A pinned node driven along a prescribed path instead of staying put.
+pub struct Kinematic {
+    pub origin: Vec2,
+    pub path: KinematicPath,
+}
+
+// The forces on one node, kept apart by source.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ForceBreakdown {
+    // Structural and bending springs together.
+    pub spring: Vec2,
+    // The pressure of a closed loop, zero while it's off.
+    pub pressure: Vec2,
+    pub damping: Vec2,
+    // Quadratic drag, zero while it's switched off.
+    pub drag: Vec2,
+    pub wind: Vec2,
+    pub gravity: Vec2,
+    // The seeded random force.
+    pub external: Vec2,
+    // The grid's force field, zero without one.
+    pub field: Vec2,
+}
+
+impl ForceBreakdown {
+    // Everything but the springs and the pressure, which depend on other nodes too, added in the
+    // order the step adds it.
+    pub fn non_spring(&self) -> Vec2 {
+        self.wind + self.damping + self.drag + self.gravity + self.external + self.field
+    }
+
+    pub fn total(&self) -> Vec2 {
+        self.spring + self.pressure + self.non_spring()
+    }
+}
+
+// Which nodes the structural springs join.
+#[derive(Copy, Clone, Debug, Default)]
+pub enum Connectivity {
+    // Each node to the nodes beside, above and below it.
+    #[default]
+    Four,
+    // Adds the diagonals too, which stop the cloth from shearing freely.
+    Eight,
+    // Joins the nodes up to CUSTOM_REACH columns and rows apart that the function accepts, given
+    // their (x, y) coordinates. A pair accepted either way round is joined, so springs stay mutual.
+    Custom(fn((usize, usize), (usize, usize)) -> bool),
+}
+
+// A solid circle nodes can't enter.
+#[derive(Copy, Clone, Debug)]
+pub struct Sphere {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+// A solid shape nodes can't enter.
+#[derive(Copy, Clone, Debug)]
+pub enum Obstacle {
+    Sphere(Sphere),
+    // An axis-aligned box between its lower left and upper right corners.
+    Box { min: Vec2, max: Vec2 },
+}
 
+impl Obstacle {
+    // The point on the surface a node at `position` inside the obstacle is pushed out to, and the
+    // outward normal there, or None while the node is outside.
+    pub fn push_out(&self, position: Vec2) -> Option<(Vec2, Vec2)> {
+        match *self {
+            Obstacle::Sphere(Sphere { center, radius }) => {
+                let offset = position - center;
+                let distance = offset.length();
+                if distance >= radius {
+                    return None;
+                }
+                // A node exactly at the centre has no outward direction, so push it straight up.
+                let normal = if distance < MIN_SPRING_DISTANCE { Vec2::new(0.0, 1.0) } else { offset / distance };
+                Some((center + normal * radius, normal))
+            }
+            // Out through the nearest face, so a node near a corner leaves along the axis it's
+            // least far in. Ties go to the first of left, right, bottom and top.
+            Obstacle::Box { min, max } => {
+                if !(position.x > min.x && position.x < max.x && position.y > min.y && position.y < max.y) {
+                    return None;
+                }
+                let faces = [
+                    (position.x - min.x, Vec2::new(-1.0, 0.0), Vec2::new(min.x, position.y)),
+                    (max.x - position.x, Vec2::new(1.0, 0.0), Vec2::new(max.x, position.y)),
+                    (position.y - min.y, Vec2::new(0.0, -1.0), Vec2::new(position.x, min.y)),
+                    (max.y - position.y, Vec2::new(0.0, 1.0), Vec2::new(position.x, max.y)),
+                ];
+                let (_, normal, surface) = faces.into_iter().reduce(|nearest, face| if face.0 < nearest.0 { face } else { nearest })?;
+                Some((surface, normal))
+            }
+        }
+    }
+}
 
 #[derive(Copy, Clone)]
 pub struct Vertex {
     pub position: (f32, f32),
+    pub color: (f32, f32, f32),
+    // Unit normal the filled mesh is lit by. Lines and points keep FLAT_NORMAL.
+    pub normal: (f32, f32, f32),
+}
+
+implement_vertex!(Vertex, position, color, normal);
+
+// The state needed to recreate a grid; springs are rebuilt with `get_neighbors` after loading.
+#[cfg_attr(feature = "snapshot", derive(Serialize, Deserialize))]
+pub struct GridSnapshot {
+    pub width: usize,
+    pub height: usize,
+    pub positions: Vec<Vec2>,
+    pub velocities: Vec<Vec2>,
+    pub fixed: Vec<bool>,
+}
+
+impl GridSnapshot {
+    pub fn is_consistent(&self) -> bool {
+        let size = self.width * self.height;
+        self.positions.len() == size && self.velocities.len() == size && self.fixed.len() == size
+    }
+}
+
+// What the simulation thread needs from a grid, so the 2D and 3D grids can share it.
+pub trait Simulation {
+    // Moves the simulation forward by `delta_t`, split into the configured number of substeps.
+    fn advance(&mut self, delta_t: f32, gravity: bool, externalbool: bool);
+    fn dimensions(&self) -> (usize, usize);
+    // The first node whose position or velocity isn't finite, if any, counting across everything
+    // that's simulated.
+    fn has_nonfinite(&self) -> Option<usize>;
+}
+
+// Snapshot restored by `Grid::reset`.
+struct InitialState {
+    positions: Vec<Vec2>,
+    // Kept alongside the velocities, since Verlet carries them in the previous positions.
+    prev_positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    fixed: Vec<bool>,
+    neighbours: Vec<Vec<(usize, f32)>>,
 }
 
 pub struct Grid {
     pub width: usize,
     pub height: usize,
-    pub positions: Vec<(f32, f32)>,
-    pub velocities: Vec<(f32, f32)>,
+    pub positions: Vec<Vec2>,
+    pub velocities: Vec<Vec2>,
+    // Positions before the last step, needed by the Verlet integrator.
+    pub prev_positions: Vec<Vec2>,
+    // Scratch buffers written by each step and swapped in, to avoid reallocating.
+    positions_back: Vec<Vec2>,
+    velocities_back: Vec<Vec2>,
+    accelerations: Vec<Vec2>,
+    stage_positions: Vec<Vec2>,
+    stage_velocities: Vec<Vec2>,
+    collision_offsets: Vec<Vec2>,
+    // Every structural and bending spring once, as (a, b, relax distance) with a < b, rebuilt from
+    // the neighbour lists at the start of each step that uses `config.edge_springs`.
+    edges: Vec<(usize, usize, f32)>,
+    bending_edges: Vec<(usize, usize, f32)>,
     pub fixed: Vec<bool>,
-    pub neighbours: Vec<Vec<usize>>,
+    // Nodes cut out of the sheet by a mask. Absent nodes are kept pinned and never get springs.
+    pub present: Vec<bool>,
+    pub masses: Vec<f32>,
+    // Paths some pinned nodes follow. A node only follows its path while it's pinned, so a pinned
+    // node without one is the special case of a path that never moves.
+    pub kinematic: Vec<Option<Kinematic>>,
+    // Each neighbour is stored with the relax distance of the spring linking it.
+    pub neighbours: Vec<Vec<(usize, f32)>>,
+    // Weaker springs to the nodes two steps away along each axis, resisting sharp folds.
+    pub bending_neighbours: Vec<Vec<(usize, f32)>>,
+    // Springs that have torn since the springs were last built, with their relax distances, so
+    // they can heal. Each is listed once.
+    pub torn_springs: Vec<(usize, usize, f32)>,
+    pub connectivity: Connectivity,
+    // Links the last column back to the first, closing the grid into a loop.
+    pub wrap_x: bool,
+    pub bending: bool,
+    pub strain_display: StrainDisplay,
+    // Draws pinned nodes, and the ends of lines and cells at them, in their own colour.
+    pub highlight_fixed: bool,
+    // How far from `prev_positions` to `positions` the nodes are drawn, so the renderer can show
+    // the state part way through a step instead of jumping from step to step. 1 draws them where
+    // they are.
+    pub render_alpha: f32,
+    pub config: SimulationConfig,
+    pub obstacles: Vec<Obstacle>,
+    // Set with `apply_external_force_field`, and kept across resets like the obstacles.
+    pub force_field: Option<ForceField>,
+    // Force calculations since the last reset, and the simulated time they covered. They're the one
+    // clock kinematic paths, force fields and recordings read, and are kept however the steps are
+    // split up or sped up.
+    pub step_count: u64,
+    pub time: f32,
+    // Rebuilt from the positions after each step's integration.
+    spatial_hash: SpatialHash,
+    initial: InitialState,
 }
 
 impl Grid {
     pub fn new(width: usize, height: usize) -> Grid {
+        Grid::with_config(width, height, SimulationConfig::default())
+    }
+
+    pub fn with_config(width: usize, height: usize, config: SimulationConfig) -> Grid {
         let size = width * height;
         let mut positions = Vec::with_capacity(size);
         let mut velocities = Vec::with_capacity(size);
         let mut fixed = Vec::with_capacity(size);
-        let y_offset = 10.0;
         for x in 0..width {
             for y in 0..height {
-                positions.push(
-                               ((width  / 2) as f32 * -1.0 + x as f32, 
-                                y_offset + (height / 2) as f32 * -1.0 + y as f32)
-                              );
-                velocities.push((0f32, 0f32));
+                positions.push(Vec2::new(
+                    (x as f32 - (width / 2) as f32) * config.spacing,
+                    Y_OFFSET + (y as f32 - (height / 2) as f32) * config.spacing,
+                ));
+                velocities.push(Vec2::ZERO);
                 fixed.push(false);
             }
         }
 
-        Grid {
+        let mut grid = Grid {
             width,
             height,
+            prev_positions: positions.clone(),
+            positions_back: positions.clone(),
+            velocities_back: velocities.clone(),
+            accelerations: vec![Vec2::ZERO; size],
+            stage_positions: positions.clone(),
+            stage_velocities: velocities.clone(),
+            collision_offsets: vec![Vec2::ZERO; size],
+            edges: vec![],
+            bending_edges: vec![],
+            initial: InitialState {
+                positions: positions.clone(),
+                prev_positions: positions.clone(),
+                velocities: velocities.clone(),
+                fixed: fixed.clone(),
+                neighbours: vec![vec![]; size],
+            },
             positions,
             velocities,
             fixed,
+            present: vec![true; size],
+            masses: vec![config.mass; size],
+            kinematic: (0..size).map(|_| None).collect(),
             neighbours: vec![vec![]; size],
+            bending_neighbours: vec![vec![]; size],
+            torn_springs: vec![],
+            connectivity: Connectivity::default(),
+            wrap_x: false,
+            bending: false,
+            strain_display: StrainDisplay::default(),
+            highlight_fixed: false,
+            render_alpha: 1.0,
+            config,
+            obstacles: vec![],
+            force_field: None,
+            step_count: 0,
+            time: 0.0,
+            spatial_hash: SpatialHash::new(config.collision_radius),
+        };
+        grid.spatial_hash.rebuild(&grid.positions);
+        grid
+    }
+
+    // A grid with only the nodes marked in `present`, which is indexed like the grid itself.
+    pub fn with_presence(width: usize, height: usize, present: Vec<bool>) -> Grid {
+        assert_eq!(present.len(), width * height, "presence doesn't match the grid dimensions");
+        let mut grid = Grid::new(width, height);
+        grid.fixed = present.iter().map(|&present| !present).collect();
+        grid.present = present;
+        grid
+    }
+
+    // A single free node starting at `position` with `velocity` and pushed by nothing but the
+    // constant `force`, so its motion has a closed form to check the integrators against. Damping,
+    // drag, the floor and the speed limit are all off, and it should be advanced with gravity and
+    // the random force off too. Verlet carries the starting velocity in the previous position, so
+    // it's set for steps of `delta_t`.
+    pub fn single_node(position: Vec2, velocity: Vec2, force: Vec2, delta_t: f32) -> Grid {
+        let config = SimulationConfig {
+            wind: force,
+            damping_coefficient: 0.0,
+            quadratic_drag: false,
+            floor_y: f32::NEG_INFINITY,
+            max_velocity: f32::INFINITY,
+            substeps: 1,
+            ..SimulationConfig::default()
+        };
+        let mut grid = Grid::with_config(1, 1, config);
+        grid.positions[0] = position;
+        grid.prev_positions[0] = position;
+        grid.initial.positions[0] = position;
+        grid.apply_impulse(velocity, delta_t);
+        grid.initial.prev_positions[0] = grid.prev_positions[0];
+        grid.initial.velocities[0] = velocity;
+        grid.spatial_hash.rebuild(&grid.positions);
+        grid
+    }
+
+    // Builds a grid shaped like the white pixels of an image, one node per pixel. The top row of
+    // the image becomes the top row of the grid.
+    #[cfg(feature = "mask")]
+    pub fn from_mask<P: AsRef<Path>>(path: P) -> image::ImageResult<Grid> {
+        let mask = image::open(path)?.into_luma8();
+        let (width, height) = (mask.width() as usize, mask.height() as usize);
+        let mut present = vec![false; width * height];
+        for (x, row, pixel) in mask.enumerate_pixels() {
+            let y = height - 1 - row as usize;
+            present[x as usize * height + y] = pixel.0[0] > MASK_THRESHOLD;
+        }
+        Ok(Grid::with_presence(width, height, present))
+    }
+
+    pub fn to_snapshot(&self) -> GridSnapshot {
+        GridSnapshot {
+            width: self.width,
+            height: self.height,
+            positions: self.positions.clone(),
+            velocities: self.velocities.clone(),
+            fixed: self.fixed.clone(),
+        }
+    }
+
+    // A grid simulated with `config` in the state `snapshot` recorded, which `reset` returns to.
+    // Fails when the snapshot's buffers don't match its dimensions.
+    pub fn from_snapshot(snapshot: GridSnapshot, config: SimulationConfig) -> io::Result<Grid> {
+        if !snapshot.is_consistent() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "snapshot buffers don't match its dimensions"));
+        }
+        let mut grid = Grid::with_config(snapshot.width, snapshot.height, config);
+        grid.prev_positions.clone_from(&snapshot.positions);
+        grid.positions = snapshot.positions;
+        grid.velocities = snapshot.velocities;
+        grid.fixed = snapshot.fixed;
+        grid.spatial_hash.rebuild(&grid.positions);
+        grid.save_initial_state();
+        Ok(grid)
+    }
+
+    // Records the current state, including anchors and springs, as the one `reset` returns to.
+    pub fn save_initial_state(&mut self) {
+        self.initial = InitialState {
+            positions: self.positions.clone(),
+            prev_positions: self.prev_positions.clone(),
+            velocities: self.velocities.clone(),
+            fixed: self.fixed.clone(),
+            neighbours: self.neighbours.clone(),
+        };
+    }
+
+    pub fn reset(&mut self) {
+        self.positions.clone_from(&self.initial.positions);
+        self.prev_positions.clone_from(&self.initial.prev_positions);
+        self.velocities.clone_from(&self.initial.velocities);
+        self.fixed.clone_from(&self.initial.fixed);
+        self.neighbours.clone_from(&self.initial.neighbours);
+        self.torn_springs.clear();
+        self.step_count = 0;
+        self.time = 0.0;
+        self.move_kinematic(None);
+        self.spatial_hash.rebuild(&self.positions);
+    }
+
+    // Settles the springs before the simulation starts, so a pre-stretched or irregular mesh
+    // doesn't snap when it's released. Each iteration moves the free nodes as far as one
+    // symplectic Euler step from rest under their spring forces alone would, so the mesh slides
+    // downhill in spring energy instead of oscillating about its rest shape. Nothing else a step
+    // does runs: no other force, no kinematic anchors, floor, obstacles or tearing, and the clock
+    // doesn't move.
+    pub fn relax(&mut self, iterations: usize) {
+        let Some(delta_t) = self.relax_time_step() else {
+            return;
+        };
+        let mut displacements = std::mem::take(&mut self.accelerations);
+        for _ in 0..iterations {
+            let positions = &self.positions;
+            self.for_each_index(self.config.parallel, &mut displacements, |index, displacement| {
+                *displacement = if self.fixed[index] {
+                    Vec2::ZERO
+                } else {
+                    self.spring_force(positions, index, None) / self.masses[index] * delta_t * delta_t
+                };
+            });
+            for (position, &displacement) in self.positions.iter_mut().zip(&displacements) {
+                *position += displacement;
+            }
+        }
+        self.accelerations = displacements;
+        self.velocities.fill(Vec2::ZERO);
+        self.prev_positions.copy_from_slice(&self.positions);
+        self.spatial_hash.rebuild(&self.positions);
+    }
+
+    // A node's springs can stiffen it by at most twice the sum of their coefficients, so stepping
+    // at this length moves each node less than the distance to its spring energy minimum and the
+    // energy falls on every relaxation step. None when there's no spring to relax.
+    fn relax_time_step(&self) -> Option<f32> {
+        let bending_coefficient = if self.bending { self.config.bending_coefficient } else { 0.0 };
+        let stiffness = self
+            .neighbours
+            .iter()
+            .zip(&self.bending_neighbours)
+            .map(|(links, bending_links)| {
+                links.len() as f32 * self.max_stiffness() + bending_links.len() as f32 * bending_coefficient
+            })
+            .fold(0.0, f32::max);
+        let mass = self.masses.iter().copied().fold(f32::INFINITY, f32::min);
+        if stiffness <= 0.0 || !(mass > 0.0 && mass.is_finite()) {
+            return None;
+        }
+        Some((mass / (2.0 * stiffness)).sqrt())
+    }
+
+    // Moves every node, including where `reset` returns them to, by `offset`.
+    pub fn translate(&mut self, offset: Vec2) {
+        let initial = self.initial.positions.iter_mut().chain(&mut self.initial.prev_positions);
+        for position in self.positions.iter_mut().chain(&mut self.prev_positions).chain(initial) {
+            *position += offset;
+        }
+        for kinematic in self.kinematic.iter_mut().flatten() {
+            kinematic.origin += offset;
+        }
+        self.spatial_hash.rebuild(&self.positions);
+    }
+
+    // Takes on the state `steps` steps of `delta_t` run somewhere else, such as on the GPU, ended
+    // in: nodes at `positions` moving at `velocities`, having come from `prev_positions`.
+    pub fn load_stepped(&mut self, positions: &[Vec2], prev_positions: &[Vec2], velocities: &[Vec2], steps: u64, delta_t: f32) {
+        self.positions.copy_from_slice(positions);
+        self.prev_positions.copy_from_slice(prev_positions);
+        self.velocities.copy_from_slice(velocities);
+        self.step_count += steps;
+        self.time += steps as f32 * delta_t;
+        self.spatial_hash.rebuild(&self.positions);
+    }
+
+    // Rotates every node anticlockwise by `angle` radians about `center` and scales its distance
+    // from it by `scale`, pinned nodes included, along with where `reset` returns them to.
+    // Velocities turn and scale with the grid, but relax distances stay, so scaling up stretches
+    // the springs.
+    pub fn transform(&mut self, scale: f32, angle: f32, center: Vec2) {
+        let (sin, cos) = angle.sin_cos();
+        let turn = |offset: Vec2| Vec2::new(offset.x * cos - offset.y * sin, offset.x * sin + offset.y * cos) * scale;
+        let initial = self.initial.positions.iter_mut().chain(&mut self.initial.prev_positions);
+        for position in self.positions.iter_mut().chain(&mut self.prev_positions).chain(initial) {
+            *position = center + turn(*position - center);
+        }
+        for velocity in self.velocities.iter_mut().chain(&mut self.initial.velocities) {
+            *velocity = turn(*velocity);
         }
+        for kinematic in self.kinematic.iter_mut().flatten() {
+            kinematic.origin = center + turn(kinematic.origin - center);
+        }
+        self.spatial_hash.rebuild(&self.positions);
+    }
+
+    // Grows the grid by a row under the current bottom one, continuing the spacing and velocity of
+    // the two rows above it. Springs are rebuilt from scratch, so any torn ones come back.
+    pub fn add_bottom_row(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let spacing = self.config.spacing;
+        let drop = Vec2::new(0.0, self.config.spring_relax_distance);
+        let extend = |values: &[Vec2], x: usize| match height {
+            0 => Vec2::new((x as f32 - (width / 2) as f32) * spacing, Y_OFFSET),
+            1 => values[x] - drop,
+            _ => values[x * height] * 2.0 - values[x * height + 1],
+        };
+        let positions: Vec<Vec2> = (0..width).map(|x| extend(&self.positions, x)).collect();
+        let prev_positions: Vec<Vec2> = (0..width).map(|x| extend(&self.prev_positions, x)).collect();
+        let initial_positions: Vec<Vec2> = (0..width).map(|x| extend(&self.initial.positions, x)).collect();
+        let velocities: Vec<Vec2> = (0..width)
+            .map(|x| if height == 0 { Vec2::ZERO } else { self.velocities[x * height] })
+            .collect();
+
+        insert_row(&mut self.positions, width, height, |x| positions[x]);
+        insert_row(&mut self.prev_positions, width, height, |x| prev_positions[x]);
+        insert_row(&mut self.velocities, width, height, |x| velocities[x]);
+        insert_row(&mut self.fixed, width, height, |_| false);
+        insert_row(&mut self.present, width, height, |_| true);
+        insert_row(&mut self.masses, width, height, |_| self.config.mass);
+        insert_row(&mut self.kinematic, width, height, |_| None);
+        insert_row(&mut self.initial.positions, width, height, |x| initial_positions[x]);
+        insert_row(&mut self.initial.prev_positions, width, height, |x| initial_positions[x]);
+        insert_row(&mut self.initial.velocities, width, height, |_| Vec2::ZERO);
+        insert_row(&mut self.initial.fixed, width, height, |_| false);
+        self.height += 1;
+        self.resized();
+    }
+
+    // Shrinks the grid by its bottom row. Returns false, leaving the grid alone, when only one row
+    // is left.
+    pub fn remove_bottom_row(&mut self) -> bool {
+        if self.height <= 1 {
+            return false;
+        }
+        let (width, height) = (self.width, self.height);
+        remove_row(&mut self.positions, width, height);
+        remove_row(&mut self.prev_positions, width, height);
+        remove_row(&mut self.velocities, width, height);
+        remove_row(&mut self.fixed, width, height);
+        remove_row(&mut self.present, width, height);
+        remove_row(&mut self.masses, width, height);
+        remove_row(&mut self.kinematic, width, height);
+        remove_row(&mut self.initial.positions, width, height);
+        remove_row(&mut self.initial.prev_positions, width, height);
+        remove_row(&mut self.initial.velocities, width, height);
+        remove_row(&mut self.initial.fixed, width, height);
+        self.height -= 1;
+        self.resized();
+        true
+    }
+
+    // Brings the scratch buffers and springs back in line with a new node count.
+    fn resized(&mut self) {
+        let size = self.width * self.height;
+        self.positions_back = self.positions.clone();
+        self.velocities_back = self.velocities.clone();
+        self.accelerations = vec![Vec2::ZERO; size];
+        self.stage_positions = self.positions.clone();
+        self.stage_velocities = self.velocities.clone();
+        self.collision_offsets = vec![Vec2::ZERO; size];
+        self.get_neighbors();
+        self.get_bending_neighbors();
+        self.spatial_hash.rebuild(&self.positions);
+    }
+
+    // Replaces the config, along with the per-node masses it sets. Positions are left alone, so a
+    // new spacing only shows once springs pull the nodes to it.
+    pub fn apply_config(&mut self, config: SimulationConfig) {
+        self.masses.fill(config.mass);
+        self.spatial_hash = SpatialHash::new(config.collision_radius);
+        self.spatial_hash.rebuild(&self.positions);
+        self.config = config;
+    }
+
+    // A horizontal chain of `length` nodes.
+    pub fn rope(length: usize) -> Grid {
+        Grid::rope_with_config(length, SimulationConfig::default())
+    }
+
+    pub fn rope_with_config(length: usize, config: SimulationConfig) -> Grid {
+        Grid::with_config(length, 1, config)
     }
 
+    // A closed loop `circumference` nodes around and `layers` nodes thick, starting at rest.
+    pub fn tube(circumference: usize, layers: usize) -> Grid {
+        Grid::tube_with_config(circumference, layers, SimulationConfig::default())
+    }
+
+    pub fn tube_with_config(circumference: usize, layers: usize, config: SimulationConfig) -> Grid {
+        let mut grid = Grid::with_config(circumference, layers, config);
+        grid.wrap_x = true;
+        let scale = grid.config.spacing / grid.config.spring_relax_distance;
+        let centre = Vec2::new(0.0, Y_OFFSET);
+        for x in 0..circumference {
+            for y in 0..layers {
+                let index = grid.get_index(x, y);
+                grid.positions[index] = centre + grid.rest_position(x, y) * scale;
+            }
+        }
+        grid.prev_positions.clone_from(&grid.positions);
+        grid.initial.positions.clone_from(&grid.positions);
+        grid.initial.prev_positions.clone_from(&grid.positions);
+        grid.spatial_hash.rebuild(&grid.positions);
+        grid
+    }
+
+    pub fn new_with_shear(width: usize, height: usize) -> Grid {
+        let mut grid = Grid::new(width, height);
+        grid.connectivity = Connectivity::Eight;
+        grid
+    }
+
+    // One line per spring. Every spring is listed on both of its nodes, so it's drawn from the lower
+    // index only; torn springs are no longer listed and leave a gap.
     pub fn create_grid(&self) -> Vec<Vertex> {
+        let colors = self.node_colors();
         let mut lines = vec![];
-        for x in 0..(self.width - 1) {
-            for y in 0..(self.height - 1) {
-                lines.push(Vertex {
-                    position: self.positions[self.get_index(x, y)],
-                });
-                lines.push(Vertex {
-                    position: self.positions[self.get_index(x, y + 1)],
-                });
-                lines.push(Vertex {
-                    position: self.positions[self.get_index(x, y + 1)],
-                });
-                lines.push(Vertex {
-                    position: self.positions[self.get_index(x + 1, y + 1)],
-                });
-                lines.push(Vertex {
-                    position: self.positions[self.get_index(x + 1, y + 1)],
-                });
-                lines.push(Vertex {
-                    position: self.positions[self.get_index(x + 1, y)],
-                });
-                lines.push(Vertex {
-                    position: self.positions[self.get_index(x + 1, y)],
-                });
-                lines.push(Vertex {
-                    position: self.positions[self.get_index(x, y)],
-                });
+        for (index, neighbours) in self.neighbours.iter().enumerate() {
+            for &(neighbor_index, relax_distance) in neighbours {
+                if neighbor_index < index {
+                    continue;
+                }
+                match self.strain_display {
+                    StrainDisplay::Magnitude => {
+                        lines.push(self.vertex(index, colors[index]));
+                        lines.push(self.vertex(neighbor_index, colors[neighbor_index]));
+                    }
+                    StrainDisplay::Signed => {
+                        let distance = (self.positions[neighbor_index] - self.positions[index]).length();
+                        let color = signed_strain_color((distance - relax_distance) / self.config.spring_relax_distance);
+                        lines.push(self.vertex(index, color));
+                        lines.push(self.vertex(neighbor_index, color));
+                    }
+                }
             }
         }
         lines
     }
 
-    pub fn get_index(&self, n: usize, m: usize) -> usize {
-        n * self.height + m
+    // One point per present node.
+    pub fn create_points(&self) -> Vec<Vertex> {
+        let colors = self.node_colors();
+        (0..self.positions.len())
+            .filter(|&index| self.present[index])
+            .map(|index| self.vertex(index, colors[index]))
+            .collect()
     }
 
-    pub fn get_neighbors(&mut self) {
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let mut neighbors = Vec::new();
-                if x != (self.width - 1) {
-                    neighbors.push(self.get_index(x + 1, y));
-                }
-                if x != 0 {
-                    neighbors.push(self.get_index(x - 1, y));
-                }
-                if y != (self.height - 1) {
-                    neighbors.push(self.get_index(x, y + 1));
+    // Outline of every obstacle as pairs of line vertices.
+    pub fn create_obstacles(&self) -> Vec<Vertex> {
+        let mut lines = vec![];
+        let vertex = |position: Vec2| Vertex { position: position.into(), color: OBSTACLE_COLOR, normal: FLAT_NORMAL };
+        for obstacle in &self.obstacles {
+            match *obstacle {
+                Obstacle::Sphere(sphere) => {
+                    let point = |segment: usize| {
+                        let angle = segment as f32 * std::f32::consts::TAU / OBSTACLE_SEGMENTS as f32;
+                        vertex(sphere.center + Vec2::new(angle.cos(), angle.sin()) * sphere.radius)
+                    };
+                    for segment in 0..OBSTACLE_SEGMENTS {
+                        lines.push(point(segment));
+                        lines.push(point(segment + 1));
+                    }
                 }
-                if y != 0 {
-                    neighbors.push(self.get_index(x, y - 1));
+                Obstacle::Box { min, max } => {
+                    let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+                    for corner in 0..corners.len() {
+                        lines.push(vertex(corners[corner]));
+                        lines.push(vertex(corners[(corner + 1) % corners.len()]));
+                    }
                 }
-                let index = self.get_index(x, y);
-                self.neighbours[index] = neighbors;
             }
         }
+        lines
     }
 
-    pub fn calculate_forces(&mut self, delta_t: f32, externalbool: bool) {
-        let positions = &self.positions;
-        let velocities = &mut self.velocities;
-        let fixed = &self.fixed;
-        let neighbours = &self.neighbours;
+    // A line from each free node along its velocity, `scale` world units long per unit of speed.
+    // Nodes with a non-finite velocity are skipped.
+    pub fn create_velocity_field(&self, scale: f32) -> Vec<Vertex> {
+        let mut lines = vec![];
+        for index in 0..self.positions.len() {
+            let velocity = self.velocities[index];
+            if self.fixed[index] || !(velocity.x.is_finite() && velocity.y.is_finite()) {
+                continue;
+            }
+            let position = self.render_position(index);
+            lines.push(Vertex { position: position.into(), color: VELOCITY_COLORS[0], normal: FLAT_NORMAL });
+            lines.push(Vertex { position: (position + velocity * scale).into(), color: VELOCITY_COLORS[1], normal: FLAT_NORMAL });
+        }
+        lines
+    }
+
+    pub fn create_triangles(&self) -> Vec<Vertex> {
+        let colors = self.node_colors();
+        let triangles = self.intact_triangles();
+        let normals = self.vertex_normals(&triangles);
+        triangles
+            .into_iter()
+            .flatten()
+            .map(|index| Vertex { normal: normals[index], ..self.vertex(index, colors[index]) })
+            .collect()
+    }
 
-        let new_positions: Vec<(f32, f32)> = positions
-            .par_iter()
+    // How far each drawn node would stand out of the plane if its compressed springs buckled
+    // instead of shortening: a spring of relax distance L squeezed to d bows out by
+    // sqrt(L² - d²) / 2 at its middle, averaged over the node's springs. Stretched springs stay flat.
+    pub fn node_heights(&self) -> Vec<f32> {
+        self.neighbours
+            .iter()
             .enumerate()
-            .map(|(index, &position)| {
-                if fixed[index] {
-                    return position;
+            .map(|(index, neighbours)| {
+                if neighbours.is_empty() {
+                    return 0.0;
                 }
+                let position = self.render_position(index);
+                let total: f32 = neighbours
+                    .iter()
+                    .map(|&(neighbor_index, relax_distance)| {
+                        let distance_squared = (self.render_position(neighbor_index) - position).length_squared();
+                        (relax_distance * relax_distance - distance_squared).max(0.0).sqrt() / 2.0
+                    })
+                    .sum();
+                total / neighbours.len() as f32
+            })
+            .collect()
+    }
 
-                let mut total_force = (0.0, 0.0);
-                let current_velocity = velocities[index];
-
-                for &neighbor_index in &neighbours[index] {
-                    let neighbor_position = positions[neighbor_index];
-                    let displacement_x = neighbor_position.0 - position.0;
-                    let displacement_y = neighbor_position.1 - position.1;
-                    let distance = (displacement_x.powf(2.0) + displacement_y.powf(2.0)).sqrt();
-                    let magnitude = SPRING_COEFFICIENT * (distance - SPRING_RELAX_DISTANCE);
-
-                    let spring_force_x = magnitude * displacement_x / distance;
-                    let spring_force_y = magnitude * displacement_y / distance;
-                    total_force.0 += spring_force_x;
-                    total_force.1 += spring_force_y;
+    // Unit normal at each node of the height field `node_heights` raises the drawn mesh into,
+    // averaged over the faces of `triangles` around it. Nodes on none of them face the viewer.
+    pub fn vertex_normals(&self, triangles: &[[usize; 3]]) -> Vec<(f32, f32, f32)> {
+        let heights = self.node_heights();
+        let point = |index: usize| {
+            let position = self.render_position(index);
+            [position.x, position.y, heights[index]]
+        };
+        let mut sums = vec![[0.0f32; 3]; self.positions.len()];
+        for &[a, b, c] in triangles {
+            let (pa, pb, pc) = (point(a), point(b), point(c));
+            let u = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+            let v = [pc[0] - pa[0], pc[1] - pa[1], pc[2] - pa[2]];
+            let normal = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+            let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+            // A collapsed face has no direction to contribute.
+            if length < MIN_SPRING_DISTANCE {
+                continue;
+            }
+            for index in [a, b, c] {
+                for axis in 0..3 {
+                    sums[index][axis] += normal[axis] / length;
+                }
+            }
+        }
+        sums.into_iter()
+            .map(|[x, y, z]| {
+                let length = (x * x + y * y + z * z).sqrt();
+                if length < MIN_SPRING_DISTANCE {
+                    FLAT_NORMAL
+                } else {
+                    (x / length, y / length, z / length)
                 }
-
-                let damper_force_x = -current_velocity.0 * DAMPING_COEFFICIENT;
-                let damper_force_y = -current_velocity.1 * DAMPING_COEFFICIENT;
-                total_force.0 += damper_force_x;
-                total_force.1 += damper_force_y;
-
-                if externalbool {
-                    let mut random = rand::thread_rng();
-                    let random_force_x = random.gen_range(-1.0..1.0) * EXTERNAL_MAGNITUDE;
-                    let random_force_y = random.gen_range(-1.0..1.0) * EXTERNAL_MAGNITUDE;
-                    total_force.0 += random_force_x;
-                    total_force.1 += random_force_y;
-                }  
-                let acceleration_x = total_force.0 / MASS;
-                let acceleration_y = total_force.1 / MASS;
-
-                let new_position_x = position.0 + current_velocity.0 * delta_t + 0.5 * acceleration_x * delta_t.powf(2.0);
-                let new_position_y = position.1 + current_velocity.1 * delta_t + 0.5 * acceleration_y * delta_t.powf(2.0);
-
-                (new_position_x, new_position_y)
             })
-            .collect();
+            .collect()
+    }
 
-        let new_velocities: Vec<(f32, f32)> = new_positions
-            .par_iter()
-            .enumerate()
-            .map(|(index, &new_position)| {
-                if fixed[index] {
-                    return velocities[index];
+    // Two counter-clockwise triangles per cell. Each half of a cell is only included while both
+    // of its structural edges are intact.
+    pub fn intact_triangles(&self) -> Vec<[usize; 3]> {
+        let mut triangles = vec![];
+        // A wrapped grid has an extra column of cells joining its last column to its first. A grid
+        // one node wide or tall has no cells at all.
+        let columns = if self.wraps_x() { self.width } else { self.width.saturating_sub(1) };
+        for x in 0..columns {
+            let next_x = (x + 1) % self.width;
+            for y in 0..self.height.saturating_sub(1) {
+                let bottom_left = self.get_index(x, y);
+                let bottom_right = self.get_index(next_x, y);
+                let top_right = self.get_index(next_x, y + 1);
+                let top_left = self.get_index(x, y + 1);
+                let halves = [
+                    [bottom_left, bottom_right, top_right],
+                    [top_right, top_left, bottom_left],
+                ];
+                for [a, b, c] in halves {
+                    if self.is_connected(a, b) && self.is_connected(b, c) {
+                        triangles.push([a, b, c]);
+                    }
                 }
-                let old_position = positions[index];
-                let new_velocity_x = (new_position.0 - old_position.0) / delta_t;
-                let new_velocity_y = (new_position.1 - old_position.1) / delta_t;
-                (new_velocity_x, new_velocity_y)
-            })
-            .collect();
+            }
+        }
+        triangles
+    }
 
-        self.positions = new_positions;
-        self.velocities = new_velocities;
+    pub fn export_obj<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        for position in &self.positions {
+            writeln!(file, "v {} {} 0", position.x, position.y)?;
+        }
+        // OBJ indices start at 1.
+        for [a, b, c] in self.intact_triangles() {
+            writeln!(file, "f {} {} {}", a + 1, b + 1, c + 1)?;
+        }
+        file.flush()
     }
 
-    pub fn calculate_forces_with_gravity(&mut self, delta_t: f32, externalbool: bool) {
-        let positions = &self.positions;
-        let velocities = &mut self.velocities;
-        let fixed = &self.fixed;
-        let neighbours = &self.neighbours;
+    // Kinetic and spring potential energy. Each spring is listed on both of its nodes, so only the
+    // copy on the lower index is counted.
+    pub fn total_energy(&self) -> (f32, f32) {
+        let kinetic = self
+            .velocities
+            .iter()
+            .zip(&self.masses)
+            .map(|(velocity, mass)| 0.5 * mass * velocity.length_squared())
+            .sum();
 
-        let new_positions: Vec<(f32, f32)> = positions
-            .par_iter()
-            .enumerate()
-            .map(|(index, &position)| {
-                if fixed[index] {
-                    return position;
-                }
+        let spring_energy = |neighbours: &[Vec<(usize, f32)>], coefficient: &dyn Fn(usize, usize) -> f32, model: SpringModel| -> f32 {
+            neighbours
+                .iter()
+                .enumerate()
+                .flat_map(|(index, links)| links.iter().map(move |&link| (index, link)))
+                .filter(|&(index, (neighbor_index, _))| index < neighbor_index)
+                .map(|(index, (neighbor_index, relax_distance))| {
+                    let distance = (self.positions[neighbor_index] - self.positions[index]).length();
+                    model.energy(coefficient(index, neighbor_index), distance - relax_distance, relax_distance)
+                })
+                .sum()
+        };
+        let mut potential = spring_energy(&self.neighbours, &|a, b| self.spring_stiffness(a, b), self.config.spring_model);
+        if self.bending {
+            potential += spring_energy(&self.bending_neighbours, &|_, _| self.config.bending_coefficient, SpringModel::Linear);
+        }
 
-                let mut total_force = (0.0, 0.0);
-                let current_velocity = velocities[index];
+        (kinetic, potential)
+    }
 
-                for &neighbor_index in &neighbours[index] {
-                    let neighbor_position = positions[neighbor_index];
-                    let displacement_x = neighbor_position.0 - position.0;
-                    let displacement_y = neighbor_position.1 - position.1;
-                    let distance = (displacement_x.powf(2.0) + displacement_y.powf(2.0)).sqrt();
-                    let magnitude = SPRING_COEFFICIENT * (distance - SPRING_RELAX_DISTANCE);
+    // Mass-weighted mean position of the present nodes, or None when they have no mass.
+    pub fn center_of_mass(&self) -> Option<Vec2> {
+        self.mass_weighted_mean(&self.positions, |index| self.present[index], self.config.parallel)
+    }
 
-                    let spring_force_x = magnitude * displacement_x / distance;
-                    let spring_force_y = magnitude * displacement_y / distance;
-                    total_force.0 += spring_force_x;
-                    total_force.1 += spring_force_y;
-                }
+    // Mass-weighted mean of `values` over the nodes `include` accepts, or None when they have no
+    // mass. Summed on the rayon pool when `parallel`.
+    fn mass_weighted_mean<F: Fn(usize) -> bool + Sync>(&self, values: &[Vec2], include: F, parallel: bool) -> Option<Vec2> {
+        let weigh = |index: usize| {
+            if include(index) {
+                (values[index] * self.masses[index], self.masses[index])
+            } else {
+                (Vec2::ZERO, 0.0)
+            }
+        };
+        let add = |(sum_a, mass_a): (Vec2, f32), (sum_b, mass_b): (Vec2, f32)| (sum_a + sum_b, mass_a + mass_b);
+        let (sum, mass) = if parallel {
+            (0..values.len()).into_par_iter().map(weigh).reduce_with(add)
+        } else {
+            (0..values.len()).map(weigh).reduce(add)
+        }
+        .unwrap_or((Vec2::ZERO, 0.0));
+        (mass > 0.0).then(|| sum / mass)
+    }
 
-                let damper_force_x = -current_velocity.0 * DAMPING_COEFFICIENT;
-                let damper_force_y = -current_velocity.1 * DAMPING_COEFFICIENT;
-                total_force.0 += damper_force_x;
-                total_force.1 += damper_force_y;
+    // Moves the free nodes together so the centre of mass is back at `center`, and takes away
+    // their net velocity so the drift doesn't build up again. Pinned nodes stay put, so the free
+    // ones move further to make up for them.
+    fn hold_center_of_mass(&mut self, center: Vec2, delta_t: f32, parallel: bool) {
+        let present = |index: usize| self.present[index];
+        let free = |index: usize| self.present[index] && !self.fixed[index];
+        let free_mass: f32 = (0..self.masses.len()).filter(|&index| free(index)).map(|index| self.masses[index]).sum();
+        let total_mass: f32 = (0..self.masses.len()).filter(|&index| present(index)).map(|index| self.masses[index]).sum();
+        let current = self.mass_weighted_mean(&self.positions, present, parallel);
+        let (Some(current), Some(velocity)) = (current, self.mass_weighted_mean(&self.velocities, free, parallel)) else {
+            return;
+        };
+        let shift = (center - current) * (total_mass / free_mass);
+        for index in 0..self.positions.len() {
+            if !self.present[index] || self.fixed[index] {
+                continue;
+            }
+            self.positions[index] += shift;
+            self.velocities[index] -= velocity;
+            // Verlet reads velocity from the previous positions, so they move to match.
+            self.prev_positions[index] += shift + velocity * delta_t;
+        }
+    }
 
-                let gravity_force_y = GRAVITY * MASS;
-                total_force.1 += gravity_force_y;
+    // A digest of the grid's size, positions and velocities that's stable between runs, builds and
+    // platforms, for catching unintended changes to the physics. Values are quantized to
+    // HASH_QUANTUM first; non-finite ones all hash alike.
+    pub fn state_hash(&self) -> u64 {
+        let quantize = |value: f32| (value / HASH_QUANTUM).round() as i64 as u64;
+        let mut hash = FNV_OFFSET_BASIS;
+        hash = fnv1a(hash, self.width as u64);
+        hash = fnv1a(hash, self.height as u64);
+        for value in self.positions.iter().chain(&self.velocities) {
+            hash = fnv1a(hash, quantize(value.x));
+            hash = fnv1a(hash, quantize(value.y));
+        }
+        hash
+    }
 
-                if externalbool {
-                    let mut random = rand::thread_rng();
-                    let random_force_x = random.gen_range(-1.0..1.0) * EXTERNAL_MAGNITUDE;
-                    let random_force_y = random.gen_range(-1.0..1.0) * EXTERNAL_MAGNITUDE;
-                    total_force.0 += random_force_x;
-                    total_force.1 += random_force_y;
-                }  
+    // Smallest and largest corners over every present node, ignoring any that have become
+    // non-finite. None when no node has a finite position. Found on the rayon pool when the config
+    // is parallel.
+    pub fn bounding_box(&self) -> Option<(Vec2, Vec2)> {
+        let corners = |index: usize| {
+            let position = self.positions[index];
+            (self.present[index] && position.x.is_finite() && position.y.is_finite()).then_some((position, position))
+        };
+        let merge = |(min_a, max_a): (Vec2, Vec2), (min_b, max_b): (Vec2, Vec2)| {
+            (Vec2::new(min_a.x.min(min_b.x), min_a.y.min(min_b.y)), Vec2::new(max_a.x.max(max_b.x), max_a.y.max(max_b.y)))
+        };
+        if self.config.parallel {
+            (0..self.positions.len()).into_par_iter().filter_map(corners).reduce_with(merge)
+        } else {
+            (0..self.positions.len()).filter_map(corners).reduce(merge)
+        }
+    }
 
-                let acceleration_x = total_force.0 / MASS;
-                let acceleration_y = total_force.1 / MASS;
+    // Average of |distance - relax distance| over each node's springs.
+    pub fn node_strains(&self) -> Vec<f32> {
+        self.average_over_springs(f32::abs)
+    }
 
-                let new_position_x = position.0 + current_velocity.0 * delta_t + 0.5 * acceleration_x * delta_t.powf(2.0);
-                let new_position_y = position.1 + current_velocity.1 * delta_t + 0.5 * acceleration_y * delta_t.powf(2.0);
+    // Average of distance - relax distance over each node's springs, so tension is positive and
+    // compression negative.
+    pub fn node_signed_strains(&self) -> Vec<f32> {
+        self.average_over_springs(|strain| strain)
+    }
 
-                (new_position_x, new_position_y)
-            })
-            .collect();
+    // Counts the structural springs by signed strain, (distance - relax distance) / relax distance,
+    // in `bins` equal bins from -STRAIN_HISTOGRAM_RANGE to STRAIN_HISTOGRAM_RANGE. Every spring is
+    // listed on both of its nodes, so it's counted from the lower index only.
+    pub fn strain_histogram(&self, bins: usize) -> Vec<usize> {
+        let mut counts = vec![0; bins];
+        if bins == 0 {
+            return counts;
+        }
+        let bin_width = 2.0 * STRAIN_HISTOGRAM_RANGE / bins as f32;
+        for (index, neighbours) in self.neighbours.iter().enumerate() {
+            for &(neighbor_index, relax_distance) in neighbours {
+                if neighbor_index < index {
+                    continue;
+                }
+                let distance = (self.positions[neighbor_index] - self.positions[index]).length();
+                let strain = (distance - relax_distance) / relax_distance;
+                // Casting saturates, so compressions past the range, and NaN, land in the first bin.
+                let bin = ((strain + STRAIN_HISTOGRAM_RANGE) / bin_width) as usize;
+                counts[bin.min(bins - 1)] += 1;
+            }
+        }
+        counts
+    }
 
-        let new_velocities: Vec<(f32, f32)> = new_positions
-            .par_iter()
+    // Average of `measure(distance - relax distance)` over each node's springs, 0 for a node
+    // without any.
+    fn average_over_springs<F: Fn(f32) -> f32>(&self, measure: F) -> Vec<f32> {
+        self.neighbours
+            .iter()
             .enumerate()
-            .map(|(index, &new_position)| {
-                if fixed[index] {
-                    return velocities[index];
+            .map(|(index, neighbours)| {
+                if neighbours.is_empty() {
+                    return 0.0;
                 }
-                let old_position = positions[index];
-                let new_velocity_x = (new_position.0 - old_position.0) / delta_t;
-                let new_velocity_y = (new_position.1 - old_position.1) / delta_t;
-                (new_velocity_x, new_velocity_y)
+                let position = self.positions[index];
+                let total: f32 = neighbours
+                    .iter()
+                    .map(|&(neighbor_index, relax_distance)| {
+                        let distance = (self.positions[neighbor_index] - position).length();
+                        measure(distance - relax_distance)
+                    })
+                    .sum();
+                total / neighbours.len() as f32
             })
-            .collect();
+            .collect()
+    }
+
+    // Each node's colour under the current strain display.
+    fn node_colors(&self) -> Vec<(f32, f32, f32)> {
+        let relax_distance = self.config.spring_relax_distance;
+        match self.strain_display {
+            StrainDisplay::Magnitude => self
+                .node_strains()
+                .into_iter()
+                .map(|strain| strain_color(strain / relax_distance))
+                .collect(),
+            StrainDisplay::Signed => self
+                .node_signed_strains()
+                .into_iter()
+                .map(|strain| signed_strain_color(strain / relax_distance))
+                .collect(),
+        }
+    }
+
+    // Where a node is drawn, at `render_alpha` of the way through the last step.
+    pub fn render_position(&self, index: usize) -> Vec2 {
+        let prev_position = self.prev_positions[index];
+        prev_position + (self.positions[index] - prev_position) * self.render_alpha
+    }
+
+    fn vertex(&self, index: usize, color: (f32, f32, f32)) -> Vertex {
+        Vertex {
+            position: self.render_position(index).into(),
+            color: if self.highlight_fixed && self.fixed[index] { FIXED_COLOR } else { color },
+            normal: FLAT_NORMAL,
+        }
+    }
+
+    pub fn get_index(&self, n: usize, m: usize) -> usize {
+        n * self.height + m
+    }
+
+    // Replaces the pinned set, so an empty slice frees every present node.
+    pub fn set_fixed(&mut self, indices: &[usize]) {
+        self.fixed.iter_mut().zip(&self.present).for_each(|(fixed, &present)| *fixed = !present);
+        for &index in indices {
+            self.fixed[index] = true;
+        }
+    }
+
+    pub fn pin_row(&mut self, y: usize) {
+        for x in 0..self.width {
+            let index = self.get_index(x, y);
+            self.fixed[index] = true;
+        }
+    }
 
-        self.positions = new_positions;
-        self.velocities = new_velocities;
+    pub fn pin_column(&mut self, x: usize) {
+        for y in 0..self.height {
+            let index = self.get_index(x, y);
+            self.fixed[index] = true;
+        }
+    }
+
+    // Panics unless `mass` is finite and positive, since the step divides each node's force by it.
+    pub fn set_mass(&mut self, index: usize, mass: f32) {
+        assert!(mass.is_finite() && mass > 0.0, "node mass must be finite and positive, not {}", mass);
+        self.masses[index] = mass;
+    }
+
+    pub fn toggle_fixed(&mut self, index: usize) -> bool {
+        if !self.present[index] {
+            return true;
+        }
+        self.fixed[index] = !self.fixed[index];
+        self.fixed[index]
     }
+
+    // Pins, or given `fixed` false unpins, every node within `radius` of the segment from `from` to
+    // `to`, the path the cursor took since it last painted. Returns how many nodes changed.
+    pub fn paint_fixed(&mut self, from: Vec2, to: Vec2, radius: f32, fixed: bool) -> usize {
+        let stroke = to - from;
+        let middle = (from + to) * 0.5;
+        let distance_squared = |position: Vec2| {
+            let along = if stroke == Vec2::ZERO { 0.0 } else { ((position - from).dot(stroke) / stroke.length_squared()).clamp(0.0, 1.0) };
+            (position - (from + stroke * along)).length_squared()
+        };
+        let mut changed = 0;
+        for index in self.spatial_hash.query_radius(middle, radius + stroke.length() * 0.5, &self.positions) {
+            if self.present[index] && self.fixed[index] != fixed && distance_squared(self.positions[index]) <= radius * radius {
+                self.fixed[index] = fixed;
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    // Pins `index` and drives it along `path` from here on, offset so it doesn't jump at the current time.
+    pub fn set_kinematic<F: Fn(f32) -> Vec2 + Send + Sync + 'static>(&mut self, index: usize, path: F) {
+        let origin = self.positions[index] - path(self.time);
+        self.fixed[index] = true;
+        self.kinematic[index] = Some(Kinematic { origin, path: Box::new(path) });
+    }
+
+    // Moves every pinned node with a path to its position at the current time. Given the length of
+    // the step that got there, its velocity is set to match.
+    fn move_kinematic(&mut self, delta_t: Option<f32>) {
+        for (index, kinematic) in self.kinematic.iter().enumerate() {
+            let Some(kinematic) = kinematic else {
+                continue;
+            };
+            if !self.fixed[index] {
+                continue;
+            }
+            let position = kinematic.origin + (kinematic.path)(self.time);
+            if let Some(delta_t) = delta_t {
+                self.velocities[index] = (position - self.positions[index]) / delta_t;
+            }
+            self.positions[index] = position;
+        }
+    }
+
+    pub fn add_sphere(&mut self, center: Vec2, radius: f32) {
+        self.obstacles.push(Obstacle::Sphere(Sphere { center, radius }));
+    }
+
+    // The corners can be given in either order.
+    pub fn add_box(&mut self, min: Vec2, max: Vec2) {
+        let (min, max) = (Vec2::new(min.x.min(max.x), min.y.min(max.y)), Vec2::new(min.x.max(max.x), min.y.max(max.y)));
+        self.obstacles.push(Obstacle::Box { min, max });
+    }
+
+    // Adds `field`, evaluated at each free node's position and the simulation time, to the forces
+    // every step, replacing any field set before. Vortices, attractors or wind that varies across
+    // the cloth can be tried this way without touching the step.
+    pub fn apply_external_force_field<F: Fn(Vec2, f32) -> Vec2 + Send + Sync + 'static>(&mut self, field: F) {
+        self.force_field = Some(Box::new(field));
+    }
+
+    // The leftmost and rightmost present nodes of the highest row that has any, which is where the
+    // sheet is hung from.
+    pub fn top_corners(&self) -> Option<[usize; 2]> {
+        let row = (0..self.height).rev().map(|y| self.present_in_row(y)).find(|row| !row.is_empty())?;
+        row_ends(&row)
+    }
+
+    // The nodes `preset` pins, skipping any the grid's shape leaves out. Corners are the ends of
+    // the highest and lowest rows with any present nodes.
+    pub fn preset_pins(&self, preset: PinPreset) -> Vec<usize> {
+        let rows: Vec<Vec<usize>> = (0..self.height)
+            .map(|y| self.present_in_row(y))
+            .filter(|row| !row.is_empty())
+            .collect();
+        let mut pins = match (preset, rows.first(), rows.last()) {
+            (PinPreset::Free, ..) | (_, None, _) | (_, _, None) => vec![],
+            (PinPreset::TopCorners, _, Some(top)) => row_ends(top).map(Vec::from).unwrap_or_default(),
+            (PinPreset::TopEdge, _, Some(top)) => top.clone(),
+            (PinPreset::FourCorners, Some(bottom), Some(top)) => {
+                row_ends(bottom).into_iter().chain(row_ends(top)).flatten().collect()
+            }
+            (PinPreset::Center, ..) => {
+                let (min, max) = self.bounding_box().unwrap_or_default();
+                let center = (min + max) * 0.5;
+                let distance_squared = |index: usize| (self.positions[index] - center).length_squared();
+                (0..self.positions.len())
+                    .filter(|&index| self.present[index])
+                    .min_by(|&a, &b| distance_squared(a).total_cmp(&distance_squared(b)))
+                    .into_iter()
+                    .collect()
+            }
+        };
+        // A single row or column has the same node at more than one corner.
+        pins.sort_unstable();
+        pins.dedup();
+        pins
+    }
+
+    // Present nodes of row `y`, from left to right.
+    fn present_in_row(&self, y: usize) -> Vec<usize> {
+        (0..self.width).map(|x| self.get_index(x, y)).filter(|&index| self.present[index]).collect()
+    }
+
+    pub fn is_connected(&self, a: usize, b: usize) -> bool {
+        self.neighbours[a].iter().any(|&(neighbor_index, _)| neighbor_index == b)
+    }
+
+    // Links both nodes to each other, ignoring links that already exist so springs are never doubled up.
+    pub fn add_spring(&mut self, a: usize, b: usize, relax_distance: f32) {
+        if a == b {
+            return;
+        }
+        if !self.is_connected(a, b) {
+            self.neighbours[a].push((b, relax_distance));
+        }
+        if !self.is_connected(b, a) {
+            self.neighbours[b].push((a, relax_distance));
+        }
+    }
+
+    pub fn remove_spring(&mut self, a: usize, b: usize) {
+        self.neighbours[a].retain(|&(neighbor_index, _)| neighbor_index != b);
+        self.neighbours[b].retain(|&(neighbor_index, _)| neighbor_index != a);
+    }
+
+    pub fn nearest_node(&self, point: Vec2) -> Option<usize> {
+        let distance_squared = |index: usize| (self.positions[index] - point).length_squared();
+        let mut nearby = self.spatial_hash.query_radius(point, PICK_RADIUS, &self.positions);
+        nearby.retain(|&index| self.present[index]);
+        if !nearby.is_empty() {
+            return nearby.into_iter().min_by(|&a, &b| distance_squared(a).total_cmp(&distance_squared(b)));
+        }
+        (0..self.positions.len())
+            .filter(|&index| self.present[index])
+            .min_by(|&a, &b| distance_squared(a).total_cmp(&distance_squared(b)))
+    }
+
+    pub fn drag_node(&mut self, index: usize, position: Vec2) {
+        self.positions[index] = position;
+        self.prev_positions[index] = position;
+        self.velocities[index] = Vec2::ZERO;
+    }
+
+    // Adds `impulse` to the velocity of every free node. Verlet reads velocity from the previous
+    // positions, so those are moved back by the change over one step of `delta_t` to match.
+    pub fn apply_impulse(&mut self, impulse: Vec2, delta_t: f32) {
+        for index in 0..self.velocities.len() {
+            if self.fixed[index] {
+                continue;
+            }
+            self.velocities[index] += impulse;
+            self.prev_positions[index] -= impulse * delta_t;
+        }
+    }
+
+    // Pushes the free nodes within `ripple.radius` of `center` directly away from it. Like
+    // `apply_impulse`, the previous positions are moved back to match for Verlet.
+    pub fn apply_ripple(&mut self, center: Vec2, ripple: Ripple, delta_t: f32) {
+        if ripple.radius <= 0.0 {
+            return;
+        }
+        for index in self.spatial_hash.query_radius(center, ripple.radius, &self.positions) {
+            if self.fixed[index] {
+                continue;
+            }
+            let offset = self.positions[index] - center;
+            let weight = ripple.falloff.weight(offset.length() / ripple.radius);
+            let impulse = offset.normalize() * (ripple.strength * weight);
+            self.velocities[index] += impulse;
+            self.prev_positions[index] -= impulse * delta_t;
+        }
+    }
+
+    // Starting velocities for demos, replacing whatever the nodes had. Pinned nodes are left at
+    // rest, and like `apply_impulse` the previous positions are set for steps of `delta_t` so
+    // Verlet starts with the same velocities. Call `save_initial_state` after to keep them on reset.
+    pub fn set_uniform_velocity(&mut self, velocity: Vec2, delta_t: f32) {
+        self.set_velocities(|_| velocity, delta_t);
+    }
+
+    // Every node moves at `speed` directly away from the middle of the grid's bounding box, and
+    // one exactly at the middle stays at rest.
+    pub fn set_radial_velocity(&mut self, speed: f32, delta_t: f32) {
+        let Some((min, max)) = self.bounding_box() else {
+            return;
+        };
+        let center = (min + max) * 0.5;
+        self.set_velocities(|position| {
+            let offset = position - center;
+            if offset.length() < MIN_SPRING_DISTANCE { Vec2::ZERO } else { offset.normalize() * speed }
+        }, delta_t);
+    }
+
+    // Nodes move sideways in proportion to their height above the middle of the bounding box, the
+    // top edge at `speed` along +x and the bottom edge at `speed` along -x. A grid with no height,
+    // like a level rope, has no gradient to follow and is left as it was.
+    pub fn set_shear_velocity(&mut self, speed: f32, delta_t: f32) {
+        let Some((min, max)) = self.bounding_box() else {
+            return;
+        };
+        let (center, half_height) = ((min.y + max.y) * 0.5, (max.y - min.y) * 0.5);
+        if half_height < MIN_SPRING_DISTANCE {
+            return;
+        }
+        self.set_velocities(|position| Vec2::new(speed * (position.y - center) / half_height, 0.0), delta_t);
+    }
+
+    fn set_velocities<F: Fn(Vec2) -> Vec2>(&mut self, velocity_at: F, delta_t: f32) {
+        for index in 0..self.positions.len() {
+            let velocity = if self.fixed[index] { Vec2::ZERO } else { velocity_at(self.positions[index]) };
+            self.velocities[index] = velocity;
+            self.prev_positions[index] = self.positions[index] - velocity * delta_t;
+        }
+    }
+
+    // Rebuilds every node's springs from scratch with the grid's connectivity, so calling it again
+    // never duplicates links.
+    pub fn get_neighbors(&mut self) {
+        self.build_neighbours(self.connectivity);
+    }
+
+    // Rebuilds every node's springs from scratch with `connectivity`, which later rebuilds keep.
+    // `reset` restores the rebuilt springs until the next `save_initial_state`. Each list is
+    // ordered +x, -x, +y, -y, followed by the diagonals +x+y, +x-y, -x+y, -x-y for eight-way
+    // grids. Custom links are ordered by column offset, then row offset.
+    pub fn build_neighbours(&mut self, connectivity: Connectivity) {
+        self.connectivity = connectivity;
+        let mut offsets = vec![(1, 0), (-1, 0), (0, 1), (0, -1)];
+        self.neighbours = match connectivity {
+            Connectivity::Four => self.links_at(&offsets, |_, _| true),
+            Connectivity::Eight => {
+                offsets.extend([(1, 1), (1, -1), (-1, 1), (-1, -1)]);
+                self.links_at(&offsets, |_, _| true)
+            }
+            Connectivity::Custom(adjacent) => {
+                let reach = CUSTOM_REACH as isize;
+                let offsets: Vec<(isize, isize)> = (-reach..=reach)
+                    .flat_map(|dx| (-reach..=reach).map(move |dy| (dx, dy)))
+                    .filter(|&offset| offset != (0, 0))
+                    .collect();
+                self.links_at(&offsets, |a, b| adjacent(a, b) || adjacent(b, a))
+            }
+        };
+        self.torn_springs.clear();
+        self.initial.neighbours.clone_from(&self.neighbours);
+        debug_assert!(springs_are_mutual(&self.neighbours), "structural springs must be symmetric");
+    }
+
+    // Makes the current shape the relaxed one: every structural and bending spring's relax distance
+    // becomes the distance between its ends now. `reset` still restores the springs it saved.
+    pub fn set_rest_from_current(&mut self) {
+        let positions = &self.positions;
+        let set_rest = |links: &mut [Vec<(usize, f32)>]| {
+            for (index, node_links) in links.iter_mut().enumerate() {
+                for (neighbor_index, relax_distance) in node_links {
+                    *relax_distance = (positions[*neighbor_index] - positions[index]).length();
+                }
+            }
+        };
+        set_rest(&mut self.neighbours);
+        set_rest(&mut self.bending_neighbours);
+    }
+
+    pub fn get_bending_neighbors(&mut self) {
+        self.bending_neighbours = self.links_at(&[(2, 0), (-2, 0), (0, 2), (0, -2)], |_, _| true);
+        debug_assert!(springs_are_mutual(&self.bending_neighbours), "bending springs must be symmetric");
+    }
+
+    // Links every node to the nodes at each of `offsets` from it that `accept` agrees to, given
+    // both nodes' coordinates, in that order. The relax distance is the distance between them in
+    // the rest layout.
+    fn links_at<F>(&self, offsets: &[(isize, isize)], accept: F) -> Vec<Vec<(usize, f32)>>
+    where
+        F: Fn((usize, usize), (usize, usize)) -> bool,
+    {
+        let relax_distance = self.config.spring_relax_distance;
+        let mut links = vec![vec![]; self.width * self.height];
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let node_links = offsets
+                    .iter()
+                    .filter_map(|&(dx, dy)| {
+                        let (neighbor_x, neighbor_y) = self.offset_node(x, y, dx, dy)?;
+                        if !accept((x, y), (neighbor_x, neighbor_y)) {
+                            return None;
+                        }
+                        let distance = if self.wraps_x() {
+                            (self.rest_position(neighbor_x, neighbor_y) - self.rest_position(x, y)).length()
+                        } else {
+                            relax_distance * ((dx * dx + dy * dy) as f32).sqrt()
+                        };
+                        Some((self.get_index(neighbor_x, neighbor_y), distance))
+                    })
+                    .collect();
+                let index = self.get_index(x, y);
+                links[index] = self.present_links(index, node_links);
+            }
+        }
+        links
+    }
+
+    // The node `dx` columns and `dy` rows away, if there is one.
+    fn offset_node(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let neighbor_y = y.checked_add_signed(dy).filter(|&neighbor_y| neighbor_y < self.height)?;
+        // Wrapping a grid no wider than twice the offset would link a node to itself, or to the same
+        // neighbour in both directions.
+        if self.wrap_x && self.width > 2 * dx.unsigned_abs() {
+            let neighbor_x = (x as isize + dx).rem_euclid(self.width as isize) as usize;
+            return Some((neighbor_x, neighbor_y));
+        }
+        let neighbor_x = x.checked_add_signed(dx).filter(|&neighbor_x| neighbor_x < self.width)?;
+        Some((neighbor_x, neighbor_y))
+    }
+
+    fn wraps_x(&self) -> bool {
+        self.wrap_x && self.width > 2
+    }
+
+    // Where a node sits when every spring is at its relax distance. Wrapped grids are laid out as
+    // rings, one per row, with the columns spaced evenly around them and the innermost ring's
+    // spacing equal to the relax distance.
+    fn rest_position(&self, x: usize, y: usize) -> Vec2 {
+        let relax_distance = self.config.spring_relax_distance;
+        if !self.wraps_x() {
+            return Vec2::new(x as f32, y as f32) * relax_distance;
+        }
+        let angle = TAU * x as f32 / self.width as f32;
+        let radius = relax_distance / (2.0 * (PI / self.width as f32).sin()) + y as f32 * relax_distance;
+        Vec2::new(angle.cos(), angle.sin()) * radius
+    }
+
+    // Drops the links of an absent node, and any link to one.
+    fn present_links(&self, index: usize, mut links: Vec<(usize, f32)>) -> Vec<(usize, f32)> {
+        if !self.present[index] {
+            return vec![];
+        }
+        links.retain(|&(neighbor_index, _)| self.present[neighbor_index]);
+        links
+    }
+
+    pub fn calculate_forces(&mut self, delta_t: f32, gravity: bool, externalbool: bool) {
+        self.step(delta_t, gravity, externalbool, self.config.parallel, self.config.integrator);
+    }
+
+    // Same step as `calculate_forces`, but always on the calling thread in index order, whatever
+    // `config.parallel` says.
+    pub fn calculate_forces_serial(&mut self, delta_t: f32, gravity: bool, externalbool: bool) {
+        self.step(delta_t, gravity, externalbool, false, self.config.integrator);
+    }
+
+    // One position based step under gravity, whatever `config.integrator` says.
+    pub fn step_pbd(&mut self, delta_t: f32, iterations: usize) {
+        self.step(delta_t, true, false, self.config.parallel, Integrator::PositionBased { iterations });
+    }
+
+    // Profiled as "step", with the phases below it as child spans; the time left over in "step"
+    // itself is the integration.
+    fn step(&mut self, delta_t: f32, gravity: bool, externalbool: bool, parallel: bool, integrator: Integrator) {
+        profile_span!("step");
+        // Springs can't be removed from `neighbours` during the parallel pass, so collect them here.
+        let torn = Mutex::new(Vec::new());
+        if self.config.edge_springs {
+            collect_edges(&self.neighbours, &mut self.edges);
+            collect_edges(&self.bending_neighbours, &mut self.bending_edges);
+        }
+        let mut accelerations = std::mem::take(&mut self.accelerations);
+        let center = if self.config.hold_center_of_mass {
+            self.mass_weighted_mean(&self.positions, |index| self.present[index], parallel)
+        } else {
+            None
+        };
+
+        match integrator {
+            Integrator::SymplecticEuler => {
+                self.compute_accelerations(&self.positions, &self.velocities, gravity, externalbool, parallel, &torn, &mut accelerations);
+                let positions = &self.positions;
+                let velocities = &self.velocities;
+                let fixed = &self.fixed;
+                for_each_node(parallel, &mut self.positions_back, &mut self.velocities_back, |index, new_position, new_velocity| {
+                    let position = positions[index];
+                    let velocity = velocities[index];
+                    if fixed[index] {
+                        *new_position = position;
+                        *new_velocity = velocity;
+                        return;
+                    }
+                    *new_velocity = velocity + accelerations[index] * delta_t;
+                    *new_position = position + *new_velocity * delta_t;
+                });
+            }
+            Integrator::Verlet => {
+                self.compute_accelerations(&self.positions, &self.velocities, gravity, externalbool, parallel, &torn, &mut accelerations);
+                let positions = &self.positions;
+                let prev_positions = &self.prev_positions;
+                let velocities = &self.velocities;
+                let fixed = &self.fixed;
+                for_each_node(parallel, &mut self.positions_back, &mut self.velocities_back, |index, new_position, new_velocity| {
+                    let position = positions[index];
+                    if fixed[index] {
+                        *new_position = position;
+                        *new_velocity = velocities[index];
+                        return;
+                    }
+                    *new_position = position * 2.0 - prev_positions[index] + accelerations[index] * delta_t.powf(2.0);
+                    *new_velocity = (*new_position - position) / delta_t;
+                });
+            }
+            Integrator::Rk4 => {
+                let mut stage_positions = std::mem::take(&mut self.stage_positions);
+                let mut stage_velocities = std::mem::take(&mut self.stage_velocities);
+                stage_positions.copy_from_slice(&self.positions);
+                stage_velocities.copy_from_slice(&self.velocities);
+                self.positions_back.copy_from_slice(&self.positions);
+                self.velocities_back.copy_from_slice(&self.velocities);
+
+                for (stage, &weight) in RK4_WEIGHTS.iter().enumerate() {
+                    self.compute_accelerations(&stage_positions, &stage_velocities, gravity, externalbool, parallel, &torn, &mut accelerations);
+                    // The last stage only accumulates and has no following stage to set up.
+                    let offset = RK4_OFFSETS.get(stage).copied();
+                    let positions = &self.positions;
+                    let velocities = &self.velocities;
+                    let accelerations = &accelerations;
+                    let accumulate = |index: usize, new_position: &mut Vec2, new_velocity: &mut Vec2,
+                                      stage_position: &mut Vec2, stage_velocity: &mut Vec2| {
+                        let acceleration = accelerations[index];
+                        let stage_derivative = *stage_velocity;
+                        *new_position += stage_derivative * (weight * delta_t);
+                        *new_velocity += acceleration * (weight * delta_t);
+                        if let Some(offset) = offset {
+                            *stage_position = positions[index] + stage_derivative * (offset * delta_t);
+                            *stage_velocity = velocities[index] + acceleration * (offset * delta_t);
+                        }
+                    };
+                    if parallel {
+                        self.positions_back
+                            .par_iter_mut()
+                            .zip(self.velocities_back.par_iter_mut())
+                            .zip(stage_positions.par_iter_mut())
+                            .zip(stage_velocities.par_iter_mut())
+                            .enumerate()
+                            .for_each(|(index, (((new_position, new_velocity), stage_position), stage_velocity))| {
+                                accumulate(index, new_position, new_velocity, stage_position, stage_velocity)
+                            });
+                    } else {
+                        self.positions_back
+                            .iter_mut()
+                            .zip(self.velocities_back.iter_mut())
+                            .zip(stage_positions.iter_mut())
+                            .zip(stage_velocities.iter_mut())
+                            .enumerate()
+                            .for_each(|(index, (((new_position, new_velocity), stage_position), stage_velocity))| {
+                                accumulate(index, new_position, new_velocity, stage_position, stage_velocity)
+                            });
+                    }
+                }
+
+                let positions = &self.positions;
+                let velocities = &self.velocities;
+                let fixed = &self.fixed;
+                for_each_node(parallel, &mut self.positions_back, &mut self.velocities_back, |index, new_position, new_velocity| {
+                    if fixed[index] {
+                        *new_position = positions[index];
+                        *new_velocity = velocities[index];
+                    }
+                });
+
+                self.stage_positions = stage_positions;
+                self.stage_velocities = stage_velocities;
+            }
+            Integrator::PositionBased { iterations } => {
+                // Only the external forces and the pressure move the prediction; the springs are
+                // enforced as constraints below.
+                let step_seed = self.step_seed();
+                let pressure = self.pressure_forces(&self.positions);
+                let accelerate = |index: usize, acceleration: &mut Vec2| {
+                    *acceleration = if self.fixed[index] {
+                        Vec2::ZERO
+                    } else {
+                        let mut force = self.external_force(index, self.positions[index], self.velocities[index], gravity, externalbool, step_seed);
+                        if let Some(&pressure) = pressure.get(index) {
+                            force += pressure;
+                        }
+                        force / self.masses[index]
+                    };
+                };
+                self.for_each_index(parallel, &mut accelerations, accelerate);
+
+                let positions = &self.positions;
+                let velocities = &self.velocities;
+                let fixed = &self.fixed;
+                for_each_node(parallel, &mut self.positions_back, &mut self.velocities_back, |index, predicted, _| {
+                    *predicted = if fixed[index] {
+                        positions[index]
+                    } else {
+                        positions[index] + (velocities[index] + accelerations[index] * delta_t) * delta_t
+                    };
+                });
+
+                let mut corrected = std::mem::take(&mut self.stage_positions);
+                for iteration in 0..iterations {
+                    // Springs are only checked for tearing once, against the unconstrained prediction.
+                    let torn = if iteration == 0 { Some(&torn) } else { None };
+                    self.project_constraints(&self.positions_back, torn, parallel, &mut corrected);
+                    std::mem::swap(&mut self.positions_back, &mut corrected);
+                }
+                self.stage_positions = corrected;
+
+                let positions = &self.positions;
+                let velocities = &self.velocities;
+                let fixed = &self.fixed;
+                for_each_node(parallel, &mut self.positions_back, &mut self.velocities_back, |index, new_position, new_velocity| {
+                    *new_velocity = if fixed[index] {
+                        velocities[index]
+                    } else {
+                        (*new_position - positions[index]) / delta_t
+                    };
+                });
+            }
+        }
+
+        self.accelerations = accelerations;
+
+        // Rotate so that the positions from this step become the previous positions for the next.
+        std::mem::swap(&mut self.prev_positions, &mut self.positions);
+        std::mem::swap(&mut self.positions, &mut self.positions_back);
+        std::mem::swap(&mut self.velocities, &mut self.velocities_back);
+
+        self.clamp_velocities(delta_t, parallel);
+        self.apply_obstacles(delta_t, parallel);
+        self.time += delta_t;
+        self.move_kinematic(Some(delta_t));
+        if let Some(center) = center {
+            self.hold_center_of_mass(center, delta_t, parallel);
+        }
+
+        {
+            profile_span!("spatial_hash");
+            self.spatial_hash.rebuild(&self.positions);
+        }
+        if self.config.self_collision {
+            self.apply_self_collision(parallel);
+        }
+        self.apply_floor(delta_t, parallel);
+
+        {
+            profile_span!("tearing");
+            for (a, b) in torn.into_inner().unwrap() {
+                // Both ends can report the same spring, so only the first removes it.
+                if let Some(&(_, relax_distance)) = self.neighbours[a].iter().find(|&&(neighbor_index, _)| neighbor_index == b) {
+                    self.remove_spring(a, b);
+                    self.torn_springs.push((a, b, relax_distance));
+                }
+            }
+        }
+        if self.config.heal {
+            self.heal_springs();
+        }
+
+        self.step_count += 1;
+    }
+
+    // One Jacobi pass over the structural springs: every node moves by the average of the
+    // corrections that would bring each of its springs back to its relax distance, scaled by the
+    // spring's constraint stiffness and split between the two ends by inverse mass. Pinned nodes
+    // have no inverse mass and so never move.
+    fn project_constraints(&self, predicted: &[Vec2], torn: Option<&Mutex<Vec<(usize, usize)>>>, parallel: bool,
+                           corrected: &mut [Vec2]) {
+        profile_span!("project_constraints");
+        let inverse_mass = |index: usize| if self.fixed[index] { 0.0 } else { 1.0 / self.masses[index] };
+        let tear_threshold = self.config.tear_threshold;
+        let constraint_stiffness = self.config.constraint_stiffness.clamp(0.0, 1.0);
+        let max_stiffness = self.max_stiffness();
+        let stiffness = |a: usize, b: usize| {
+            let relative = if max_stiffness > 0.0 { self.spring_stiffness(a, b) / max_stiffness } else { 1.0 };
+            (constraint_stiffness * relative).clamp(0.0, 1.0)
+        };
+
+        let project = |index: usize, corrected: &mut Vec2| {
+            let position = predicted[index];
+            *corrected = position;
+            let weight = inverse_mass(index);
+            if weight == 0.0 || self.neighbours[index].is_empty() {
+                return;
+            }
+            let mut correction = Vec2::ZERO;
+            for &(neighbor_index, relax_distance) in &self.neighbours[index] {
+                let displacement = predicted[neighbor_index] - position;
+                let distance = displacement.length();
+                if distance < MIN_SPRING_DISTANCE {
+                    continue;
+                }
+                if distance > tear_threshold * relax_distance {
+                    if let Some(torn) = torn {
+                        torn.lock().unwrap().push((index, neighbor_index));
+                    }
+                    continue;
+                }
+                let share = stiffness(index, neighbor_index) * weight / (weight + inverse_mass(neighbor_index));
+                correction += displacement * (share * (distance - relax_distance) / distance);
+            }
+            *corrected = position + correction / self.neighbours[index].len() as f32;
+        };
+        self.for_each_index(parallel, corrected, project);
+    }
+
+    // Relinks the torn springs whose ends are back within the heal distance.
+    fn heal_springs(&mut self) {
+        profile_span!("healing");
+        let heal_threshold = self.config.heal_threshold.min(self.config.tear_threshold * HEAL_HYSTERESIS);
+        let positions = &self.positions;
+        let (healed, torn): (Vec<_>, Vec<_>) = std::mem::take(&mut self.torn_springs)
+            .into_iter()
+            .partition(|&(a, b, relax_distance)| (positions[b] - positions[a]).length() <= heal_threshold * relax_distance);
+        self.torn_springs = torn;
+        for (a, b, relax_distance) in healed {
+            self.add_spring(a, b, relax_distance);
+        }
+    }
+
+    // Pushes apart nodes closer than the collision radius that aren't joined by a spring, on the
+    // rayon pool when `parallel`.
+    pub fn apply_self_collision(&mut self, parallel: bool) {
+        profile_span!("self_collision");
+        let mut offsets = std::mem::take(&mut self.collision_offsets);
+        let radius = self.config.collision_radius;
+        let stiffness = self.config.collision_stiffness;
+
+        self.for_each_index(parallel, &mut offsets, |index, offset| {
+            *offset = Vec2::ZERO;
+            if self.fixed[index] {
+                return;
+            }
+            let position = self.positions[index];
+            for other in self.collision_candidates(index) {
+                if other == index || !self.present[other] || self.is_connected(index, other) {
+                    continue;
+                }
+                let displacement = position - self.positions[other];
+                let distance = displacement.length();
+                if distance >= radius || distance < MIN_SPRING_DISTANCE {
+                    continue;
+                }
+                // Each node of the pair moves half of the way.
+                let push = 0.5 * stiffness * (radius - distance) / distance;
+                *offset += displacement * push;
+            }
+        });
+
+        if parallel {
+            self.positions.par_iter_mut().zip(offsets.par_iter()).for_each(|(position, &offset)| *position += offset);
+        } else {
+            self.positions.iter_mut().zip(&offsets).for_each(|(position, &offset)| *position += offset);
+        }
+
+        self.collision_offsets = offsets;
+    }
+
+    fn collision_candidates(&self, index: usize) -> Vec<usize> {
+        self.spatial_hash.query_radius(self.positions[index], self.config.collision_radius, &self.positions)
+    }
+
+    // Limits each node's speed and moves it back along its path to match, resetting any node whose
+    // velocity has already become non-finite.
+    fn clamp_velocities(&mut self, delta_t: f32, parallel: bool) {
+        profile_span!("clamp_velocities");
+        let fixed = &self.fixed;
+        let max_velocity = self.config.max_velocity;
+        let prev_positions = &self.prev_positions;
+
+        for_each_node(parallel, &mut self.positions, &mut self.velocities, |index, position, velocity| {
+            if fixed[index] {
+                return;
+            }
+            let speed = velocity.length();
+            if speed <= max_velocity {
+                return;
+            }
+            *velocity = if speed.is_finite() {
+                *velocity * (max_velocity / speed)
+            } else {
+                Vec2::ZERO
+            };
+            *position = prev_positions[index] + *velocity * delta_t;
+        });
+    }
+
+    // Projects nodes inside an obstacle onto its surface and reflects the part of their velocity
+    // heading into it, scaled by the restitution.
+    fn apply_obstacles(&mut self, delta_t: f32, parallel: bool) {
+        profile_span!("obstacles");
+        if self.obstacles.is_empty() {
+            return;
+        }
+        let fixed = &self.fixed;
+        let obstacles = &self.obstacles;
+        let restitution = self.config.restitution.clamp(0.0, 1.0);
+
+        for_each_node_with_prev(parallel, &mut self.positions, &mut self.velocities, &mut self.prev_positions,
+                                |index, position, velocity, prev_position| {
+            if fixed[index] {
+                return;
+            }
+            for obstacle in obstacles {
+                let Some((surface, normal)) = obstacle.push_out(*position) else {
+                    continue;
+                };
+                *position = surface;
+                let inward_speed = velocity.dot(normal);
+                if inward_speed < 0.0 {
+                    *velocity -= normal * ((1.0 + restitution) * inward_speed);
+                }
+                // Keep the implied Verlet velocity consistent with the projection.
+                *prev_position = *position - *velocity * delta_t;
+            }
+        });
+    }
+
+    fn apply_floor(&mut self, delta_t: f32, parallel: bool) {
+        profile_span!("floor");
+        let fixed = &self.fixed;
+        let floor_y = self.config.floor_y;
+        let restitution = self.config.restitution.clamp(0.0, 1.0);
+
+        for_each_node_with_prev(parallel, &mut self.positions, &mut self.velocities, &mut self.prev_positions,
+                                |index, position, velocity, prev_position| {
+            if fixed[index] || position.y >= floor_y {
+                return;
+            }
+            position.y = floor_y;
+            if velocity.y < 0.0 {
+                velocity.y *= -restitution;
+            }
+            // Keep the implied Verlet velocity consistent with the bounce.
+            prev_position.y = floor_y - velocity.y * delta_t;
+        });
+    }
+
+    // Seed every node's random stream on this step is drawn from.
+    fn step_seed(&self) -> u64 {
+        hash_step_seed(self.config.seed, self.step_count)
+    }
+
+    // Damping, wind, gravity, the random external force and the force field on one node:
+    // everything but the springs.
+    fn external_force(&self, index: usize, position: Vec2, velocity: Vec2, gravity: bool, externalbool: bool, step_seed: u64) -> Vec2 {
+        self.external_forces(index, position, velocity, gravity, externalbool, step_seed).non_spring()
+    }
+
+    // The same forces kept apart by source, with the spring force left at zero.
+    fn external_forces(&self, index: usize, position: Vec2, velocity: Vec2, gravity: bool, externalbool: bool,
+                       step_seed: u64) -> ForceBreakdown {
+        let config = &self.config;
+        let mut forces = ForceBreakdown {
+            wind: config.wind,
+            damping: velocity * -config.damping_coefficient,
+            ..ForceBreakdown::default()
+        };
+        if config.quadratic_drag {
+            forces.drag = -(velocity * (config.drag_coefficient * velocity.length()));
+        }
+
+        if gravity {
+            forces.gravity = config.gravity * self.masses[index];
+        }
+
+        if externalbool {
+            let mut random = StdRng::seed_from_u64(node_seed(step_seed, index));
+            let random_force = Vec2::new(random.gen_range(-1.0..1.0), random.gen_range(-1.0..1.0));
+            forces.external = random_force * config.external_magnitude;
+        }
+
+        if let Some(field) = &self.force_field {
+            forces.field = field(position, self.time);
+        }
+
+        forces
+    }
+
+    // Stiffness of the structural spring joining `a` and `b`: stiffness_x along a row, stiffness_y
+    // along a column and spring_coefficient across a diagonal or when they're unset.
+    fn spring_stiffness(&self, a: usize, b: usize) -> f32 {
+        let config = &self.config;
+        let axis_stiffness = if a % self.height == b % self.height {
+            config.stiffness_x
+        } else if a / self.height == b / self.height {
+            config.stiffness_y
+        } else {
+            None
+        };
+        axis_stiffness.unwrap_or(config.spring_coefficient)
+    }
+
+    // The stiffest any structural spring can be.
+    fn max_stiffness(&self) -> f32 {
+        let config = &self.config;
+        [config.stiffness_x, config.stiffness_y].into_iter().flatten().fold(config.spring_coefficient, f32::max)
+    }
+
+    // The structural and bending spring forces on a node at `positions`, added up link by link.
+    // Springs stretched past tearing are left out and, given `torn`, reported there.
+    fn spring_force(&self, positions: &[Vec2], index: usize, torn: Option<&Mutex<Vec<(usize, usize)>>>) -> Vec2 {
+        let config = &self.config;
+        let position = positions[index];
+        let mut force = Vec2::ZERO;
+
+        for &(neighbor_index, relax_distance) in &self.neighbours[index] {
+            let displacement = positions[neighbor_index] - position;
+            let distance = displacement.length();
+            if distance < MIN_SPRING_DISTANCE {
+                continue;
+            }
+            if distance > config.tear_threshold * relax_distance {
+                if let Some(torn) = torn {
+                    torn.lock().unwrap().push((index, neighbor_index));
+                }
+                continue;
+            }
+            let stiffness = self.spring_stiffness(index, neighbor_index);
+            let magnitude = config.spring_model.force(stiffness, distance - relax_distance, relax_distance);
+            force += displacement * (magnitude / distance);
+        }
+
+        let bending = if self.bending { self.bending_neighbours.get(index) } else { None };
+        for &(neighbor_index, relax_distance) in bending.into_iter().flatten() {
+            let displacement = positions[neighbor_index] - position;
+            let distance = displacement.length();
+            if distance < MIN_SPRING_DISTANCE {
+                continue;
+            }
+            let magnitude = config.bending_coefficient * (distance - relax_distance);
+            force += displacement * (magnitude / distance);
+        }
+
+        force
+    }
+
+    // The forces `spring_force` gives every node, worked out once per spring in `edges` and
+    // `bending_edges` and added to both ends in opposite directions. In parallel each task sums
+    // its share of the springs into its own buffer, and the buffers are added up at the end.
+    fn edge_spring_forces(&self, positions: &[Vec2], edges: &[(usize, usize, f32)], bending_edges: &[(usize, usize, f32)],
+                          parallel: bool, torn: Option<&Mutex<Vec<(usize, usize)>>>) -> Vec<Vec2> {
+        let config = &self.config;
+        let bending_edges = if self.bending { bending_edges } else { &[] };
+        let accumulate = |edges: &[(usize, usize, f32)], bending: bool| {
+            let mut forces = vec![Vec2::ZERO; positions.len()];
+            for &(a, b, relax_distance) in edges {
+                let displacement = positions[b] - positions[a];
+                let distance = displacement.length();
+                if distance < MIN_SPRING_DISTANCE {
+                    continue;
+                }
+                let magnitude = if bending {
+                    config.bending_coefficient * (distance - relax_distance)
+                } else if distance > config.tear_threshold * relax_distance {
+                    if let Some(torn) = torn {
+                        torn.lock().unwrap().push((a, b));
+                    }
+                    continue;
+                } else {
+                    config.spring_model.force(self.spring_stiffness(a, b), distance - relax_distance, relax_distance)
+                };
+                let force = displacement * (magnitude / distance);
+                forces[a] += force;
+                forces[b] -= force;
+            }
+            forces
+        };
+        let add = |mut total: Vec<Vec2>, forces: Vec<Vec2>| {
+            for (total, force) in total.iter_mut().zip(forces) {
+                *total += force;
+            }
+            total
+        };
+
+        if !parallel {
+            return add(accumulate(edges, false), accumulate(bending_edges, true));
+        }
+        let tasks = rayon::current_num_threads().max(1);
+        let chunk = |edges: &[(usize, usize, f32)]| edges.len().div_ceil(tasks).max(1);
+        edges
+            .par_chunks(chunk(edges))
+            .map(|edges| accumulate(edges, false))
+            .chain(bending_edges.par_chunks(chunk(bending_edges)).map(|edges| accumulate(edges, true)))
+            .reduce_with(add)
+            .unwrap_or_else(|| vec![Vec2::ZERO; positions.len()])
+    }
+
+    // Every force the next step would apply to `index` given the gravity and random force
+    // switches, computed from the current state by the same code the step uses, so the total is
+    // exactly what it would add up. Pinned nodes get theirs too, though the step ignores them.
+    pub fn debug_forces(&self, index: usize, gravity: bool, externalbool: bool) -> ForceBreakdown {
+        let forces = self.external_forces(index, self.positions[index], self.velocities[index], gravity, externalbool, self.step_seed());
+        let spring = if self.config.edge_springs {
+            let (mut edges, mut bending_edges) = (vec![], vec![]);
+            collect_edges(&self.neighbours, &mut edges);
+            collect_edges(&self.bending_neighbours, &mut bending_edges);
+            self.edge_spring_forces(&self.positions, &edges, &bending_edges, false, None)[index]
+        } else {
+            self.spring_force(&self.positions, index, None)
+        };
+        ForceBreakdown {
+            spring,
+            pressure: self.pressure_forces(&self.positions).get(index).copied().unwrap_or_default(),
+            ..forces
+        }
+    }
+
+    // The area inside a wrapped grid's innermost ring, or None for a grid that isn't closed.
+    pub fn enclosed_area(&self) -> Option<f32> {
+        self.wraps_x().then(|| self.ring_signed_area(&self.positions).abs())
+    }
+
+    // Shoelace formula over the innermost ring at `positions`, positive when it runs anticlockwise.
+    fn ring_signed_area(&self, positions: &[Vec2]) -> f32 {
+        let twice_area: f32 = (0..self.width)
+            .map(|x| {
+                let a = positions[self.get_index(x, 0)];
+                let b = positions[self.get_index((x + 1) % self.width, 0)];
+                a.x * b.y - b.x * a.y
+            })
+            .sum();
+        0.5 * twice_area
+    }
+
+    // Each node's share of the pressure on the innermost ring of a wrapped grid at `positions`.
+    // Every edge of the ring is pushed along its outward normal with a force of the pressure times
+    // its length, split between its two ends. Empty while the pressure is off or the grid isn't
+    // closed.
+    fn pressure_forces(&self, positions: &[Vec2]) -> Vec<Vec2> {
+        if self.config.pressure_coefficient == 0.0 || !self.wraps_x() {
+            return vec![];
+        }
+        let signed_area = self.ring_signed_area(positions);
+        let pressure = self.config.pressure_coefficient * (self.config.target_area - signed_area.abs());
+        // Turning an edge clockwise points it out of an anticlockwise ring, and into a clockwise one.
+        let outward = if signed_area < 0.0 { -1.0 } else { 1.0 };
+        let mut forces = vec![Vec2::ZERO; positions.len()];
+        for x in 0..self.width {
+            let (a, b) = (self.get_index(x, 0), self.get_index((x + 1) % self.width, 0));
+            let edge = positions[b] - positions[a];
+            let share = Vec2::new(edge.y, -edge.x) * (0.5 * outward * pressure);
+            forces[a] += share;
+            forces[b] += share;
+        }
+        forces
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn compute_accelerations(&self, positions: &[Vec2], velocities: &[Vec2], gravity: bool, externalbool: bool,
+                             parallel: bool, torn: &Mutex<Vec<(usize, usize)>>, accelerations: &mut [Vec2]) {
+        profile_span!("accelerations");
+        let step_seed = self.step_seed();
+        let pressure = self.pressure_forces(positions);
+        let spring_forces = if self.config.edge_springs {
+            self.edge_spring_forces(positions, &self.edges, &self.bending_edges, parallel, Some(torn))
+        } else {
+            vec![]
+        };
+
+        let accelerate = |index: usize, acceleration: &mut Vec2| {
+            if self.fixed[index] {
+                *acceleration = Vec2::ZERO;
+                return;
+            }
+            let mut total_force = match spring_forces.get(index) {
+                Some(&force) => force,
+                None => self.spring_force(positions, index, Some(torn)),
+            };
+            if let Some(&pressure) = pressure.get(index) {
+                total_force += pressure;
+            }
+            total_force += self.external_force(index, positions[index], velocities[index], gravity, externalbool, step_seed);
+            *acceleration = total_force / self.masses[index];
+        };
+        self.for_each_index(parallel, accelerations, accelerate);
+    }
+
+    // Calls `update` with each node's index and its entry in `values`, serially in index order or
+    // split across the rayon pool as `config.partitioning` says.
+    fn for_each_index<F>(&self, parallel: bool, values: &mut [Vec2], update: F)
+    where
+        F: Fn(usize, &mut Vec2) + Sync + Send,
+    {
+        if !parallel {
+            values.iter_mut().enumerate().for_each(|(index, value)| update(index, value));
+            return;
+        }
+        match self.config.partitioning {
+            Partitioning::Flat => {
+                values.par_iter_mut().enumerate().for_each(|(index, value)| update(index, value));
+            }
+            Partitioning::Columns { per_task } => {
+                let block = (self.height * per_task).max(1);
+                values.par_chunks_mut(block).enumerate().for_each(|(block_index, values)| {
+                    for (offset, value) in values.iter_mut().enumerate() {
+                        update(block_index * block + offset, value);
+                    }
+                });
+            }
+        }
+    }
+}
+
+// The seed and step are hashed rather than added, so neighbouring seeds or steps don't hand the
+// nodes each other's streams shifted along by one.
+pub(crate) fn hash_step_seed(seed: u64, step: u64) -> u64 {
+    fnv1a(fnv1a(FNV_OFFSET_BASIS, seed), step)
+}
+
+// Seed of the random force on node `index` during the step seeded with `step_seed`.
+pub(crate) fn node_seed(step_seed: u64, index: usize) -> u64 {
+    fnv1a(step_seed, index as u64)
+}
+
+// Folds the little-endian bytes of `value` into an FNV-1a hash.
+fn fnv1a(mut hash: u64, value: u64) -> u64 {
+    for byte in value.to_le_bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+// Inserts a value at the bottom of every column of a buffer laid out like the grid, `value` being
+// given the column. Columns are visited from the last so the earlier ones haven't moved yet.
+fn insert_row<T, F: FnMut(usize) -> T>(values: &mut Vec<T>, width: usize, height: usize, mut value: F) {
+    for x in (0..width).rev() {
+        values.insert(x * height, value(x));
+    }
+}
+
+fn remove_row<T>(values: &mut Vec<T>, width: usize, height: usize) {
+    for x in (0..width).rev() {
+        values.remove(x * height);
+    }
+}
+
+// Each spring in `links` once, from its lower-indexed end, into `edges`.
+fn collect_edges(links: &[Vec<(usize, f32)>], edges: &mut Vec<(usize, usize, f32)>) {
+    edges.clear();
+    for (index, node_links) in links.iter().enumerate() {
+        edges.extend(node_links.iter().filter(|&&(other, _)| index < other).map(|&(other, relax_distance)| (index, other, relax_distance)));
+    }
+}
+
+// The first and last of a row's nodes, which are the same node when it has only one.
+fn row_ends(row: &[usize]) -> Option<[usize; 2]> {
+    Some([*row.first()?, *row.last()?])
+}
+
+// Maps relative strain onto a blue (relaxed) to red (overstretched) gradient.
+pub fn strain_color(strain: f32) -> (f32, f32, f32) {
+    let t = (strain / MAX_DISPLAYED_STRAIN).clamp(0.0, 1.0);
+    (t, 0.0, 1.0 - t)
+}
+
+// Maps signed relative strain onto blue (compressed) through green (relaxed) to red (stretched).
+pub fn signed_strain_color(strain: f32) -> (f32, f32, f32) {
+    let t = (strain / MAX_DISPLAYED_STRAIN).clamp(-1.0, 1.0);
+    if t >= 0.0 {
+        (t, 1.0 - t, 0.0)
+    } else {
+        (0.0, 1.0 + t, -t)
+    }
+}
+
+impl Simulation for Grid {
+    fn advance(&mut self, delta_t: f32, gravity: bool, externalbool: bool) {
+        let substeps = self.config.substeps.max(1);
+        for _ in 0..substeps {
+            self.calculate_forces(delta_t / substeps as f32, gravity, externalbool);
+        }
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn has_nonfinite(&self) -> Option<usize> {
+        let finite = |value: &Vec2| value.x.is_finite() && value.y.is_finite();
+        (0..self.positions.len()).find(|&index| !finite(&self.positions[index]) || !finite(&self.velocities[index]))
+    }
+}
+
+// Calls `update` with each node's index and its entries in the two buffers, either on the rayon
+// pool or serially in index order.
+fn for_each_node<F>(parallel: bool, positions: &mut [Vec2], velocities: &mut [Vec2], update: F)
+where
+    F: Fn(usize, &mut Vec2, &mut Vec2) + Sync + Send,
+{
+    if parallel {
+        positions
+            .par_iter_mut()
+            .zip(velocities.par_iter_mut())
+            .enumerate()
+            .for_each(|(index, (position, velocity))| update(index, position, velocity));
+    } else {
+        positions
+            .iter_mut()
+            .zip(velocities.iter_mut())
+            .enumerate()
+            .for_each(|(index, (position, velocity))| update(index, position, velocity));
+    }
+}
+
+// Like `for_each_node`, with each node's previous position as well.
+fn for_each_node_with_prev<F>(parallel: bool, positions: &mut [Vec2], velocities: &mut [Vec2], prev_positions: &mut [Vec2],
+                              update: F)
+where
+    F: Fn(usize, &mut Vec2, &mut Vec2, &mut Vec2) + Sync + Send,
+{
+    if parallel {
+        positions
+            .par_iter_mut()
+            .zip(velocities.par_iter_mut())
+            .zip(prev_positions.par_iter_mut())
+            .enumerate()
+            .for_each(|(index, ((position, velocity), prev_position))| update(index, position, velocity, prev_position));
+    } else {
+        positions
+            .iter_mut()
+            .zip(velocities.iter_mut())
+            .zip(prev_positions.iter_mut())
+            .enumerate()
+            .for_each(|(index, ((position, velocity), prev_position))| update(index, position, velocity, prev_position));
+    }
+}
+
+// True when every link appears exactly once on each of its two nodes, which tearing relies on.
+fn springs_are_mutual(neighbours: &[Vec<(usize, f32)>]) -> bool {
+    neighbours.iter().enumerate().all(|(index, links)| {
+        links.iter().enumerate().all(|(position, &(other, _))| {
+            let unique = links[..position].iter().all(|&(earlier, _)| earlier != other);
+            let mutual = neighbours[other].iter().filter(|&&(back, _)| back == index).count() == 1;
+            other != index && unique && mutual
+        })
+    })
 }
@@ -0,0 +1,232 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::grid::{hash_step_seed, node_seed, strain_color, Simulation, SimulationConfig};
+
+// Height above the origin at which the sheet starts out lying flat.
+const Y_OFFSET: f32 = 10.0;
+
+// Below this distance two nodes are treated as coincident and the spring has no direction.
+const MIN_SPRING_DISTANCE: f32 = 1e-6;
+
+#[derive(Copy, Clone)]
+pub struct Vertex3D {
+    pub position: (f32, f32, f32),
+    pub color: (f32, f32, f32),
+}
+
+implement_vertex!(Vertex3D, position, color);
+
+// A 3D counterpart of `Grid`. The sheet starts flat in the x-z plane so that gravity along -y
+// makes it drape, and is integrated with symplectic Euler.
+pub struct Grid3D {
+    pub width: usize,
+    pub height: usize,
+    pub positions: Vec<(f32, f32, f32)>,
+    pub velocities: Vec<(f32, f32, f32)>,
+    // Scratch buffers written by each step and swapped in, to avoid reallocating.
+    positions_back: Vec<(f32, f32, f32)>,
+    velocities_back: Vec<(f32, f32, f32)>,
+    pub fixed: Vec<bool>,
+    // Each neighbour is stored with the relax distance of the spring linking it.
+    pub neighbours: Vec<Vec<(usize, f32)>>,
+    pub config: SimulationConfig,
+    pub step_count: u64,
+}
+
+impl Grid3D {
+    pub fn new(width: usize, height: usize) -> Grid3D {
+        Grid3D::with_config(width, height, SimulationConfig::default())
+    }
+
+    pub fn with_config(width: usize, height: usize, config: SimulationConfig) -> Grid3D {
+        let size = width * height;
+        let mut positions = Vec::with_capacity(size);
+        for x in 0..width {
+            for z in 0..height {
+                positions.push((
+                    (x as f32 - (width / 2) as f32) * config.spacing,
+                    Y_OFFSET,
+                    (z as f32 - (height / 2) as f32) * config.spacing,
+                ));
+            }
+        }
+        let velocities = vec![(0.0, 0.0, 0.0); size];
+
+        Grid3D {
+            width,
+            height,
+            positions_back: positions.clone(),
+            velocities_back: velocities.clone(),
+            positions,
+            velocities,
+            fixed: vec![false; size],
+            neighbours: vec![vec![]; size],
+            config,
+            step_count: 0,
+        }
+    }
+
+    pub fn get_index(&self, n: usize, m: usize) -> usize {
+        n * self.height + m
+    }
+
+    pub fn get_neighbors(&mut self) {
+        let relax_distance = self.config.spring_relax_distance;
+        for x in 0..self.width {
+            for z in 0..self.height {
+                let mut neighbors = Vec::new();
+                if x != (self.width - 1) {
+                    neighbors.push((self.get_index(x + 1, z), relax_distance));
+                }
+                if x != 0 {
+                    neighbors.push((self.get_index(x - 1, z), relax_distance));
+                }
+                if z != (self.height - 1) {
+                    neighbors.push((self.get_index(x, z + 1), relax_distance));
+                }
+                if z != 0 {
+                    neighbors.push((self.get_index(x, z - 1), relax_distance));
+                }
+                let index = self.get_index(x, z);
+                self.neighbours[index] = neighbors;
+            }
+        }
+    }
+
+    pub fn create_grid(&self) -> Vec<Vertex3D> {
+        let mut lines = vec![];
+        for (index, neighbours) in self.neighbours.iter().enumerate() {
+            for &(neighbor_index, relax_distance) in neighbours {
+                // Every spring is listed on both of its nodes; draw it once.
+                if neighbor_index < index {
+                    continue;
+                }
+                let strain = (distance(self.positions[index], self.positions[neighbor_index]) - relax_distance).abs();
+                let color = strain_color(strain / relax_distance);
+                lines.push(Vertex3D {
+                    position: self.positions[index],
+                    color,
+                });
+                lines.push(Vertex3D {
+                    position: self.positions[neighbor_index],
+                    color,
+                });
+            }
+        }
+        lines
+    }
+
+    pub fn calculate_forces(&mut self, delta_t: f32, gravity: bool, externalbool: bool) {
+        let positions = &self.positions;
+        let velocities = &self.velocities;
+        let fixed = &self.fixed;
+        let neighbours = &self.neighbours;
+        let config = &self.config;
+        let step_seed = hash_step_seed(config.seed, self.step_count);
+
+        self.positions_back
+            .par_iter_mut()
+            .zip(self.velocities_back.par_iter_mut())
+            .enumerate()
+            .for_each(|(index, (new_position, new_velocity))| {
+                let position = positions[index];
+                let velocity = velocities[index];
+                if fixed[index] {
+                    *new_position = position;
+                    *new_velocity = velocity;
+                    return;
+                }
+
+                let mut total_force = (0.0, 0.0, 0.0);
+                for &(neighbor_index, relax_distance) in &neighbours[index] {
+                    let neighbor_position = positions[neighbor_index];
+                    let displacement = (
+                        neighbor_position.0 - position.0,
+                        neighbor_position.1 - position.1,
+                        neighbor_position.2 - position.2,
+                    );
+                    let distance = distance(position, neighbor_position);
+                    if distance < MIN_SPRING_DISTANCE {
+                        continue;
+                    }
+                    let magnitude = config.spring_coefficient * (distance - relax_distance) / distance;
+                    total_force.0 += magnitude * displacement.0;
+                    total_force.1 += magnitude * displacement.1;
+                    total_force.2 += magnitude * displacement.2;
+                }
+
+                total_force.0 -= velocity.0 * config.damping_coefficient;
+                total_force.1 -= velocity.1 * config.damping_coefficient;
+                total_force.2 -= velocity.2 * config.damping_coefficient;
+
+                if config.quadratic_drag {
+                    let drag = config.drag_coefficient * distance((0.0, 0.0, 0.0), velocity);
+                    total_force.0 -= drag * velocity.0;
+                    total_force.1 -= drag * velocity.1;
+                    total_force.2 -= drag * velocity.2;
+                }
+
+                total_force.0 += config.wind.x;
+                total_force.1 += config.wind.y;
+
+                if gravity {
+                    total_force.0 += config.gravity.x * config.mass;
+                    total_force.1 += config.gravity.y * config.mass;
+                }
+
+                if externalbool {
+                    let mut random = StdRng::seed_from_u64(node_seed(step_seed, index));
+                    total_force.0 += random.gen_range(-1.0..1.0) * config.external_magnitude;
+                    total_force.1 += random.gen_range(-1.0..1.0) * config.external_magnitude;
+                    total_force.2 += random.gen_range(-1.0..1.0) * config.external_magnitude;
+                }
+
+                let mut velocity = (
+                    velocity.0 + total_force.0 / config.mass * delta_t,
+                    velocity.1 + total_force.1 / config.mass * delta_t,
+                    velocity.2 + total_force.2 / config.mass * delta_t,
+                );
+                let mut position = (
+                    position.0 + velocity.0 * delta_t,
+                    position.1 + velocity.1 * delta_t,
+                    position.2 + velocity.2 * delta_t,
+                );
+                if position.1 < config.floor_y {
+                    position.1 = config.floor_y;
+                    if velocity.1 < 0.0 {
+                        velocity.1 *= -config.restitution.clamp(0.0, 1.0);
+                    }
+                }
+                *new_position = position;
+                *new_velocity = velocity;
+            });
+
+        std::mem::swap(&mut self.positions, &mut self.positions_back);
+        std::mem::swap(&mut self.velocities, &mut self.velocities_back);
+        self.step_count += 1;
+    }
+}
+
+impl Simulation for Grid3D {
+    fn advance(&mut self, delta_t: f32, gravity: bool, externalbool: bool) {
+        let substeps = self.config.substeps.max(1);
+        for _ in 0..substeps {
+            self.calculate_forces(delta_t / substeps as f32, gravity, externalbool);
+        }
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn has_nonfinite(&self) -> Option<usize> {
+        let finite = |value: &(f32, f32, f32)| value.0.is_finite() && value.1.is_finite() && value.2.is_finite();
+        (0..self.positions.len()).find(|&index| !finite(&self.positions[index]) || !finite(&self.velocities[index]))
+    }
+}
+
+fn distance(a: (f32, f32, f32), b: (f32, f32, f32)) -> f32 {
+    ((b.0 - a.0).powf(2.0) + (b.1 - a.1).powf(2.0) + (b.2 - a.2).powf(2.0)).sqrt()
+}
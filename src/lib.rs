@@ -0,0 +1,30 @@
+#[macro_use]
+extern crate glium;
+extern crate num_cpus;
+
+pub mod benchmark;
+pub mod camera;
+pub mod cli;
+pub mod gpu;
+pub mod grid;
+pub mod grid3d;
+pub mod recorder;
+pub mod replay;
+pub mod run_log;
+pub mod scene;
+#[cfg(feature = "config")]
+pub mod scenario;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+pub mod spatial_hash;
+pub mod theme;
+pub mod vec2;
+
+pub use crate::grid::{Connectivity, Falloff, ForceBreakdown, ForceField, Grid, GridSnapshot, Integrator, Kinematic, KinematicPath, Obstacle, Partitioning, PinPreset, Ripple, Simulation, SimulationConfig, Sphere, SpringModel, StrainDisplay, Vertex};
+pub use crate::grid3d::{Grid3D, Vertex3D};
+pub use crate::scene::Scene;
+pub use crate::vec2::Vec2;
+
+// Default grid dimensions when none are given on the command line.
+pub const HEIGHT: usize = 30;
+pub const WIDTH: usize = 30;
@@ -2,127 +2,1116 @@
 extern crate glium;
 extern crate winit;
 extern crate num_cpus;
+extern crate soft_body_sim_rust;
 
-use std::sync::{Arc, RwLock};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::f32::consts::TAU;
+use std::iter::Peekable;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::thread; 
 use std::time::{Duration , Instant};
+use glium::glutin::config::ConfigTemplateBuilder;
+use glium::glutin::surface::WindowSurface;
 use glium::Surface;
-use rayon::ThreadPoolBuilder;
+#[cfg(feature = "gui")]
+use egui_glium::egui_winit::egui;
+#[cfg(feature = "gui")]
+use egui_glium::EguiGlium;
+use rayon::ThreadPool;
 
-use crate::grid::Grid;
-use crate::grid::Vertex;
-
-mod grid;
+use soft_body_sim_rust::benchmark::{self, RunSummary, TimingSummary};
+use soft_body_sim_rust::camera::{Camera, OrbitCamera};
+use soft_body_sim_rust::cli::{InitialVelocity, Options, Pins, Shape};
+use soft_body_sim_rust::gpu::{self, GpuStepper};
+use soft_body_sim_rust::grid::STRAIN_HISTOGRAM_RANGE;
+use soft_body_sim_rust::recorder::Recorder;
+use soft_body_sim_rust::replay::{self, Input, InputRecorder};
+use soft_body_sim_rust::run_log::{self, Level, RunLog, RunRecord};
+use soft_body_sim_rust::theme::Theme;
+#[cfg(feature = "snapshot")]
+use soft_body_sim_rust::snapshot;
+use soft_body_sim_rust::{Grid, Grid3D, Partitioning, PinPreset, Ripple, Scene, Simulation, SimulationConfig, StrainDisplay, Vec2};
 
 const DELTA_TIME: f32 = 0.01;
-const LOG_DURATION: u64 = 10; // In seconds
+const LOG_DURATION: f32 = 10.0; // In seconds, unless --duration gives a time
+const BENCHMARK_STEPS: usize = 1000;
+const BENCHMARK_OUTPUT: &str = "benchmark.json";
+#[cfg(feature = "snapshot")]
+const STATE_FILE: &str = "state.json";
 static GRAVITY_ACTIVE: AtomicBool = 
 AtomicBool::new(true);
 static EXTERNAL_MAGNITUDE: AtomicBool = 
 AtomicBool::new(false);
-const HEIGHT: usize = 30;
-const WIDTH: usize = 30;
+static PAUSED: AtomicBool = AtomicBool::new(false);
+// Set by the step key to advance a paused simulation by a single force calculation.
+static STEP_REQUESTED: AtomicBool = AtomicBool::new(false);
+// Cleared when the window closes so the simulation thread can finish and be joined.
+static RUNNING: AtomicBool = AtomicBool::new(true);
+// Total force calculations completed, read by the render loop to report the step rate.
+static SIM_STEPS: AtomicU64 = AtomicU64::new(0);
+// When the simulation thread last worked out how many steps it was due, and the time it carried
+// over. The render loop adds the time since to find how far it is into the next step.
+static CAUGHT_UP: Mutex<Option<(Instant, f32)>> = Mutex::new(None);
+// Simulated time per step relative to real time, stepped through with Z and X, and the index of 1x.
+// It's distinct from pausing: the simulation keeps advancing, only by more or less each step.
+const TIME_SCALES: [f32; 6] = [0.1, 0.25, 0.5, 1.0, 2.0, 4.0];
+const REAL_TIME: usize = 3;
+static TIME_SCALE: AtomicUsize = AtomicUsize::new(REAL_TIME);
+// Set while the window steps the scene on the GPU. The simulation thread keeps its clock but
+// leaves the steps it works out in GPU_STEPS_DUE for the window to run.
+static GPU_STEPPING: AtomicBool = AtomicBool::new(false);
+static GPU_STEPS_DUE: AtomicUsize = AtomicUsize::new(0);
+// Change in each wind component per arrow key press.
+const WIND_STEP: f32 = 0.02;
+// Velocity added to every free node by the drop key.
+const DROP_IMPULSE: Vec2 = Vec2::new(0.0, 5.0);
+// Factor applied to the spring coefficient per +/- key press, and the floor it can't be lowered past.
+const STIFFNESS_STEP: f32 = 1.1;
+const MIN_SPRING_COEFFICIENT: f32 = 0.1;
+// Factor applied to the damping coefficient per 9/0 key press. Lowering it below the minimum turns
+// damping off, and raising it from off starts at the minimum.
+const DAMPING_STEP: f32 = 1.25;
+const MIN_DAMPING_COEFFICIENT: f32 = 0.001;
+// Change in downward gravity per Page Up/Page Down press, in m/s^2. It's allowed to go negative.
+const GRAVITY_STEP: f32 = 0.2;
+// Relaxation steps a fresh grid gets once its anchors are set, before the simulation starts.
+const RELAX_ITERATIONS: usize = 200;
+// Horizontal space left between neighbouring patches, in world units.
+const PATCH_GAP: f32 = 5.0;
+// Upper bound on catch-up steps per update, so a slow machine falls behind instead of spiralling.
+const MAX_STEPS_PER_UPDATE: usize = 50;
+// Steps between checks for a simulation that has blown up, which scan every node.
+const NONFINITE_CHECK_INTERVAL: u64 = 10;
+// Zoom applied per line of mouse wheel scrolling.
+const ZOOM_STEP: f32 = 1.1;
+// Samples per pixel requested for the window's framebuffer.
+const MSAA_SAMPLES: u8 = 4;
+// Wireframe line widths, in pixels, cycled through with the L key.
+const LINE_WIDTHS: [f32; 4] = [1.0, 2.0, 3.0, 4.0];
+// Share of the filled mesh's colour it keeps facing away from the light.
+const AMBIENT_LIGHT: f32 = 0.3;
+// Diameter of a node in point rendering, in world units, and the pixel size range it's kept within.
+const POINT_DIAMETER: f32 = 0.3;
+const MIN_POINT_SIZE: f32 = 1.0;
+const MAX_POINT_SIZE: f32 = 32.0;
+// Keys that change the simulation rather than just the view, by the name `Input::Key` records
+// them under. Pausing and single steps are left out since a replay is run by steps anyway.
+const SIMULATION_KEYS: [&str; 24] = [
+    "g", "w", "z", "x", "r", "+", "=", "-", "_", "9", "0", "d", "a", "b", "m", "]", "[",
+    "ArrowLeft", "ArrowRight", "ArrowUp", "ArrowDown", "PageUp", "PageDown", "Tab",
+];
+// Bins the H key sorts the active patch's springs into by strain.
+const STRAIN_HISTOGRAM_BINS: usize = 10;
+// Length of a velocity line, in world units, per unit of node speed.
+const VELOCITY_SCALE: f32 = 0.1;
+const WINDOW_TITLE: &str = "600086-Lab-I Soft body physics";
+// Upper ends of the parameter panel's sliders. Stiffness starts from MIN_SPRING_COEFFICIENT, and
+// wind and gravity reach as far either way.
+#[cfg(feature = "gui")]
+const MAX_SLIDER_SPRING_COEFFICIENT: f32 = 1000.0;
+#[cfg(feature = "gui")]
+const MAX_SLIDER_DAMPING_COEFFICIENT: f32 = 1.0;
+#[cfg(feature = "gui")]
+const MAX_SLIDER_WIND: f32 = 2.0;
+#[cfg(feature = "gui")]
+const MAX_SLIDER_GRAVITY: f32 = 30.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum RenderMode {
+    Wireframe,
+    Points,
+    Filled,
+}
+
+impl RenderMode {
+    fn next(self) -> RenderMode {
+        match self {
+            RenderMode::Wireframe => RenderMode::Points,
+            RenderMode::Points => RenderMode::Filled,
+            RenderMode::Filled => RenderMode::Wireframe,
+        }
+    }
+}
+
+// Fixed-timestep accumulator: turns elapsed real time into a whole number of DELTA_TIME steps,
+// carrying the remainder into the next update.
+struct StepClock {
+    accumulator: f32,
+    last_update: Instant,
+}
+
+impl StepClock {
+    fn new() -> StepClock {
+        StepClock {
+            accumulator: 0.0,
+            last_update: Instant::now(),
+        }
+    }
+
+    fn steps_due(&mut self) -> usize {
+        let now = Instant::now();
+        self.accumulator += (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        let steps = (self.accumulator / DELTA_TIME) as usize;
+        self.accumulator -= steps as f32 * DELTA_TIME;
+        if steps > MAX_STEPS_PER_UPDATE {
+            self.accumulator = 0.0;
+            return MAX_STEPS_PER_UPDATE;
+        }
+        steps
+    }
+
+    // Publishes the carried over time for the render loop to interpolate with.
+    fn publish(&self) {
+        *CAUGHT_UP.lock().unwrap() = Some((self.last_update, self.accumulator));
+    }
+}
+
+// How far through the next step of DELTA_TIME the simulation is now, from 0 to 1. It stays at 1
+// while the simulation is behind, paused, or not run by a clock at all.
+fn step_alpha() -> f32 {
+    match *CAUGHT_UP.lock().unwrap() {
+        Some((last_update, carried)) => ((carried + last_update.elapsed().as_secs_f32()) / DELTA_TIME).min(1.0),
+        None => 1.0,
+    }
+}
+
+// Sets how far through its last step each grid is drawn. `prev_positions` only go back one force
+// calculation, which is a fraction of a step once it's split into substeps or sped up, so the
+// drawn state stays at the previous positions until that last calculation's share of the step.
+fn interpolate_render(scene: &RwLock<Scene>, alpha: f32) {
+    let advances = advances_per_step(TIME_SCALES[TIME_SCALE.load(Ordering::Relaxed)]);
+    for grid in &mut scene.write().unwrap().grids {
+        let calculations = (advances * grid.config.substeps.max(1)) as f32;
+        grid.render_alpha = (1.0 - (1.0 - alpha) * calculations).max(0.0);
+    }
+}
+
+// Returns how many steps were run, or handed to the GPU to run.
+fn update_grid<G: Simulation, R: FnMut(&mut G)>(grid: &mut G, substeps: usize, on_step: &mut R) -> usize {
+    if GPU_STEPPING.load(Ordering::Relaxed) {
+        let steps = if PAUSED.load(Ordering::Relaxed) { STEP_REQUESTED.swap(false, Ordering::Relaxed) as usize } else { substeps };
+        GPU_STEPS_DUE.fetch_add(steps, Ordering::Relaxed);
+        return steps;
+    }
+    if PAUSED.load(Ordering::Relaxed) {
+        if STEP_REQUESTED.swap(false, Ordering::Relaxed) {
+            step_grid(grid, on_step);
+            return 1;
+        }
+        return 0;
+    }
+    for step in 0..substeps {
+        // The step may have paused the simulation after finding it had blown up.
+        if PAUSED.load(Ordering::Relaxed) {
+            return step;
+        }
+        step_grid(grid, on_step);
+    }
+    substeps
+}
+
+// Running faster than real time takes several advances no longer than DELTA_TIME rather than one
+// long one, which stiff springs wouldn't survive.
+fn advances_per_step(time_scale: f32) -> usize {
+    time_scale.ceil().max(1.0) as usize
+}
+
+fn step_grid<G: Simulation, R: FnMut(&mut G)>(grid: &mut G, on_step: &mut R) {
+    let current = EXTERNAL_MAGNITUDE.load(Ordering::Relaxed);
+    let gravity = GRAVITY_ACTIVE.load(Ordering::Relaxed);
+    let time_scale = TIME_SCALES[TIME_SCALE.load(Ordering::Relaxed)];
+    let advances = advances_per_step(time_scale);
+    for _ in 0..advances {
+        grid.advance(DELTA_TIME * time_scale / advances as f32, gravity, current);
+    }
+    let steps = SIM_STEPS.fetch_add(1, Ordering::Relaxed) + 1;
+    on_step(grid);
+    if steps % NONFINITE_CHECK_INTERVAL == 0 {
+        if let Some(node) = grid.has_nonfinite() {
+            PAUSED.store(true, Ordering::Relaxed);
+            eprintln!("Node {} stopped being finite by step {}, pausing", node, steps);
+        }
+    }
+}
+
+// Runs updates until `keep_running` (given the number of updates so far) returns false,
+// returning how long each update held the grid for. `substeps` decides how many steps each update runs,
+// and `on_step` sees the grid after every one of them. Updates that ran no steps, while paused or
+// before the clock had a step due, are left out of the durations so they don't drag the averages
+// down, though `keep_running` still counts them.
+fn time_updates<G, S, F, R>(grid: &RwLock<G>, pool: &ThreadPool, mut substeps: S, pause: Duration, mut keep_running: F,
+                            mut on_step: R) -> Vec<Duration>
+where
+    G: Simulation + Send,
+    S: FnMut() -> usize,
+    F: FnMut(usize) -> bool,
+    R: FnMut(&mut G) + Send,
+{
+    let mut durations = Vec::new();
+    let mut updates = 0;
+    while keep_running(updates) {
+        updates += 1;
+        let substeps = substeps();
+        let start = Instant::now();
+        let steps = {
+            let mut guard = grid.write().unwrap();
+            // The lock guard itself can't cross into the pool, so hand over the grid it protects.
+            let grid: &mut G = &mut guard;
+            let on_step = &mut on_step;
+            pool.install(|| update_grid(grid, substeps, on_step))
+        };
+        let elapsed = start.elapsed();
+        if steps > 0 {
+            durations.push(elapsed);
+        }
+        run_log::print(Level::Debug, format_args!("Update {} ran {} steps in {:?}", updates, steps, elapsed));
+
+        if !pause.is_zero() {
+            thread::sleep(pause);
+        }
+    }
+    durations
+}
 
-fn run_threaded(grid: Arc<RwLock<Grid>>, thread_count: usize) ->  std::thread::JoinHandle<()> {
-    
-    ThreadPoolBuilder::new().num_threads(thread_count).build_global().unwrap();
+// The pool a simulation runs on, or None once it's reported why the pool couldn't be built.
+fn thread_pool(thread_count: usize) -> Option<ThreadPool> {
+    match benchmark::build_thread_pool(thread_count) {
+        Ok(pool) => Some(pool),
+        Err(error) => {
+            eprintln!("Failed to start {} simulation threads: {}", thread_count, error);
+            None
+        }
+    }
+}
 
+// The thread hands back each update's duration and how long it ran for in total. It runs for
+// `duration` seconds if given and then closes the window, or for LOG_DURATION otherwise, leaving
+// the window open.
+fn run_threaded<G, R>(grid: Arc<RwLock<G>>, pool: ThreadPool, duration: Option<f32>, on_step: R)
+                      -> thread::JoinHandle<(Vec<Duration>, Duration)>
+where
+    G: Simulation + Send + Sync + 'static,
+    R: FnMut(&mut G) + Send + 'static,
+{
     let handle = thread::spawn(move || {
-        let mut total_duration = Duration::new(0, 0);
-        let mut iterations = 0;
         let start_time = Instant::now();
+        let mut clock = StepClock::new();
+        let steps_due = || {
+            let steps = clock.steps_due();
+            clock.publish();
+            steps
+        };
+        let limit = duration.unwrap_or(LOG_DURATION);
+        let durations = time_updates(&grid, &pool, steps_due, Duration::from_secs_f32(DELTA_TIME),
+                                     |_| RUNNING.load(Ordering::Relaxed) && start_time.elapsed().as_secs_f32() < limit,
+                                     on_step);
+        if duration.is_some() {
+            RUNNING.store(false, Ordering::Relaxed);
+        }
+        (durations, start_time.elapsed())
+    });
+    handle
+}
 
-        while start_time.elapsed().as_secs() < LOG_DURATION {
-            let start = Instant::now();
-            {
-                let mut grid = grid.write().unwrap();
-                let current = EXTERNAL_MAGNITUDE.load(Ordering::Relaxed);
-                if HEIGHT < 100
-                {
-                    for _ in 0..20
-                    {
-                        
-                        if GRAVITY_ACTIVE.load(Ordering::Relaxed) {
-                            grid.calculate_forces_with_gravity(DELTA_TIME, current);
-                        } else {
-                            grid.calculate_forces(DELTA_TIME, current);
-                        }
-                    }
-                }
-                else
-                {
-                    if GRAVITY_ACTIVE.load(Ordering::Relaxed) {
-                        grid.calculate_forces_with_gravity(DELTA_TIME, current);
-                    } else {
-                        grid.calculate_forces(DELTA_TIME, current);
-                    }
-                }
+// Returns each update's duration and the total time taken, like `run_threaded`.
+fn run_headless<G: Simulation + Send>(grid: Arc<RwLock<G>>, pool: &ThreadPool, thread_count: usize) -> (Vec<Duration>, Duration) {
+    let start_time = Instant::now();
+    // One substep per update so every timing is a single force calculation.
+    let durations = time_updates(&grid, pool, || 1, Duration::ZERO, |steps| steps < BENCHMARK_STEPS, |_: &mut G| ());
+    let summary = TimingSummary::from_durations(&durations).expect("benchmark ran no steps");
+    let output = Path::new(BENCHMARK_OUTPUT);
+    let (width, height) = grid.read().unwrap().dimensions();
+    match summary.write_json(output, width, height, thread_count) {
+        Ok(()) => println!("Wrote timings for {} steps to {}", summary.steps, output.display()),
+        Err(error) => eprintln!("Failed to write {}: {}", output.display(), error),
+    }
+    (durations, start_time.elapsed())
+}
+
+// Runs the headless workload once per thread count, each from the same starting state, and prints
+// how the average step time scales. Every run gets its own pool since the global one is fixed once built.
+fn bench_threads(scene: &RwLock<Scene>) {
+    let mut runs = vec![];
+    for threads in benchmark::thread_counts(num_cpus::get()) {
+        scene.write().unwrap().grids.iter_mut().for_each(Grid::reset);
+        // A run that blew up paused itself, which would leave the next one with nothing to time.
+        PAUSED.store(false, Ordering::Relaxed);
+        let Some(pool) = thread_pool(threads) else {
+            continue;
+        };
+        let durations = time_updates(scene, &pool, || 1, Duration::ZERO, |steps| steps < BENCHMARK_STEPS, |_: &mut Scene| ());
+        run_log::print(Level::Info, format_args!("Ran {} steps with {} threads", durations.len(), threads));
+        runs.push((threads, mean_duration(&durations)));
+    }
+    print!("{}", benchmark::speedup_table(&runs));
+}
+
+// Prints what a windowed run did once its simulation thread has finished, with the simulated time
+// and final energy for simulations that track them.
+fn print_summary((durations, elapsed): &(Vec<Duration>, Duration), sim_time: Option<f32>, energy: Option<f32>) {
+    if !run_log::enabled(Level::Info) {
+        return;
+    }
+    let summary = RunSummary {
+        steps: SIM_STEPS.load(Ordering::Relaxed),
+        elapsed: *elapsed,
+        updates: TimingSummary::from_durations(durations),
+        sim_time,
+        peak_memory: benchmark::peak_memory(),
+        energy,
+    };
+    print!("{}", summary.report());
+}
+
+fn mean_duration(durations: &[Duration]) -> Duration {
+    durations.iter().sum::<Duration>() / durations.len().max(1) as u32
+}
+
+// Appends the run's parameters and average update time to the --log-file, if one was given.
+fn log_run(options: &Options, mode: &'static str, (width, height): (usize, usize), config: SimulationConfig,
+           (durations, elapsed): (Vec<Duration>, Duration)) {
+    let Some(path) = options.log_file.as_deref() else {
+        return;
+    };
+    let record = RunRecord {
+        mode,
+        width,
+        height,
+        patches: if options.three_d { 1 } else { options.patches },
+        threads: options.threads,
+        config,
+        updates: durations.len(),
+        seconds: elapsed.as_secs_f32(),
+        mean_update: mean_duration(&durations),
+    };
+    match RunLog::open(path, options.log_format).and_then(|mut log| log.write(&record)) {
+        Ok(()) => run_log::print(Level::Info, format_args!("Logged run to {}", path)),
+        Err(error) => eprintln!("Failed to write {}: {}", path, error),
+    }
+}
+
+// Requests a multisampled framebuffer so lines and edges are anti-aliased.
+fn build_window(event_loop: &winit::event_loop::EventLoop<()>) -> (winit::window::Window, glium::Display<WindowSurface>) {
+    glium::backend::glutin::SimpleWindowBuilder::new()
+        .with_title(WINDOW_TITLE)
+        .with_inner_size(800, 800)
+        .with_config_template_builder(ConfigTemplateBuilder::new().with_multisampling(MSAA_SAMPLES))
+        .build(event_loop)
+}
+
+// What input has done so far that later input depends on. The window keeps one for what it's
+// given, and a replay keeps its own for what it re-applies.
+struct Interaction {
+    // The patch keys change, which Tab cycles through.
+    active: usize,
+    // The grid and node being dragged, along with the node's fixed state before it was grabbed.
+    grabbed: Option<(usize, usize, bool)>,
+    // Whether the brush being dragged pins or unpins, and how many nodes it has changed so far.
+    painting: Option<(bool, usize)>,
+    // Where the last input left the cursor, which a brush stroke carries on from.
+    cursor: Vec2,
+    ripple: Ripple,
+    brush_radius: f32,
+}
+
+impl Interaction {
+    fn new(ripple: Ripple, brush_radius: f32) -> Interaction {
+        Interaction {
+            active: 0,
+            grabbed: None,
+            painting: None,
+            cursor: Vec2::ZERO,
+            ripple,
+            brush_radius,
+        }
+    }
+
+    // Whether a node or the brush is being dragged, so cursor moves and the release matter.
+    fn dragging(&self) -> bool {
+        self.grabbed.is_some() || self.painting.is_some()
+    }
+}
+
+// The name a key that changes the simulation is recorded under, or None for keys that only change the view.
+fn simulation_key(key: &winit::keyboard::Key) -> Option<String> {
+    let name = match key {
+        winit::keyboard::Key::Character(c) => c.to_lowercase(),
+        winit::keyboard::Key::Named(named) => format!("{:?}", named),
+        _ => return None,
+    };
+    SIMULATION_KEYS.contains(&name.as_str()).then_some(name)
+}
+
+// Applies `input` under the scene's lock and, when recording, logs it with the steps taken so far.
+// Steps run under the same lock, so a replay can put it back between exactly the same two steps.
+fn handle_input(scene: &RwLock<Scene>, interaction: &mut Interaction, recorder: &mut Option<InputRecorder>, input: Input) {
+    let mut scene = scene.write().unwrap();
+    if let Some(active) = recorder.as_mut() {
+        if let Err(error) = active.record(SIM_STEPS.load(Ordering::Relaxed), &input) {
+            eprintln!("Stopped recording input: {}", error);
+            *recorder = None;
+        }
+    }
+    apply_input(&mut scene, interaction, &input);
+}
+
+// Applies the replayed inputs recorded by `step`, the number of steps taken so far, in their order.
+fn replay_due(scene: &mut Scene, interaction: &mut Interaction, inputs: &mut Peekable<std::vec::IntoIter<(u64, Input)>>, step: u64) {
+    while let Some((_, input)) = inputs.next_if(|(due, _)| *due <= step) {
+        apply_input(scene, interaction, &input);
+    }
+}
+
+fn apply_input(scene: &mut Scene, interaction: &mut Interaction, input: &Input) {
+    match *input {
+        Input::Key(ref key) => apply_key(scene, interaction, key),
+        Input::Grab(position) => {
+            interaction.cursor = position;
+            if let Some((grid_index, index)) = scene.nearest_node(position) {
+                let grid = &mut scene.grids[grid_index];
+                // Hold the node as fixed so the simulation doesn't pull it away between moves.
+                interaction.grabbed = Some((grid_index, index, grid.fixed[index]));
+                grid.fixed[index] = true;
+                grid.drag_node(index, position);
+            }
+        }
+        Input::Move(position) => {
+            if let Some((fixed, changed)) = &mut interaction.painting {
+                *changed += scene.paint_fixed(interaction.cursor, position, interaction.brush_radius, *fixed);
+            }
+            if let Some((grid_index, index, _)) = interaction.grabbed {
+                scene.grids[grid_index].drag_node(index, position);
+            }
+            interaction.cursor = position;
+        }
+        Input::Release => {
+            if let Some((fixed, changed)) = interaction.painting.take() {
+                println!("Brush {} {} nodes", if fixed { "pinned" } else { "unpinned" }, changed);
+            }
+            if let Some((grid_index, index, was_fixed)) = interaction.grabbed.take() {
+                scene.grids[grid_index].fixed[index] = was_fixed;
+            }
+        }
+        Input::Ripple(position) => {
+            interaction.cursor = position;
+            for grid in &mut scene.grids {
+                let step_time = DELTA_TIME / grid.config.substeps.max(1) as f32;
+                grid.apply_ripple(position, interaction.ripple, step_time);
             }
-            let duration = start.elapsed();
-            total_duration += duration;
-            iterations += 1;
+        }
+        Input::Paint { position, fixed } => {
+            interaction.cursor = position;
+            let changed = scene.paint_fixed(position, position, interaction.brush_radius, fixed);
+            interaction.painting = Some((fixed, changed));
+        }
+        Input::TogglePin(position) => {
+            interaction.cursor = position;
+            if let Some((grid_index, index)) = scene.nearest_node(position) {
+                let pinned = scene.grids[grid_index].toggle_fixed(index);
+                println!("Patch {} node {} pinned: {}", grid_index, index, pinned);
+            }
+        }
+    }
+}
+
+// The keys in SIMULATION_KEYS. Keys that change a parameter only change it on the active patch.
+fn apply_key(scene: &mut Scene, interaction: &mut Interaction, key: &str) {
+    let active = interaction.active;
+    match key {
+        "g" => {
+            let current_state = GRAVITY_ACTIVE.load(Ordering::Relaxed);
+            GRAVITY_ACTIVE.store(!current_state, Ordering::Relaxed);
+            println!("Gravity toggled: {}", !current_state);
+        }
+        "w" => {
+            let current_state = EXTERNAL_MAGNITUDE.load(Ordering::Relaxed);
+            EXTERNAL_MAGNITUDE.store(!current_state, Ordering::Relaxed);
+            println!("External toggled: {}", !current_state);
+        }
+        "z" | "x" => {
+            let index = TIME_SCALE.load(Ordering::Relaxed);
+            let index = if key == "x" { (index + 1).min(TIME_SCALES.len() - 1) } else { index.saturating_sub(1) };
+            TIME_SCALE.store(index, Ordering::Relaxed);
+            println!("Time scale: {}x", TIME_SCALES[index]);
+        }
+        "r" => {
+            interaction.grabbed = None;
+            scene.grids.iter_mut().for_each(Grid::reset);
+            println!("Grid reset");
+        }
+        "ArrowLeft" | "ArrowRight" | "ArrowUp" | "ArrowDown" => {
+            let change = match key {
+                "ArrowLeft" => Vec2::new(-WIND_STEP, 0.0),
+                "ArrowRight" => Vec2::new(WIND_STEP, 0.0),
+                "ArrowUp" => Vec2::new(0.0, WIND_STEP),
+                _ => Vec2::new(0.0, -WIND_STEP),
+            };
+            let config = &mut scene.grids[active].config;
+            config.wind += change;
+            println!("Wind: ({:.2}, {:.2})", config.wind.x, config.wind.y);
+        }
+        // "=" shares a key with "+" on most layouts, so accept it without shift too.
+        "+" | "=" | "-" | "_" => {
+            let factor = if key == "+" || key == "=" { STIFFNESS_STEP } else { 1.0 / STIFFNESS_STEP };
+            let config = &mut scene.grids[active].config;
+            config.spring_coefficient = (config.spring_coefficient * factor).max(MIN_SPRING_COEFFICIENT);
+            // The row and column stiffnesses keep their ratio to it.
+            for stiffness in [&mut config.stiffness_x, &mut config.stiffness_y].into_iter().flatten() {
+                *stiffness = (*stiffness * factor).max(MIN_SPRING_COEFFICIENT);
+            }
+            println!("Spring coefficient: {:.3}", config.spring_coefficient);
+        }
+        "9" | "0" => {
+            let config = &mut scene.grids[active].config;
+            config.damping_coefficient = if key == "0" {
+                (config.damping_coefficient * DAMPING_STEP).max(MIN_DAMPING_COEFFICIENT)
+            } else {
+                let lowered = config.damping_coefficient / DAMPING_STEP;
+                if lowered < MIN_DAMPING_COEFFICIENT { 0.0 } else { lowered }
+            };
+            println!("Damping coefficient: {:.4}", config.damping_coefficient);
+        }
+        "PageUp" | "PageDown" => {
+            let change = if key == "PageUp" { -GRAVITY_STEP } else { GRAVITY_STEP };
+            for grid in &mut scene.grids {
+                grid.config.gravity.y += change;
+            }
+            // Reported as a downward strength, so Earth reads 9.81 and upward is negative.
+            println!("Gravity: {:.2} m/s^2", -scene.grids[active].config.gravity.y);
+        }
+        "d" => {
+            for grid in &mut scene.grids {
+                let step_time = DELTA_TIME / grid.config.substeps.max(1) as f32;
+                grid.apply_impulse(DROP_IMPULSE, step_time);
+            }
+            println!("Applied impulse ({:.2}, {:.2})", DROP_IMPULSE.x, DROP_IMPULSE.y);
+        }
+        "a" => {
+            let config = &mut scene.grids[active].config;
+            config.quadratic_drag = !config.quadratic_drag;
+            println!("Quadratic drag toggled: {}", config.quadratic_drag);
+        }
+        "b" => {
+            let grid = &mut scene.grids[active];
+            grid.bending = !grid.bending;
+            println!("Bending springs toggled: {}", grid.bending);
+        }
+        "m" => {
+            scene.grids[active].set_rest_from_current();
+            println!("Patch {} relaxed in its current shape", active);
+        }
+        "]" | "[" => {
+            // Indices shift when a row changes, so let go of any grabbed node first.
+            if let Some((grid_index, index, was_fixed)) = interaction.grabbed.take() {
+                scene.grids[grid_index].fixed[index] = was_fixed;
+            }
+            let grid = &mut scene.grids[active];
+            if key == "]" {
+                grid.add_bottom_row();
+            } else if !grid.remove_bottom_row() {
+                println!("Can't remove the last row");
+            }
+            println!("Grid size: {}x{}", grid.width, grid.height);
+        }
+        "Tab" => {
+            interaction.active = (active + 1) % scene.grids.len();
+            println!("Active patch: {}", interaction.active);
+        }
+        _ => eprintln!("Warning: ignoring unknown key {}", key),
+    }
+}
+
+// Changes the config of one patch under the scene's lock, returning the result for reporting.
+#[cfg(feature = "gui")]
+fn update_config<F: FnOnce(&mut SimulationConfig)>(scene: &RwLock<Scene>, patch: usize, change: F) -> SimulationConfig {
+    let mut scene = scene.write().unwrap();
+    let config = &mut scene.grids[patch].config;
+    change(config);
+    *config
+}
+
+// The theme's line colour as shader uniforms: the colour, and how much of it replaces each vertex's own.
+fn line_uniforms(theme: Theme) -> ([f32; 3], f32) {
+    match theme.line_color() {
+        Some((red, green, blue)) => ([red, green, blue], 1.0),
+        None => ([0.0; 3], 0.0),
+    }
+}
 
-            //println!("Time taken for update with {} threads: {:?}", thread_count, duration);
-            thread::sleep(Duration::from_secs_f32(DELTA_TIME));
+// Sliders for the active patch's parameters and toggles for the view, drawn over the cloth each frame.
+// They change the same settings as the keybinds, which keep working alongside them, so gravity,
+// the strain display and the pin highlight apply to every patch here too. Unlike the keybinds,
+// what's changed here isn't written to an input log.
+#[cfg(feature = "gui")]
+fn parameter_panel(ctx: &egui::Context, scene: &RwLock<Scene>, active: usize, render_mode: &mut RenderMode,
+                   show_velocities: &mut bool, theme: &mut Theme) {
+    egui::Window::new("Parameters").show(ctx, |ui| {
+        let (mut config, mut strain_display, mut highlight_fixed) = {
+            let scene = scene.read().unwrap();
+            let grid = &scene.grids[active];
+            (grid.config, grid.strain_display, grid.highlight_fixed)
+        };
+
+        ui.heading(format!("Patch {}", active));
+        let mut changed = false;
+        changed |= ui.add(egui::Slider::new(&mut config.spring_coefficient, MIN_SPRING_COEFFICIENT..=MAX_SLIDER_SPRING_COEFFICIENT)
+            .logarithmic(true).text("Stiffness")).changed();
+        changed |= ui.add(egui::Slider::new(&mut config.damping_coefficient, 0.0..=MAX_SLIDER_DAMPING_COEFFICIENT)
+            .text("Damping")).changed();
+        changed |= ui.add(egui::Slider::new(&mut config.wind.x, -MAX_SLIDER_WIND..=MAX_SLIDER_WIND).text("Wind x")).changed();
+        changed |= ui.add(egui::Slider::new(&mut config.wind.y, -MAX_SLIDER_WIND..=MAX_SLIDER_WIND).text("Wind y")).changed();
+        if changed {
+            update_config(scene, active, |current| *current = config);
+        }
+
+        ui.heading("Simulation");
+        // Shown as a downward strength, like the Page Up/Page Down report.
+        let mut gravity = -config.gravity.y;
+        if ui.add(egui::Slider::new(&mut gravity, -MAX_SLIDER_GRAVITY..=MAX_SLIDER_GRAVITY).text("Gravity (m/s^2)")).changed() {
+            scene.write().unwrap().grids.iter_mut().for_each(|grid| grid.config.gravity.y = -gravity);
+        }
+        let mut time_scale = TIME_SCALE.load(Ordering::Relaxed);
+        let slider = egui::Slider::new(&mut time_scale, 0..=TIME_SCALES.len() - 1)
+            .custom_formatter(|index, _| format!("{}x", TIME_SCALES[index as usize]))
+            .text("Time scale");
+        if ui.add(slider).changed() {
+            TIME_SCALE.store(time_scale, Ordering::Relaxed);
+        }
+        for (flag, label) in [(&GRAVITY_ACTIVE, "Gravity"), (&EXTERNAL_MAGNITUDE, "Random force"), (&PAUSED, "Paused")] {
+            let mut value = flag.load(Ordering::Relaxed);
+            if ui.checkbox(&mut value, label).changed() {
+                flag.store(value, Ordering::Relaxed);
+            }
         }
 
-        let average_duration = total_duration / iterations;
-        println!("Average time taken for update with {} threads over {} seconds: {:?}", 
-        thread_count, LOG_DURATION, average_duration);
+        ui.heading("View");
+        ui.horizontal(|ui| {
+            ui.radio_value(render_mode, RenderMode::Wireframe, "Wireframe");
+            ui.radio_value(render_mode, RenderMode::Points, "Points");
+            ui.radio_value(render_mode, RenderMode::Filled, "Filled");
+        });
+        egui::ComboBox::from_label("Theme").selected_text(format!("{:?}", theme)).show_ui(ui, |ui| {
+            let themes = std::iter::successors(Some(Theme::default()), |theme| Some(theme.next()).filter(|&next| next != Theme::default()));
+            for option in themes {
+                ui.selectable_value(theme, option, format!("{:?}", option));
+            }
+        });
+        let mut signed = strain_display == StrainDisplay::Signed;
+        let mut changed = ui.checkbox(&mut signed, "Signed strain").changed();
+        strain_display = if signed { StrainDisplay::Signed } else { StrainDisplay::Magnitude };
+        changed |= ui.checkbox(&mut highlight_fixed, "Highlight pinned nodes").changed();
+        if changed {
+            for grid in &mut scene.write().unwrap().grids {
+                grid.strain_display = strain_display;
+                grid.highlight_fixed = highlight_fixed;
+            }
+        }
+        ui.checkbox(show_velocities, "Velocity field");
     });
-    handle
 }
 
-fn render(grid: Arc<RwLock<Grid>>) {
-    //rendering taken from triangles lab
+fn render_3d(grid: Arc<RwLock<Grid3D>>, theme: Theme) {
     let event_loop = winit::event_loop::EventLoopBuilder::new().build().expect("event loop building");
-    let (_window, display) = glium::backend::glutin::SimpleWindowBuilder::new().with_title("600086-Lab-I Soft body physics").with_inner_size(800, 800).build(&event_loop);
+    let (_window, display) = build_window(&event_loop);
 
-    implement_vertex!(Vertex, position);
     let indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
 
     pub const VERT_SHADER: &str = r#"
     #version 140
 
-    in vec2 position;
+    uniform mat4 matrix;
+
+    in vec3 position;
+    in vec3 color;
+    out vec3 v_color;
 
     void main() {
-        gl_Position = vec4(position, 0.0, 25.0);
+        v_color = color;
+        gl_Position = matrix * vec4(position, 1.0);
     }
     "#;
 
+    // Lines and points take the theme's colour instead of their own when it has one.
     pub const FRAG_SHADER: &str = r#"
     #version 140
 
+    uniform vec3 line_color;
+    uniform float line_mix;
+
+    in vec3 v_color;
     out vec4 color;
 
     void main() {
-        color = vec4(1.0, 1.0, 1.0, 1.0);
+        color = vec4(mix(v_color, line_color, line_mix), 1.0);
     }
     "#;
 
     let program = glium::Program::from_source(&display, VERT_SHADER, FRAG_SHADER, None).unwrap();
 
+    let mut camera = OrbitCamera::default();
+    let mut orbiting = false;
+    let mut cursor_screen = (0.0f32, 0.0f32);
+
     let _ = event_loop.run(move |event, window_target| {
         match event {
             winit::event::Event::WindowEvent { event, .. } => match event {
-                winit::event::WindowEvent::CloseRequested => window_target.exit(),
+                winit::event::WindowEvent::CloseRequested => {
+                    RUNNING.store(false, Ordering::Relaxed);
+                    window_target.exit();
+                },
                 winit::event::WindowEvent::Resized(window_size) => {
                     display.resize(window_size.into());
                 },
+                winit::event::WindowEvent::CursorMoved { position, .. } => {
+                    let screen = (position.x as f32, position.y as f32);
+                    if orbiting {
+                        camera.orbit(screen.0 - cursor_screen.0, screen.1 - cursor_screen.1);
+                    }
+                    cursor_screen = screen;
+                },
+                winit::event::WindowEvent::MouseInput { state, button: winit::event::MouseButton::Left, .. } => {
+                    orbiting = state == winit::event::ElementState::Pressed;
+                },
+                winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                    let lines = match delta {
+                        winit::event::MouseScrollDelta::LineDelta(_, y) => y,
+                        winit::event::MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+                    };
+                    camera.zoom(ZOOM_STEP.powf(lines));
+                },
                 winit::event::WindowEvent::KeyboardInput { event, .. } => {
                     if event.state == winit::event::ElementState::Pressed && !event.repeat {
                         match event.logical_key {
                             winit::keyboard::Key::Character(c) if c == "G" || c == "g" => {
                                 let current_state = GRAVITY_ACTIVE.load(Ordering::Relaxed);
-                                    GRAVITY_ACTIVE.store(!current_state, Ordering::Relaxed);
-                                    println!("Gravity toggled: {}", !current_state);
+                                GRAVITY_ACTIVE.store(!current_state, Ordering::Relaxed);
+                                println!("Gravity toggled: {}", !current_state);
                             }
                             winit::keyboard::Key::Character(c) if c == "W" || c == "w" => {
                                 let current_state = EXTERNAL_MAGNITUDE.load(Ordering::Relaxed);
-                                    EXTERNAL_MAGNITUDE.store(!current_state, Ordering::Relaxed);
-                                    println!("External toggled: {}", !current_state);
+                                EXTERNAL_MAGNITUDE.store(!current_state, Ordering::Relaxed);
+                                println!("External toggled: {}", !current_state);
+                            }
+                            winit::keyboard::Key::Named(winit::keyboard::NamedKey::Space) => {
+                                let current_state = PAUSED.load(Ordering::Relaxed);
+                                PAUSED.store(!current_state, Ordering::Relaxed);
+                                println!("Paused: {}", !current_state);
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                winit::event::WindowEvent::RedrawRequested => {
+                    let (width, height) = display.get_framebuffer_dimensions();
+                    let (line_color, line_mix) = line_uniforms(theme);
+                    let uniforms = uniform! {
+                        matrix: camera.view_projection(width as f32 / height.max(1) as f32),
+                        line_color: line_color,
+                        line_mix: line_mix,
+                    };
+
+                    let vertex_buffer = glium::VertexBuffer::new(&display, &grid.read().unwrap().create_grid()).unwrap();
+
+                    let mut target = display.draw();
+                    let (red, green, blue) = theme.background();
+                    target.clear_color(red, green, blue, 1.0);
+                    target.draw(&vertex_buffer, &indices, &program, &uniforms, &Default::default()).unwrap();
+                    target.finish().unwrap();
+                },
+                _ => (),
+            },
+            // A run given a --duration closes the window once it's over.
+            winit::event::Event::AboutToWait if !RUNNING.load(Ordering::Relaxed) => window_target.exit(),
+            winit::event::Event::AboutToWait => {
+                _window.request_redraw();
+            },
+            _ => (),
+        };
+    });
+}
+
+fn run_3d(options: &Options) {
+    let grid = Arc::new(RwLock::new(Grid3D::new(options.width, options.height)));
+    {
+        let mut grid = grid.write().unwrap();
+        grid.config.substeps = options.substeps;
+        grid.get_neighbors();
+        let fixed_1 = grid.get_index(0, options.height - 1);
+        let fixed_2 = grid.get_index(options.width - 1, options.height - 1);
+        grid.fixed[fixed_1] = true;
+        grid.fixed[fixed_2] = true;
+    }
+
+    println!("Running 3D simulation on a {}x{} grid with {} threads", options.width, options.height, options.threads);
+
+    let config = grid.read().unwrap().config;
+    let dimensions = (options.width, options.height);
+    let Some(pool) = thread_pool(options.threads) else {
+        return;
+    };
+    if options.headless {
+        let timing = run_headless(grid, &pool, options.threads);
+        log_run(options, "3d-headless", dimensions, config, timing);
+        return;
+    }
+
+    let sim_handle = run_threaded(grid.clone(), pool, options.duration, |_: &mut Grid3D| ());
+    render_3d(grid, options.theme);
+    RUNNING.store(false, Ordering::Relaxed);
+    let timing = sim_handle.join().unwrap();
+    print_summary(&timing, None, None);
+    log_run(options, "3d-windowed", dimensions, config, timing);
+}
+
+// Shift-clicking sends a ripple out from the cursor, and dragging with ctrl or alt held pins or
+// unpins every node within the brush radius of it. Whatever changes the simulation goes through
+// `interaction`, and into `input_recorder` when there is one.
+fn render(scene: Arc<RwLock<Scene>>, mut interaction: Interaction, mut input_recorder: Option<InputRecorder>, mut theme: Theme,
+          light: (f32, f32, f32), gpu: bool) {
+    //rendering taken from triangles lab
+    let event_loop = winit::event_loop::EventLoopBuilder::new().build().expect("event loop building");
+    let (_window, display) = build_window(&event_loop);
+
+    let line_indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
+    let triangle_indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
+    let point_indices = glium::index::NoIndices(glium::index::PrimitiveType::Points);
+
+    pub const VERT_SHADER: &str = r#"
+    #version 140
+
+    uniform vec2 offset;
+    uniform float scale;
+
+    in vec2 position;
+    in vec3 color;
+    in vec3 normal;
+    out vec3 v_color;
+    out vec3 v_normal;
+
+    void main() {
+        v_color = color;
+        v_normal = normal;
+        gl_Position = vec4((position + offset) * scale, 0.0, 1.0);
+    }
+    "#;
+
+    // Lines and points take the theme's colour instead of their own when it has one.
+    pub const FRAG_SHADER: &str = r#"
+    #version 140
+
+    uniform vec3 line_color;
+    uniform float line_mix;
+
+    in vec3 v_color;
+    out vec4 color;
+
+    void main() {
+        color = vec4(mix(v_color, line_color, line_mix), 1.0);
+    }
+    "#;
+
+    // Cells keep their winding unless the cloth folds over itself, so back faces are shaded darker.
+    // The light falls on each by its normal, and `ambient` of it reaches every side.
+    pub const FILL_FRAG_SHADER: &str = r#"
+    #version 140
+
+    uniform vec3 light_direction;
+    uniform float ambient;
+
+    in vec3 v_color;
+    in vec3 v_normal;
+    out vec4 color;
+
+    void main() {
+        float diffuse = max(dot(normalize(v_normal), light_direction), 0.0);
+        vec3 lit = v_color * (ambient + (1.0 - ambient) * diffuse);
+        color = vec4(gl_FrontFacing ? lit : 0.5 * lit, 1.0);
+    }
+    "#;
+
+    let program = glium::Program::from_source(&display, VERT_SHADER, FRAG_SHADER, None).unwrap();
+    let fill_program = glium::Program::from_source(&display, VERT_SHADER, FILL_FRAG_SHADER, None).unwrap();
+    let mut render_mode = RenderMode::Wireframe;
+    let mut show_velocities = false;
+    let mut line_width_index = 0;
+
+    let mut camera = Camera::default();
+    let mut panning = false;
+    let mut cursor_screen = (0.0f32, 0.0f32);
+    let mut cursor_position = Vec2::ZERO;
+    let mut rate_window_start = Instant::now();
+    let mut frames_in_window = 0u32;
+    let mut steps_at_window_start = SIM_STEPS.load(Ordering::Relaxed);
+    let mut modifiers = winit::keyboard::ModifiersState::empty();
+    // Set by the screenshot key, and cleared once the next frame has been saved.
+    let mut screenshot_requested = false;
+    #[cfg(feature = "gui")]
+    let mut gui = EguiGlium::new(egui::ViewportId::ROOT, &display, &_window, &event_loop);
+    let mut gpu_steppers = if gpu { start_gpu(&display, &scene) } else { None };
+    // Keys can change the springs, which the GPU only gets sent again when asked to.
+    let mut springs_changed = false;
+
+    let _ = event_loop.run(move |event, window_target| {
+        match event {
+            // Input the parameter panel takes, like dragging one of its sliders, doesn't reach the cloth.
+            #[cfg(feature = "gui")]
+            winit::event::Event::WindowEvent { ref event, .. } if gui.on_event(&_window, event).consumed => {}
+            winit::event::Event::WindowEvent { event, .. } => match event {
+                winit::event::WindowEvent::CloseRequested => {
+                    RUNNING.store(false, Ordering::Relaxed);
+                    window_target.exit();
+                },
+                winit::event::WindowEvent::Resized(window_size) => {
+                    display.resize(window_size.into());
+                },
+                winit::event::WindowEvent::CursorMoved { position, .. } => {
+                    let window_size = _window.inner_size();
+                    let window_size = (window_size.width as f32, window_size.height as f32);
+                    let screen = (position.x as f32, position.y as f32);
+                    if panning {
+                        camera.pan(cursor_screen, screen, window_size);
+                    }
+                    cursor_screen = screen;
+                    cursor_position = camera.screen_to_world(cursor_screen, window_size).into();
+                    if interaction.dragging() {
+                        handle_input(&scene, &mut interaction, &mut input_recorder, Input::Move(cursor_position));
+                    }
+                },
+                winit::event::WindowEvent::ModifiersChanged(new_modifiers) => {
+                    modifiers = new_modifiers.state();
+                },
+                winit::event::WindowEvent::MouseInput { state, button: winit::event::MouseButton::Left, .. } => {
+                    let input = match state {
+                        winit::event::ElementState::Pressed if modifiers.shift_key() => Some(Input::Ripple(cursor_position)),
+                        winit::event::ElementState::Pressed if modifiers.control_key() || modifiers.alt_key() => {
+                            Some(Input::Paint { position: cursor_position, fixed: modifiers.control_key() })
+                        }
+                        winit::event::ElementState::Pressed => Some(Input::Grab(cursor_position)),
+                        winit::event::ElementState::Released => interaction.dragging().then_some(Input::Release),
+                    };
+                    if let Some(input) = input {
+                        handle_input(&scene, &mut interaction, &mut input_recorder, input);
+                    }
+                },
+                winit::event::WindowEvent::MouseInput { state, button: winit::event::MouseButton::Middle, .. } => {
+                    panning = state == winit::event::ElementState::Pressed;
+                },
+                winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                    let lines = match delta {
+                        winit::event::MouseScrollDelta::LineDelta(_, y) => y,
+                        winit::event::MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+                    };
+                    let window_size = _window.inner_size();
+                    let window_size = (window_size.width as f32, window_size.height as f32);
+                    camera.zoom(ZOOM_STEP.powf(lines), cursor_screen, window_size);
+                    cursor_position = camera.screen_to_world(cursor_screen, window_size).into();
+                },
+                winit::event::WindowEvent::MouseInput { state: winit::event::ElementState::Pressed, button: winit::event::MouseButton::Right, .. } => {
+                    handle_input(&scene, &mut interaction, &mut input_recorder, Input::TogglePin(cursor_position));
+                },
+                winit::event::WindowEvent::KeyboardInput { event, .. } => {
+                    if event.state == winit::event::ElementState::Pressed && !event.repeat {
+                        if let Some(key) = simulation_key(&event.logical_key) {
+                            handle_input(&scene, &mut interaction, &mut input_recorder, Input::Key(key));
+                            springs_changed = true;
+                            return;
+                        }
+                        let active = interaction.active;
+                        match event.logical_key {
+                            winit::keyboard::Key::Named(winit::keyboard::NamedKey::Space) => {
+                                let current_state = PAUSED.load(Ordering::Relaxed);
+                                PAUSED.store(!current_state, Ordering::Relaxed);
+                                println!("Paused: {}", !current_state);
+                            }
+                            winit::keyboard::Key::Character(c) if c == "." => {
+                                if PAUSED.load(Ordering::Relaxed) {
+                                    STEP_REQUESTED.store(true, Ordering::Relaxed);
+                                }
+                            }
+                            winit::keyboard::Key::Named(winit::keyboard::NamedKey::F12) => {
+                                screenshot_requested = true;
+                            }
+                            #[cfg(feature = "snapshot")]
+                            winit::keyboard::Key::Named(winit::keyboard::NamedKey::F5) => {
+                                let path = Path::new(STATE_FILE);
+                                match snapshot::save(&scene.read().unwrap().grids[active], path) {
+                                    Ok(()) => println!("Saved state to {}", path.display()),
+                                    Err(error) => eprintln!("Failed to save {}: {}", path.display(), error),
+                                }
+                            }
+                            winit::keyboard::Key::Character(c) if c == "O" || c == "o" => {
+                                let scene = scene.read().unwrap();
+                                let grid = &scene.grids[active];
+                                let path = format!("frame_{}.obj", grid.step_count);
+                                match grid.export_obj(&path) {
+                                    Ok(()) => println!("Exported mesh to {}", path),
+                                    Err(error) => eprintln!("Failed to export {}: {}", path, error),
+                                }
+                            }
+                            winit::keyboard::Key::Character(c) if c == "L" || c == "l" => {
+                                line_width_index = (line_width_index + 1) % LINE_WIDTHS.len();
+                                println!("Line width: {}px", LINE_WIDTHS[line_width_index]);
+                            }
+                            winit::keyboard::Key::Character(c) if c == "F" || c == "f" => {
+                                match scene.read().unwrap().bounding_box() {
+                                    Some((min, max)) => camera.fit(min.into(), max.into()),
+                                    None => println!("Nothing to fit the view to"),
+                                }
+                            }
+                            winit::keyboard::Key::Character(c) if c == "I" || c == "i" => {
+                                let scene = scene.read().unwrap();
+                                let Some((grid_index, index)) = scene.nearest_node(cursor_position) else {
+                                    println!("No node to inspect");
+                                    return;
+                                };
+                                let gravity = GRAVITY_ACTIVE.load(Ordering::Relaxed);
+                                let external = EXTERNAL_MAGNITUDE.load(Ordering::Relaxed);
+                                let forces = scene.grids[grid_index].debug_forces(index, gravity, external);
+                                println!("Patch {} node {} forces:", grid_index, index);
+                                for (name, force) in [("spring", forces.spring), ("pressure", forces.pressure), ("damping", forces.damping), ("drag", forces.drag),
+                                                      ("wind", forces.wind), ("gravity", forces.gravity),
+                                                      ("external", forces.external), ("field", forces.field), ("total", forces.total())] {
+                                    println!("  {:<8} ({:.4}, {:.4})", name, force.x, force.y);
+                                }
+                            }
+                            winit::keyboard::Key::Character(c) if c == "K" || c == "k" => {
+                                theme = theme.next();
+                                println!("Theme: {:?}", theme);
+                            }
+                            winit::keyboard::Key::Character(c) if c == "T" || c == "t" => {
+                                render_mode = render_mode.next();
+                                println!("Render mode: {:?}", render_mode);
+                            }
+                            winit::keyboard::Key::Character(c) if c == "H" || c == "h" => {
+                                let counts = scene.read().unwrap().grids[active].strain_histogram(STRAIN_HISTOGRAM_BINS);
+                                let bin_width = 2.0 * STRAIN_HISTOGRAM_RANGE / STRAIN_HISTOGRAM_BINS as f32;
+                                println!("Patch {} springs by strain:", active);
+                                for (bin, count) in counts.iter().enumerate() {
+                                    let low = bin as f32 * bin_width - STRAIN_HISTOGRAM_RANGE;
+                                    println!("  {:+.2} to {:+.2}: {}", low, low + bin_width, count);
+                                }
+                            }
+                            winit::keyboard::Key::Character(c) if c == "C" || c == "c" => {
+                                let mut scene = scene.write().unwrap();
+                                let display = match scene.grids[active].strain_display {
+                                    StrainDisplay::Magnitude => StrainDisplay::Signed,
+                                    StrainDisplay::Signed => StrainDisplay::Magnitude,
+                                };
+                                scene.grids.iter_mut().for_each(|grid| grid.strain_display = display);
+                                println!("Strain display: {:?}", display);
+                            }
+                            winit::keyboard::Key::Character(c) if c == "P" || c == "p" => {
+                                let mut scene = scene.write().unwrap();
+                                let highlight = !scene.grids[active].highlight_fixed;
+                                scene.grids.iter_mut().for_each(|grid| grid.highlight_fixed = highlight);
+                                println!("Pinned node highlight toggled: {}", highlight);
+                            }
+                            winit::keyboard::Key::Character(c) if c == "V" || c == "v" => {
+                                show_velocities = !show_velocities;
+                                println!("Velocity field toggled: {}", show_velocities);
                             }
                             _ => (),
                         }
@@ -132,16 +1121,104 @@ fn render(grid: Arc<RwLock<Grid>>) {
                 winit::event::WindowEvent::RedrawRequested => {
                     let next_frame_time = std::time::Instant::now() + Duration::from_secs(DELTA_TIME as u64);
                     winit::event_loop::ControlFlow::WaitUntil(next_frame_time);
+                    if let Some(steppers) = &mut gpu_steppers {
+                        let steps = GPU_STEPS_DUE.swap(0, Ordering::Relaxed);
+                        if steps > 0 {
+                            if let Err(error) = step_on_gpu(&display, &scene, steppers, steps, springs_changed) {
+                                eprintln!("Stepping on the CPU from here on: {}", error);
+                                gpu_steppers = None;
+                                GPU_STEPPING.store(false, Ordering::Relaxed);
+                            }
+                            springs_changed = false;
+                        }
+                    }
+                    interpolate_render(&scene, step_alpha());
 
-                    let vertex_buffer = glium::VertexBuffer::new(&display, &*grid.read().unwrap().create_grid()).unwrap();
+                    let (line_color, line_mix) = line_uniforms(theme);
+                    let uniforms = uniform! {
+                        offset: [camera.offset.0, camera.offset.1],
+                        scale: camera.scale,
+                        line_color: line_color,
+                        line_mix: line_mix,
+                        light_direction: [light.0, light.1, light.2],
+                        ambient: AMBIENT_LIGHT,
+                    };
 
                     let mut target = display.draw();
-                    target.clear_color(0.0, 0.0, 0.0, 1.0);
-                    target.draw(&vertex_buffer, &indices, &program, &glium::uniforms::EmptyUniforms, &Default::default()).unwrap();
+                    let (red, green, blue) = theme.background();
+                    target.clear_color(red, green, blue, 1.0);
+                    let line_parameters = glium::DrawParameters {
+                        line_width: Some(LINE_WIDTHS[line_width_index]),
+                        ..Default::default()
+                    };
+                    let mut lines = vec![];
+                    match render_mode {
+                        RenderMode::Wireframe => lines = scene.read().unwrap().create_grid(),
+                        RenderMode::Points => {
+                            // Keep points the same size relative to the cloth as the view zooms.
+                            let window_height = _window.inner_size().height as f32;
+                            let point_size = (POINT_DIAMETER * camera.scale * 0.5 * window_height).clamp(MIN_POINT_SIZE, MAX_POINT_SIZE);
+                            let point_parameters = glium::DrawParameters {
+                                point_size: Some(point_size),
+                                ..Default::default()
+                            };
+                            let vertex_buffer = glium::VertexBuffer::new(&display, &*scene.read().unwrap().create_points()).unwrap();
+                            target.draw(&vertex_buffer, &point_indices, &program, &uniforms, &point_parameters).unwrap();
+                        }
+                        RenderMode::Filled => {
+                            let vertex_buffer = glium::VertexBuffer::new(&display, &*scene.read().unwrap().create_triangles()).unwrap();
+                            target.draw(&vertex_buffer, &triangle_indices, &fill_program, &uniforms, &Default::default()).unwrap();
+                        }
+                    }
+                    lines.extend(scene.read().unwrap().create_obstacles());
+                    if show_velocities {
+                        lines.extend(scene.read().unwrap().create_velocity_field(VELOCITY_SCALE));
+                    }
+                    if !lines.is_empty() {
+                        let vertex_buffer = glium::VertexBuffer::new(&display, &lines).unwrap();
+                        // Wide lines are optional in core profiles, so fall back to the 1px width the
+                        // driver must support instead of failing every frame.
+                        if let Err(error) = target.draw(&vertex_buffer, &line_indices, &program, &uniforms, &line_parameters) {
+                            eprintln!("Line width {}px not supported ({}), using {}px", LINE_WIDTHS[line_width_index], error, LINE_WIDTHS[0]);
+                            line_width_index = 0;
+                            target.draw(&vertex_buffer, &line_indices, &program, &uniforms, &Default::default()).unwrap();
+                        }
+                    }
+                    #[cfg(feature = "gui")]
+                    {
+                        gui.run(&_window, |ctx| parameter_panel(ctx, &scene, interaction.active, &mut render_mode, &mut show_velocities, &mut theme));
+                        gui.paint(&display, &mut target);
+                    }
                     target.finish().unwrap();
+                    // The finished frame is now the front buffer, at whatever size the window has been resized to.
+                    if screenshot_requested {
+                        screenshot_requested = false;
+                        save_screenshot(&display);
+                    }
+
+                    frames_in_window += 1;
+                    let elapsed = rate_window_start.elapsed();
+                    if elapsed >= Duration::from_secs(1) {
+                        let steps = SIM_STEPS.load(Ordering::Relaxed);
+                        let fps = frames_in_window as f32 / elapsed.as_secs_f32();
+                        let steps_per_second = (steps - steps_at_window_start) as f32 / elapsed.as_secs_f32();
+                        let ((step_count, time), (kinetic, potential)) = {
+                            let scene = scene.read().unwrap();
+                            (scene.clock(), scene.total_energy())
+                        };
+                        let report = format!("FPS: {:.1} | Steps/s: {:.0} | Sim time: {:.2}s ({} steps) | Energy: {:.4} kinetic, {:.4} potential",
+                                             fps, steps_per_second, time, step_count, kinetic, potential);
+                        _window.set_title(&format!("{} | {}", WINDOW_TITLE, report));
+                        run_log::print(Level::Info, format_args!("{}", report));
+                        rate_window_start = Instant::now();
+                        frames_in_window = 0;
+                        steps_at_window_start = steps;
+                    }
                 },
                 _ => (),
             },
+            // A run given a --duration closes the window once it's over.
+            winit::event::Event::AboutToWait if !RUNNING.load(Ordering::Relaxed) => window_target.exit(),
             winit::event::Event::AboutToWait => {
                 _window.request_redraw();
             },
@@ -150,35 +1227,366 @@ fn render(grid: Arc<RwLock<Grid>>) {
     });
 }
 
+#[cfg(feature = "snapshot")]
+fn load_grid(path: &str, config: SimulationConfig) -> Option<Grid> {
+    match snapshot::load(Path::new(path), config) {
+        Ok(grid) => Some(grid),
+        Err(error) => {
+            eprintln!("Failed to load {}: {}", path, error);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "snapshot"))]
+fn load_grid(path: &str, _config: SimulationConfig) -> Option<Grid> {
+    eprintln!("Warning: loading {} requires the snapshot feature", path);
+    None
+}
+
+// Writes the window's front buffer to a PNG named after the current time.
+#[cfg(feature = "screenshot")]
+fn save_screenshot(display: &glium::Display<WindowSurface>) {
+    let millis = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis();
+    let path = format!("screenshot-{}.png", millis);
+    let pixels: glium::texture::RawImage2d<u8> = match display.read_front_buffer() {
+        Ok(pixels) => pixels,
+        Err(error) => {
+            eprintln!("Failed to read the frame for {}: {:?}", path, error);
+            return;
+        }
+    };
+    let Some(image) = image::RgbaImage::from_raw(pixels.width, pixels.height, pixels.data.into_owned()) else {
+        eprintln!("Failed to save {}: the frame is {}x{} but its pixels don't fill it", path, pixels.width, pixels.height);
+        return;
+    };
+    // GL rows run bottom to top, image rows top to bottom.
+    match image::imageops::flip_vertical(&image).save(&path) {
+        Ok(()) => println!("Saved screenshot to {}", path),
+        Err(error) => eprintln!("Failed to save {}: {}", path, error),
+    }
+}
+
+#[cfg(not(feature = "screenshot"))]
+fn save_screenshot(_display: &glium::Display<WindowSurface>) {
+    eprintln!("Warning: saving screenshots requires the screenshot feature");
+}
+
+#[cfg(feature = "mask")]
+fn load_mask(path: &str) -> Option<Grid> {
+    match Grid::from_mask(path) {
+        Ok(grid) => Some(grid),
+        Err(error) => {
+            eprintln!("Failed to load mask {}: {}", path, error);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "mask"))]
+fn load_mask(path: &str) -> Option<Grid> {
+    eprintln!("Warning: loading {} requires the mask feature", path);
+    None
+}
+
+fn create_recorder(nodes: &[usize], path: &str, node_count: usize) -> Option<Recorder> {
+    if let Some(&node) = nodes.iter().find(|&&node| node >= node_count) {
+        eprintln!("Warning: not recording, node {} is outside the {} node grid", node, node_count);
+        return None;
+    }
+    match Recorder::create(path, nodes.to_vec()) {
+        Ok(recorder) => {
+            println!("Recording nodes {:?} to {}", nodes, path);
+            Some(recorder)
+        }
+        Err(error) => {
+            eprintln!("Failed to create {}: {}", path, error);
+            None
+        }
+    }
+}
+
+// The session's arguments head the log, since replaying it needs a simulation started the same way.
+fn create_input_recorder(path: &str) -> Option<InputRecorder> {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    match InputRecorder::create(path, &format!("started with: {}", arguments.join(" "))) {
+        Ok(recorder) => {
+            println!("Recording input to {}", path);
+            Some(recorder)
+        }
+        Err(error) => {
+            eprintln!("Failed to create {}: {}", path, error);
+            None
+        }
+    }
+}
+
+// A stepper per patch once the shader has been checked against the CPU step, or None after saying
+// why the CPU has to carry on instead.
+fn start_gpu(display: &glium::Display<WindowSurface>, scene: &RwLock<Scene>) -> Option<Vec<GpuStepper>> {
+    let steppers = gpu::verify(display).and_then(|()| {
+        let scene = scene.read().unwrap();
+        scene.grids.iter().map(|grid| GpuStepper::new(display, grid)).collect::<Result<Vec<_>, _>>()
+    });
+    match steppers {
+        Ok(steppers) => {
+            println!("Stepping on the GPU");
+            GPU_STEPPING.store(true, Ordering::Relaxed);
+            Some(steppers)
+        }
+        Err(error) => {
+            eprintln!("Can't step on the GPU, staying on the CPU: {}", error);
+            None
+        }
+    }
+}
+
+// Runs `steps` steps of the scene the way `step_grid` would, sending the patches over first and
+// reading them back after. Fails without stepping once a patch needs something only the CPU does.
+fn step_on_gpu(display: &glium::Display<WindowSurface>, scene: &RwLock<Scene>, steppers: &mut [GpuStepper], steps: usize,
+               springs: bool) -> Result<(), String> {
+    let mut scene = scene.write().unwrap();
+    let external = EXTERNAL_MAGNITUDE.load(Ordering::Relaxed);
+    if scene.patch_collision && scene.grids.len() > 1 {
+        return Err("patches collide".to_string());
+    }
+    if let Some(reason) = scene.grids.iter().find_map(|grid| gpu::unsupported(grid, external)) {
+        return Err(format!("the scene uses {}", reason));
+    }
+    let gravity = GRAVITY_ACTIVE.load(Ordering::Relaxed);
+    let time_scale = TIME_SCALES[TIME_SCALE.load(Ordering::Relaxed)];
+    let advances = advances_per_step(time_scale);
+    for (grid, stepper) in scene.grids.iter_mut().zip(steppers) {
+        stepper.upload(display, grid, springs)?;
+        let substeps = grid.config.substeps.max(1);
+        let delta_t = DELTA_TIME * time_scale / advances as f32 / substeps as f32;
+        for _ in 0..steps * advances * substeps {
+            stepper.step(&grid.config, delta_t, gravity);
+        }
+        stepper.download(grid)?;
+    }
+    SIM_STEPS.fetch_add(steps as u64, Ordering::Relaxed);
+    Ok(())
+}
+
+fn load_replay(path: &str) -> Option<Vec<(u64, Input)>> {
+    match replay::load(Path::new(path)) {
+        Ok(inputs) => {
+            println!("Replaying {} inputs from {}", inputs.len(), path);
+            Some(inputs)
+        }
+        Err(error) => {
+            eprintln!("Failed to load {}: {}", path, error);
+            None
+        }
+    }
+}
+
+// Builds one grid as the options describe, with its springs and anchors in place, relaxed and
+// saved as the state `reset` returns to.
+fn build_grid(options: &Options) -> Grid {
+    let loaded = options.load.as_deref().and_then(|path| load_grid(path, options.simulation));
+    let pin_corners = loaded.is_none();
+    let mut grid = loaded.unwrap_or_else(|| match options.shape {
+        Shape::Sheet => options
+            .mask
+            .as_deref()
+            .and_then(load_mask)
+            .unwrap_or_else(|| Grid::with_config(options.width, options.height, options.simulation)),
+        Shape::Rope => Grid::rope_with_config(options.width, options.simulation),
+        Shape::Tube => Grid::tube_with_config(options.width, options.height, options.simulation),
+    });
+    grid.apply_config(options.simulation);
+    if options.tilt != 0.0 && pin_corners {
+        if let Some((min, max)) = grid.bounding_box() {
+            grid.transform(1.0, options.tilt.to_radians(), (min + max) * 0.5);
+        }
+    }
+    grid.get_neighbors();
+    grid.get_bending_neighbors();
+
+    grid.config.substeps = options.substeps;
+    if let Some(per_task) = options.columns_per_task {
+        grid.config.partitioning = Partitioning::Columns { per_task };
+    }
+
+    // A loaded snapshot brings its own anchors.
+    if pin_corners {
+        // A rope hangs from one end so it swings like a pendulum, and a tube is left to fall freely.
+        let anchors = match (&options.pins, options.shape) {
+            (Some(Pins::Nodes(pins)), _) => pins_in(&grid, pins),
+            (Some(Pins::Preset(preset)), _) => grid.preset_pins(*preset),
+            (None, Shape::Sheet) => grid.preset_pins(PinPreset::default()),
+            (None, Shape::Rope) => vec![0],
+            (None, Shape::Tube) => vec![],
+        };
+        grid.set_fixed(&anchors);
+        grid.relax(RELAX_ITERATIONS);
+        if let Some((amplitude, period)) = options.swing {
+            for &anchor in &anchors {
+                grid.set_kinematic(anchor, move |time| Vec2::new(amplitude * (TAU * time / period).sin(), 0.0));
+            }
+        }
+    }
+    // After relaxing, which leaves every node at rest.
+    match options.initial_velocity {
+        Some(InitialVelocity::Uniform(velocity)) => grid.set_uniform_velocity(velocity, DELTA_TIME),
+        Some(InitialVelocity::Radial(speed)) => grid.set_radial_velocity(speed, DELTA_TIME),
+        Some(InitialVelocity::Shear(speed)) => grid.set_shear_velocity(speed, DELTA_TIME),
+        None => (),
+    }
+    grid.save_initial_state();
+    grid
+}
+
+// The pins that name a node of `grid`, warning about the rest.
+fn pins_in(grid: &Grid, pins: &[usize]) -> Vec<usize> {
+    let size = grid.positions.len();
+    let (valid, invalid): (Vec<usize>, Vec<usize>) = pins.iter().copied().partition(|&index| index < size);
+    if !invalid.is_empty() {
+        eprintln!("Warning: ignoring pins {:?} outside the {} node grid", invalid, size);
+    }
+    valid
+}
+
+// Sends the simulation's tracing spans to `path` as folded stacks. The returned guard flushes the
+// file when it's dropped, so it has to outlive the simulation.
+#[cfg(feature = "profile")]
+fn start_profiling(path: &str) -> Option<tracing_flame::FlushGuard<std::io::BufWriter<std::fs::File>>> {
+    use tracing_subscriber::prelude::*;
+
+    match tracing_flame::FlameLayer::with_file(path) {
+        Ok((layer, guard)) => {
+            tracing_subscriber::registry().with(layer).init();
+            println!("Profiling to {}", path);
+            Some(guard)
+        }
+        Err(error) => {
+            eprintln!("Failed to create {}: {}", path, error);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "profile"))]
+fn start_profiling(path: &str) -> Option<()> {
+    eprintln!("Warning: profiling to {} requires the profile feature", path);
+    None
+}
+
 fn main() {
-    let grid = Arc::new(RwLock::new(Grid::new(WIDTH, HEIGHT)));
-    grid.write().unwrap().get_neighbors();
+    let options = Options::parse(std::env::args().skip(1));
+    run_log::set_level(options.log_level);
+    let _profile = options.profile.as_deref().and_then(start_profiling);
+    GRAVITY_ACTIVE.store(options.gravity, Ordering::Relaxed);
 
-    let fixed_1 = grid.read().unwrap().get_index(0, HEIGHT - 1);
-    let fixed_2 = grid.read().unwrap().get_index(WIDTH - 1,HEIGHT -1);
-    
-    {
-        let mut grid_write = grid.write().unwrap();
-        grid_write.fixed[fixed_1] = true;
-        grid_write.fixed[fixed_2] = true;
+    if options.three_d {
+        run_3d(&options);
+        return;
+    }
+
+    let mut grids: Vec<Grid> = (0..options.patches).map(|_| build_grid(&options)).collect();
+    // Lay the patches out in a row centred on the origin, PATCH_GAP apart, or stack them upwards
+    // from where the first one hangs and let the upper ones drop.
+    if let Some((min, max)) = grids[0].bounding_box() {
+        if options.stack_patches {
+            let pitch = max.y - min.y + PATCH_GAP;
+            for (patch, grid) in grids.iter_mut().enumerate().skip(1) {
+                grid.translate(Vec2::new(0.0, patch as f32 * pitch));
+                grid.set_fixed(&[]);
+                grid.save_initial_state();
+            }
+        } else {
+            let pitch = max.x - min.x + PATCH_GAP;
+            let centre = (grids.len() - 1) as f32 / 2.0;
+            for (patch, grid) in grids.iter_mut().enumerate() {
+                grid.translate(Vec2::new((patch as f32 - centre) * pitch, 0.0));
+            }
+        }
+    }
+    for grid in &mut grids {
+        for &(x, y, radius) in &options.spheres {
+            grid.add_sphere(Vec2::new(x, y), radius);
+        }
+        for &(x0, y0, x1, y1) in &options.boxes {
+            grid.add_box(Vec2::new(x0, y0), Vec2::new(x1, y1));
+        }
     }
+    let (width, height) = grids[0].dimensions();
+    let config = grids[0].config;
+    let mut scene = Scene::new(grids);
+    scene.patch_collision = options.patch_collision;
+    let scene = Arc::new(RwLock::new(scene));
 
     let core_count = num_cpus::get() / 2;
     println!("CPU core count: {}", core_count);
 
-    let thread_count = core_count;
-    println!("Running simulation with {} threads", thread_count);
+    let thread_count = options.threads;
+    println!("Running simulation on a {}x{} grid with {} threads", width, height, thread_count);
+    if options.patches > 1 {
+        let interaction = if options.patch_collision { "colliding" } else { "independent" };
+        println!("Simulating {} {} patches", options.patches, interaction);
+    }
+
+    if options.bench_threads {
+        bench_threads(&scene);
+        return;
+    }
 
-    let update_grid = grid.clone();
-    let sim_handle = run_threaded(update_grid, thread_count);
+    let Some(pool) = thread_pool(thread_count) else {
+        return;
+    };
+    if options.headless {
+        let timing = run_headless(scene, &pool, thread_count);
+        log_run(&options, "headless", (width, height), config, timing);
+        return;
+    }
+
+    // Only the first patch is recorded.
+    let mut recorder = options.record.as_ref().and_then(|(nodes, path)| create_recorder(nodes, path, width * height));
+    // A replay puts each input back once the steps it was recorded after have run, starting with
+    // any recorded before the first.
+    let mut replay = options
+        .replay
+        .as_deref()
+        .and_then(load_replay)
+        .map(|inputs| (Interaction::new(options.ripple, options.brush_radius), inputs.into_iter().peekable()));
+    if let Some((interaction, inputs)) = &mut replay {
+        replay_due(&mut scene.write().unwrap(), interaction, inputs, SIM_STEPS.load(Ordering::Relaxed));
+    }
+    let on_step = move |scene: &mut Scene| {
+        if let Some(active) = recorder.as_mut() {
+            if let Err(error) = active.record(&scene.grids[0]) {
+                eprintln!("Stopped recording: {}", error);
+                recorder = None;
+            }
+        }
+        if let Some((interaction, inputs)) = &mut replay {
+            replay_due(scene, interaction, inputs, SIM_STEPS.load(Ordering::Relaxed));
+        }
+    };
+
+    let update_scene = scene.clone();
+    let sim_handle = run_threaded(update_scene, pool, options.duration, on_step);
 
     let enable_rendering = true; // Set this to false to disable rendering
 
     if enable_rendering {
-        render(grid.clone());
-    } else {
-        // Join the simulation thread if rendering is disabled
-        sim_handle.join().unwrap();
+        let input_recorder = options.record_input.as_deref().and_then(create_input_recorder);
+        // Recording and replaying hook into each CPU step, which the GPU doesn't take.
+        let gpu = options.gpu && options.record.is_none() && options.replay.is_none();
+        if options.gpu && !gpu {
+            eprintln!("Warning: --gpu is ignored while recording trajectories or replaying input");
+        }
+        render(scene.clone(), Interaction::new(options.ripple, options.brush_radius), input_recorder, options.theme, options.light, gpu);
+        // The window has closed, so stop the simulation rather than letting it run out its duration.
+        RUNNING.store(false, Ordering::Relaxed);
     }
-    
+    let timing = sim_handle.join().unwrap();
+    let ((_, time), (kinetic, potential)) = {
+        let scene = scene.read().unwrap();
+        (scene.clock(), scene.total_energy())
+    };
+    print_summary(&timing, Some(time), Some(kinetic + potential));
+    log_run(&options, "windowed", (width, height), config, timing);
 }
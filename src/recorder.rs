@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::grid::Grid;
+
+// Steps between flushes, so a crash loses at most this many rows per node.
+const FLUSH_INTERVAL: u64 = 100;
+
+// Appends the position and velocity of a fixed set of nodes to a CSV file after each step, one row
+// per node per step.
+pub struct Recorder {
+    nodes: Vec<usize>,
+    writer: BufWriter<File>,
+    unflushed_steps: u64,
+}
+
+impl Recorder {
+    pub fn create<P: AsRef<Path>>(path: P, nodes: Vec<usize>) -> io::Result<Recorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "step,time,node,x,y,vx,vy")?;
+        Ok(Recorder {
+            nodes,
+            writer,
+            unflushed_steps: 0,
+        })
+    }
+
+    pub fn record(&mut self, grid: &Grid) -> io::Result<()> {
+        for &node in &self.nodes {
+            let position = grid.positions[node];
+            let velocity = grid.velocities[node];
+            writeln!(self.writer, "{},{},{},{},{},{},{}",
+                     grid.step_count, grid.time, node, position.x, position.y, velocity.x, velocity.y)?;
+        }
+        self.unflushed_steps += 1;
+        if self.unflushed_steps >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.unflushed_steps = 0;
+        self.writer.flush()
+    }
+}
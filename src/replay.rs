@@ -0,0 +1,109 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::vec2::Vec2;
+
+// Something done in the window that changes the simulation. Points are in world coordinates, so
+// replaying them doesn't depend on where the camera was.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    // A key press, by its lowercase character or the name of a named key like PageUp.
+    Key(String),
+    // The left button pressed, grabbing the node nearest the point.
+    Grab(Vec2),
+    // The cursor moved while a node or the brush was being dragged.
+    Move(Vec2),
+    // The left button released.
+    Release,
+    Ripple(Vec2),
+    // The brush pressed, pinning or, given `fixed` false, unpinning.
+    Paint { position: Vec2, fixed: bool },
+    // The right button pressed, pinning or unpinning the node nearest the point.
+    TogglePin(Vec2),
+}
+
+// One input per line, as the step it happened after, a keyword and its arguments.
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Input::Key(key) => write!(f, "key {}", key),
+            Input::Grab(point) => write!(f, "grab {} {}", point.x, point.y),
+            Input::Move(point) => write!(f, "move {} {}", point.x, point.y),
+            Input::Release => write!(f, "release"),
+            Input::Ripple(point) => write!(f, "ripple {} {}", point.x, point.y),
+            Input::Paint { position, fixed: true } => write!(f, "pin {} {}", position.x, position.y),
+            Input::Paint { position, fixed: false } => write!(f, "unpin {} {}", position.x, position.y),
+            Input::TogglePin(point) => write!(f, "toggle {} {}", point.x, point.y),
+        }
+    }
+}
+
+impl FromStr for Input {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Input, ()> {
+        let mut parts = value.split_whitespace();
+        let kind = parts.next().ok_or(())?;
+        let arguments: Vec<&str> = parts.collect();
+        let point = || match arguments[..] {
+            [x, y] => Ok(Vec2::new(f32::from_str(x).map_err(|_| ())?, f32::from_str(y).map_err(|_| ())?)),
+            _ => Err(()),
+        };
+        match (kind, &arguments[..]) {
+            ("key", [key]) => Ok(Input::Key(key.to_string())),
+            ("grab", _) => point().map(Input::Grab),
+            ("move", _) => point().map(Input::Move),
+            ("release", []) => Ok(Input::Release),
+            ("ripple", _) => point().map(Input::Ripple),
+            ("pin", _) => point().map(|position| Input::Paint { position, fixed: true }),
+            ("unpin", _) => point().map(|position| Input::Paint { position, fixed: false }),
+            ("toggle", _) => point().map(Input::TogglePin),
+            _ => Err(()),
+        }
+    }
+}
+
+// Writes each input along with the number of steps taken before it, so replaying them between the
+// same steps of a seeded simulation reproduces the session.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    // `header` is written first as a comment, such as the arguments the session was started with.
+    pub fn create<P: AsRef<Path>>(path: P, header: &str) -> io::Result<InputRecorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "# {}", header)?;
+        Ok(InputRecorder { writer })
+    }
+
+    // Flushed straight away, so the log survives the crash it's often recorded to reproduce.
+    pub fn record(&mut self, step: u64, input: &Input) -> io::Result<()> {
+        writeln!(self.writer, "{} {}", step, input)?;
+        self.writer.flush()
+    }
+}
+
+// Reads back what an `InputRecorder` wrote, in order, skipping blank and comment lines. A line that
+// doesn't parse comes back as InvalidData naming it.
+pub fn load(path: &Path) -> io::Result<Vec<(u64, Input)>> {
+    let text = fs::read_to_string(path)?;
+    let mut inputs = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = line
+            .split_once(' ')
+            .and_then(|(step, input)| Some((u64::from_str(step).ok()?, Input::from_str(input).ok()?)));
+        match parsed {
+            Some(input) => inputs.push(input),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("line {}: can't read {:?}", number + 1, line))),
+        }
+    }
+    Ok(inputs)
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::grid::SimulationConfig;
+
+// How much is printed to the console, from least to most.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    // Adds a line per update.
+    Debug,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+// Prints `message` as a line on the console while `level` is enabled.
+pub fn print(level: Level, message: fmt::Arguments) {
+    if enabled(level) {
+        println!("{}", message);
+    }
+}
+
+impl FromStr for Level {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Level, ()> {
+        match value {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Format {
+    // Space separated key=value pairs.
+    #[default]
+    Text,
+    // One JSON object per line.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Format, ()> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+// The parameters a run used and how long its updates took on average.
+pub struct RunRecord {
+    pub mode: &'static str,
+    pub width: usize,
+    pub height: usize,
+    pub patches: usize,
+    pub threads: usize,
+    pub config: SimulationConfig,
+    pub updates: usize,
+    pub seconds: f32,
+    pub mean_update: Duration,
+}
+
+impl RunRecord {
+    // A single line, so every run is one grep match.
+    pub fn to_line(&self, format: Format) -> String {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+        let config = &self.config;
+        let mean_us = self.mean_update.as_secs_f64() * 1_000_000.0;
+        match format {
+            Format::Text => format!(
+                "timestamp={} mode={} width={} height={} patches={} threads={} substeps={} integrator=\"{:?}\" spring_coefficient={} damping_coefficient={} updates={} seconds={:.3} mean_update_us={:.3}",
+                timestamp, self.mode, self.width, self.height, self.patches, self.threads, config.substeps,
+                config.integrator, config.spring_coefficient, config.damping_coefficient, self.updates, self.seconds, mean_us
+            ),
+            Format::Json => format!(
+                "{{\"timestamp\": {}, \"mode\": \"{}\", \"width\": {}, \"height\": {}, \"patches\": {}, \"threads\": {}, \"substeps\": {}, \"integrator\": \"{:?}\", \"spring_coefficient\": {}, \"damping_coefficient\": {}, \"updates\": {}, \"seconds\": {:.3}, \"mean_update_us\": {:.3}}}",
+                timestamp, self.mode, self.width, self.height, self.patches, self.threads, config.substeps,
+                config.integrator, config.spring_coefficient, config.damping_coefficient, self.updates, self.seconds, mean_us
+            ),
+        }
+    }
+}
+
+// Appends a line per run to a file, so results from many configurations collect in one place.
+pub struct RunLog {
+    file: File,
+    format: Format,
+}
+
+impl RunLog {
+    pub fn open<P: AsRef<Path>>(path: P, format: Format) -> io::Result<RunLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(RunLog { file, format })
+    }
+
+    pub fn write(&mut self, record: &RunRecord) -> io::Result<()> {
+        writeln!(self.file, "{}", record.to_line(self.format))
+    }
+}
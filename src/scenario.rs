@@ -0,0 +1,38 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::grid::SimulationConfig;
+
+// A named experiment read from a TOML file: the simulation parameters along with the grid they run
+// on. Anything left out keeps its default, and unknown keys are rejected so typos don't go unseen.
+//
+//     width = 40
+//     pins = [0, 39]
+//
+//     [simulation]
+//     spring_coefficient = 50.0
+//     wind = { x = 0.5, y = 0.0 }
+//     integrator = { position_based = { iterations = 10 } }
+//     constraint_stiffness = 0.5
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    // Node indices to pin instead of the shape's usual anchors.
+    pub pins: Option<Vec<usize>>,
+    pub simulation: SimulationConfig,
+}
+
+// Parse errors come back as InvalidData with the line and column they were found at.
+pub fn load(path: &Path) -> io::Result<Scenario> {
+    let text = fs::read_to_string(path)?;
+    let scenario: Scenario = toml::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    if scenario.width == Some(0) || scenario.height == Some(0) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "width and height must be positive"));
+    }
+    Ok(scenario)
+}
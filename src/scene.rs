@@ -0,0 +1,178 @@
+use rayon::prelude::*;
+
+use crate::grid::{Grid, Simulation, Vertex};
+use crate::spatial_hash::SpatialHash;
+use crate::vec2::Vec2;
+
+// Several grids simulated and drawn together. Each is stepped on its own with its own config, and
+// nodes are picked as a (grid, node) pair so indices stay per grid.
+pub struct Scene {
+    pub grids: Vec<Grid>,
+    // Pushes apart nodes of different grids that come within collision radius of each other after
+    // every step, so patches pile up instead of passing through. Off by default since it hashes
+    // every node of every grid each step.
+    pub patch_collision: bool,
+    // Every grid's nodes, one after the other, and the hash built over them.
+    positions: Vec<Vec2>,
+    spatial_hash: SpatialHash,
+}
+
+impl Scene {
+    pub fn new(grids: Vec<Grid>) -> Scene {
+        let radius = grids.iter().map(|grid| grid.config.collision_radius).fold(0.0, f32::max);
+        Scene {
+            grids,
+            patch_collision: false,
+            positions: vec![],
+            spatial_hash: SpatialHash::new(radius),
+        }
+    }
+
+    // Steps taken and simulated time since the last reset. Every grid is stepped and reset together,
+    // so the first one speaks for them all.
+    pub fn clock(&self) -> (u64, f32) {
+        self.grids.first().map_or((0, 0.0), |grid| (grid.step_count, grid.time))
+    }
+
+    pub fn create_grid(&self) -> Vec<Vertex> {
+        self.grids.iter().flat_map(Grid::create_grid).collect()
+    }
+
+    pub fn create_points(&self) -> Vec<Vertex> {
+        self.grids.iter().flat_map(Grid::create_points).collect()
+    }
+
+    pub fn create_triangles(&self) -> Vec<Vertex> {
+        self.grids.iter().flat_map(Grid::create_triangles).collect()
+    }
+
+    pub fn create_obstacles(&self) -> Vec<Vertex> {
+        self.grids.iter().flat_map(Grid::create_obstacles).collect()
+    }
+
+    pub fn create_velocity_field(&self, scale: f32) -> Vec<Vertex> {
+        self.grids.iter().flat_map(|grid| grid.create_velocity_field(scale)).collect()
+    }
+
+    // The grid and index of the node closest to `point` across every grid.
+    pub fn nearest_node(&self, point: Vec2) -> Option<(usize, usize)> {
+        let distance_squared = |&(grid, index): &(usize, usize)| (self.grids[grid].positions[index] - point).length_squared();
+        self.grids
+            .iter()
+            .enumerate()
+            .filter_map(|(grid_index, grid)| Some((grid_index, grid.nearest_node(point)?)))
+            .min_by(|a, b| distance_squared(a).total_cmp(&distance_squared(b)))
+    }
+
+    // Pins or unpins nodes of every grid under a brush stroke, as `Grid::paint_fixed` does.
+    pub fn paint_fixed(&mut self, from: Vec2, to: Vec2, radius: f32, fixed: bool) -> usize {
+        self.grids.iter_mut().map(|grid| grid.paint_fixed(from, to, radius, fixed)).sum()
+    }
+
+    // Corners enclosing every grid's bounding box.
+    pub fn bounding_box(&self) -> Option<(Vec2, Vec2)> {
+        self.grids.iter().filter_map(Grid::bounding_box).reduce(|(min_a, max_a), (min_b, max_b)| {
+            (
+                Vec2::new(min_a.x.min(min_b.x), min_a.y.min(min_b.y)),
+                Vec2::new(max_a.x.max(max_b.x), max_a.y.max(max_b.y)),
+            )
+        })
+    }
+
+    // One pass of collisions between grids, using a single spatial hash over every node. Each node
+    // of a pair within its own grid's collision radius moves half of the way out, as in
+    // `Grid::apply_self_collision`, and loses any velocity it had into the other grid so contact
+    // doesn't pump energy in. Nodes never collide with their own grid here.
+    fn collide_patches(&mut self, delta_t: f32) {
+        let starts: Vec<usize> = self
+            .grids
+            .iter()
+            .scan(0, |start, grid| {
+                let grid_start = *start;
+                *start += grid.positions.len();
+                Some(grid_start)
+            })
+            .collect();
+        let owner = |index: usize| starts.partition_point(|&start| start <= index) - 1;
+        self.positions.clear();
+        self.positions.extend(self.grids.iter().flat_map(|grid| grid.positions.iter().copied()));
+        self.spatial_hash.rebuild(&self.positions);
+        let radius = self.grids.iter().map(|grid| grid.config.collision_radius).fold(0.0, f32::max);
+
+        let mut offsets = vec![Vec2::ZERO; self.positions.len()];
+        offsets.par_iter_mut().enumerate().for_each(|(index, offset)| {
+            let grid_index = owner(index);
+            let grid = &self.grids[grid_index];
+            let node = index - starts[grid_index];
+            if grid.fixed[node] || !grid.present[node] {
+                return;
+            }
+            let position = self.positions[index];
+            let node_radius = grid.config.collision_radius;
+            for other in self.spatial_hash.query_radius(position, radius, &self.positions) {
+                let other_grid = owner(other);
+                if other_grid == grid_index || !self.grids[other_grid].present[other - starts[other_grid]] {
+                    continue;
+                }
+                let displacement = position - self.positions[other];
+                let distance = displacement.length();
+                if distance >= node_radius || distance <= 0.0 {
+                    continue;
+                }
+                *offset += displacement * (0.5 * grid.config.collision_stiffness * (node_radius - distance) / distance);
+            }
+        });
+
+        for (grid, start) in self.grids.iter_mut().zip(starts) {
+            let step_time = delta_t / grid.config.substeps.max(1) as f32;
+            for (node, &offset) in offsets[start..start + grid.positions.len()].iter().enumerate() {
+                if offset == Vec2::ZERO {
+                    continue;
+                }
+                let normal = offset.normalize();
+                let approach = grid.velocities[node].dot(normal).min(0.0);
+                grid.velocities[node] -= normal * approach;
+                grid.positions[node] += offset;
+                // Verlet reads velocity from the previous positions, so they move to match.
+                grid.prev_positions[node] = grid.positions[node] - grid.velocities[node] * step_time;
+            }
+        }
+    }
+
+    // Kinetic and spring potential energy summed over every grid.
+    pub fn total_energy(&self) -> (f32, f32) {
+        self.grids.iter().map(Grid::total_energy).fold((0.0, 0.0), |(kinetic, potential), (grid_kinetic, grid_potential)| {
+            (kinetic + grid_kinetic, potential + grid_potential)
+        })
+    }
+}
+
+impl Simulation for Scene {
+    fn advance(&mut self, delta_t: f32, gravity: bool, externalbool: bool) {
+        for grid in &mut self.grids {
+            grid.advance(delta_t, gravity, externalbool);
+        }
+        if self.patch_collision && self.grids.len() > 1 {
+            self.collide_patches(delta_t);
+        }
+    }
+
+    // The grids laid side by side: their widths added up, as tall as the tallest.
+    fn dimensions(&self) -> (usize, usize) {
+        self.grids.iter().map(Grid::dimensions).fold((0, 0), |(width, height), (grid_width, grid_height)| {
+            (width + grid_width, height.max(grid_height))
+        })
+    }
+
+    // Nodes are counted through each grid in turn.
+    fn has_nonfinite(&self) -> Option<usize> {
+        let mut start = 0;
+        for grid in &self.grids {
+            if let Some(index) = grid.has_nonfinite() {
+                return Some(start + index);
+            }
+            start += grid.positions.len();
+        }
+        None
+    }
+}
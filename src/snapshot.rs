@@ -0,0 +1,17 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::grid::{Grid, GridSnapshot, SimulationConfig};
+
+pub fn save(grid: &Grid, path: &Path) -> io::Result<()> {
+    let json = serde_json::to_string(&grid.to_snapshot())?;
+    fs::write(path, json)
+}
+
+// Reads a grid saved by `save`, to be simulated with `config`.
+pub fn load(path: &Path, config: SimulationConfig) -> io::Result<Grid> {
+    let json = fs::read_to_string(path)?;
+    let snapshot: GridSnapshot = serde_json::from_str(&json)?;
+    Grid::from_snapshot(snapshot, config)
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::vec2::Vec2;
+
+// Smallest cell side used. Anything finer, or a side that isn't a positive number at all, would
+// have queries walk an enormous number of cells.
+const MIN_CELL_SIZE: f32 = 1e-3;
+
+// Buckets node indices by the square cell of side `cell_size` their position falls in, so
+// queries only need to look at the cells overlapping the search area.
+pub struct SpatialHash {
+    cell_size: f32,
+    buckets: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialHash {
+    // Cells smaller than `MIN_CELL_SIZE`, including zero, negative and NaN sizes, are raised to it.
+    pub fn new(cell_size: f32) -> SpatialHash {
+        let cell_size = if cell_size >= MIN_CELL_SIZE { cell_size } else { MIN_CELL_SIZE };
+        SpatialHash {
+            cell_size,
+            buckets: HashMap::new(),
+        }
+    }
+
+    pub fn rebuild(&mut self, positions: &[Vec2]) {
+        // Keep the bucket allocations around for the next rebuild.
+        for bucket in self.buckets.values_mut() {
+            bucket.clear();
+        }
+        for (index, &position) in positions.iter().enumerate() {
+            self.buckets.entry(self.cell(position)).or_default().push(index);
+        }
+    }
+
+    // Indices whose position in `positions` lies within `radius` of `point`. `positions` should be
+    // the slice the hash was last rebuilt from.
+    pub fn query_radius(&self, point: Vec2, radius: f32, positions: &[Vec2]) -> Vec<usize> {
+        let extent = Vec2::new(radius, radius);
+        let min = self.cell(point - extent);
+        let max = self.cell(point + extent);
+        let mut found = Vec::new();
+        for cell_x in min.0..=max.0 {
+            for cell_y in min.1..=max.1 {
+                let Some(bucket) = self.buckets.get(&(cell_x, cell_y)) else {
+                    continue;
+                };
+                for &index in bucket {
+                    if (positions[index] - point).length_squared() <= radius * radius {
+                        found.push(index);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    fn cell(&self, position: Vec2) -> (i32, i32) {
+        ((position.x / self.cell_size).floor() as i32, (position.y / self.cell_size).floor() as i32)
+    }
+}
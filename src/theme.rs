@@ -0,0 +1,56 @@
+use std::str::FromStr;
+
+// Colours the window is drawn in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Theme {
+    // Black background with lines coloured by strain.
+    #[default]
+    Dark,
+    // White lines on black.
+    Mono,
+    // Black lines on white, for printing.
+    Light,
+    Blueprint,
+}
+
+impl Theme {
+    pub fn next(self) -> Theme {
+        match self {
+            Theme::Dark => Theme::Mono,
+            Theme::Mono => Theme::Light,
+            Theme::Light => Theme::Blueprint,
+            Theme::Blueprint => Theme::Dark,
+        }
+    }
+
+    pub fn background(self) -> (f32, f32, f32) {
+        match self {
+            Theme::Dark | Theme::Mono => (0.0, 0.0, 0.0),
+            Theme::Light => (1.0, 1.0, 1.0),
+            Theme::Blueprint => (0.05, 0.15, 0.4),
+        }
+    }
+
+    // A single colour every line and point is drawn in, or None to keep their own colours.
+    pub fn line_color(self) -> Option<(f32, f32, f32)> {
+        match self {
+            Theme::Dark => None,
+            Theme::Mono | Theme::Blueprint => Some((1.0, 1.0, 1.0)),
+            Theme::Light => Some((0.0, 0.0, 0.0)),
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Theme, ()> {
+        match value {
+            "dark" => Ok(Theme::Dark),
+            "mono" => Ok(Theme::Mono),
+            "light" => Ok(Theme::Light),
+            "blueprint" => Ok(Theme::Blueprint),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// With separate row and column stiffnesses, the same load stretches a cloth further along its
+// softer axis. A sheet hanging from one edge under a steady pull settles with every chain of
+// springs carrying the load of the nodes beyond it, so each axis stretches by the closed form.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Connectivity, Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 6;
+const STIFFNESS_X: f32 = 5.0;
+const STIFFNESS_Y: f32 = 20.0;
+// Force on every node, the same along either axis.
+const LOAD: f32 = 0.05;
+const DELTA_T: f32 = 0.005;
+const STEPS: usize = 4000;
+const TOLERANCE: f32 = 0.01;
+
+// How far the far edge of a sheet pinned along the near edge moves under LOAD along `direction`.
+fn elongation(direction: Vec2) -> f32 {
+    let config = SimulationConfig {
+        stiffness_x: Some(STIFFNESS_X),
+        stiffness_y: Some(STIFFNESS_Y),
+        wind: direction * LOAD,
+        damping_coefficient: 0.2,
+        floor_y: f32::NEG_INFINITY,
+        parallel: false,
+        ..SimulationConfig::default()
+    };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.build_neighbours(Connectivity::Four);
+    let far = if direction.x > 0.0 {
+        grid.pin_column(0);
+        grid.get_index(SIZE - 1, SIZE / 2)
+    } else {
+        grid.pin_row(0);
+        grid.get_index(SIZE / 2, SIZE - 1)
+    };
+    let start = grid.positions[far];
+    for _ in 0..STEPS {
+        grid.advance(DELTA_T, false, false);
+    }
+    (grid.positions[far] - start).dot(direction)
+}
+
+// The spring next to the pinned edge holds up SIZE - 1 nodes, the next SIZE - 2 and so on.
+fn expected(stiffness: f32) -> f32 {
+    LOAD * (SIZE * (SIZE - 1) / 2) as f32 / stiffness
+}
+
+#[test]
+fn each_axis_stretches_by_its_own_stiffness() {
+    let along_x = elongation(Vec2::new(1.0, 0.0));
+    let along_y = elongation(Vec2::new(0.0, 1.0));
+    assert!((along_x / expected(STIFFNESS_X) - 1.0).abs() < TOLERANCE, "{} along x", along_x);
+    assert!((along_y / expected(STIFFNESS_Y) - 1.0).abs() < TOLERANCE, "{} along y", along_y);
+    assert!(along_x > 3.0 * along_y, "{} along x against {} along y", along_x, along_y);
+}
+
+#[test]
+fn unset_stiffnesses_fall_back_to_the_spring_coefficient() {
+    let mut grid = Grid::with_config(SIZE, SIZE, SimulationConfig::default());
+    grid.build_neighbours(Connectivity::Eight);
+    for position in &mut grid.positions {
+        *position = *position * 1.1;
+    }
+    let (_, isotropic) = grid.total_energy();
+    let coefficient = grid.config.spring_coefficient;
+    grid.config.stiffness_x = Some(coefficient);
+    grid.config.stiffness_y = Some(coefficient);
+    assert_eq!(grid.total_energy().1, isotropic);
+    grid.config.stiffness_x = Some(2.0 * coefficient);
+    assert!(grid.total_energy().1 > isotropic);
+}
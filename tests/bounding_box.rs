@@ -0,0 +1,53 @@
+// The bounding box spans the outermost nodes, skips any that have gone non-finite, and is None once
+// no node is left to span.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, SimulationConfig, Vec2};
+
+// A 4x3 grid at half spacing is laid out from x = -1 to 0.5 and y = 9.5 to 10.5.
+const WIDTH: usize = 4;
+const HEIGHT: usize = 3;
+const SPACING: f32 = 0.5;
+const MIN: Vec2 = Vec2::new(-1.0, 9.5);
+const MAX: Vec2 = Vec2::new(0.5, 10.5);
+
+fn grid(parallel: bool) -> Grid {
+    Grid::with_config(WIDTH, HEIGHT, SimulationConfig { spacing: SPACING, parallel, ..SimulationConfig::default() })
+}
+
+#[test]
+fn box_spans_the_corner_nodes() {
+    for parallel in [false, true] {
+        assert_eq!(grid(parallel).bounding_box(), Some((MIN, MAX)), "parallel: {}", parallel);
+    }
+}
+
+#[test]
+fn box_follows_a_moved_node() {
+    for parallel in [false, true] {
+        let mut grid = grid(parallel);
+        grid.positions[5] = Vec2::new(3.0, -2.0);
+        assert_eq!(grid.bounding_box(), Some((Vec2::new(MIN.x, -2.0), Vec2::new(3.0, MAX.y))), "parallel: {}", parallel);
+    }
+}
+
+#[test]
+fn non_finite_nodes_are_skipped() {
+    for parallel in [false, true] {
+        let mut grid = grid(parallel);
+        // Two nodes on the left edge, one of them a corner; the rest still reach the same extremes.
+        grid.positions[0] = Vec2::new(f32::NAN, f32::NAN);
+        grid.positions[1] = Vec2::new(f32::INFINITY, 0.0);
+        assert_eq!(grid.bounding_box(), Some((MIN, MAX)), "parallel: {}", parallel);
+    }
+}
+
+#[test]
+fn all_nan_grid_has_no_box() {
+    for parallel in [false, true] {
+        let mut grid = grid(parallel);
+        grid.positions.fill(Vec2::new(f32::NAN, f32::NAN));
+        assert_eq!(grid.bounding_box(), None, "parallel: {}", parallel);
+    }
+}
@@ -0,0 +1,48 @@
+// Nodes that end a step inside a box leave through its nearest face, losing the velocity that
+// carried them in, and a node near a corner leaves along the axis it's least far in.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Obstacle, Simulation, Vec2};
+
+const DELTA_T: f32 = 0.01;
+const MIN: Vec2 = Vec2::new(-2.0, -1.0);
+const MAX: Vec2 = Vec2::new(2.0, 1.0);
+
+fn boxed() -> Obstacle {
+    Obstacle::Box { min: MIN, max: MAX }
+}
+
+#[test]
+fn node_leaves_through_the_nearest_face() {
+    let cases = [
+        (Vec2::new(-1.8, 0.2), Vec2::new(-2.0, 0.2), Vec2::new(-1.0, 0.0)),
+        (Vec2::new(1.9, -0.5), Vec2::new(2.0, -0.5), Vec2::new(1.0, 0.0)),
+        (Vec2::new(0.5, -0.7), Vec2::new(0.5, -1.0), Vec2::new(0.0, -1.0)),
+        (Vec2::new(-0.3, 0.95), Vec2::new(-0.3, 1.0), Vec2::new(0.0, 1.0)),
+        // By the top right corner, but nearer the right face than the top.
+        (Vec2::new(1.95, 0.9), Vec2::new(2.0, 0.9), Vec2::new(1.0, 0.0)),
+        // And nearer the top than the right.
+        (Vec2::new(1.8, 0.95), Vec2::new(1.8, 1.0), Vec2::new(0.0, 1.0)),
+    ];
+    for (position, surface, normal) in cases {
+        assert_eq!(boxed().push_out(position), Some((surface, normal)), "from {:?}", position);
+    }
+}
+
+#[test]
+fn nodes_outside_or_on_the_surface_stay_put() {
+    for position in [Vec2::new(-3.0, 0.0), Vec2::new(0.0, 1.5), Vec2::new(2.0, 0.0), Vec2::new(0.0, -1.0)] {
+        assert_eq!(boxed().push_out(position), None, "from {:?}", position);
+    }
+}
+
+#[test]
+fn step_moves_a_node_onto_the_face_and_stops_it_going_in() {
+    let mut grid = Grid::single_node(Vec2::new(0.4, 0.9), Vec2::new(0.0, -1.0), Vec2::ZERO, DELTA_T);
+    // Corners given the wrong way round are sorted out.
+    grid.add_box(MAX, MIN);
+    grid.advance(DELTA_T, false, false);
+    assert_eq!(grid.positions[0], Vec2::new(0.4, 1.0));
+    assert!(grid.velocities[0].y >= 0.0, "{:?}", grid.velocities[0]);
+}
@@ -0,0 +1,53 @@
+// The pinning brush changes exactly the nodes its stroke passes near, and only those.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Scene, Vec2};
+
+const SIZE: usize = 10;
+const RADIUS: f32 = 1.5;
+
+fn within(grid: &Grid, from: Vec2, to: Vec2, radius: f32) -> Vec<bool> {
+    let stroke = to - from;
+    grid.positions
+        .iter()
+        .map(|&position| {
+            let along = ((position - from).dot(stroke) / stroke.length_squared()).clamp(0.0, 1.0);
+            (position - (from + stroke * along)).length() <= radius
+        })
+        .collect()
+}
+
+#[test]
+fn stroke_pins_nodes_along_its_path() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    let (from, to) = (grid.positions[grid.get_index(1, 2)], grid.positions[grid.get_index(8, 6)]);
+    let expected = within(&grid, from, to, RADIUS);
+    let changed = grid.paint_fixed(from, to, RADIUS, true);
+    assert_eq!(grid.fixed, expected);
+    assert_eq!(changed, expected.iter().filter(|&&fixed| fixed).count());
+    // Painting over them again changes nothing.
+    assert_eq!(grid.paint_fixed(from, to, RADIUS, true), 0);
+}
+
+#[test]
+fn unpinning_only_clears_nodes_under_the_brush() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    let center = grid.positions[grid.get_index(SIZE / 2, SIZE / 2)];
+    grid.paint_fixed(center, center, RADIUS, true);
+    grid.paint_fixed(center, center, RADIUS, false);
+    let pinned: Vec<usize> = (0..grid.positions.len()).filter(|&index| grid.fixed[index]).collect();
+    assert_eq!(pinned, anchors.to_vec());
+}
+
+#[test]
+fn scene_paints_every_patch() {
+    let mut scene = Scene::new(vec![Grid::new(SIZE, SIZE), Grid::new(SIZE, SIZE)]);
+    let center = scene.grids[0].positions[scene.grids[0].get_index(SIZE / 2, SIZE / 2)];
+    let changed = scene.paint_fixed(center, center, RADIUS, true);
+    let pinned: usize = scene.grids.iter().map(|grid| grid.fixed.iter().filter(|&&fixed| fixed).count()).sum();
+    assert!(changed > 0);
+    assert_eq!(changed, pinned);
+}
@@ -0,0 +1,59 @@
+// Cursor picking has to agree with the vertex shader, so the screen and world mappings must invert
+// each other for any view.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::camera::{screen_to_world, world_to_screen, Camera};
+
+const WINDOW_SIZES: [(f32, f32); 3] = [(800.0, 800.0), (1280.0, 720.0), (333.0, 999.0)];
+const TOLERANCE: f32 = 1e-3;
+
+fn cameras() -> Vec<Camera> {
+    let mut zoomed = Camera::default();
+    zoomed.zoom(3.0, (100.0, 250.0), (800.0, 800.0));
+    let mut panned = Camera::default();
+    panned.pan((400.0, 400.0), (10.0, 700.0), (800.0, 800.0));
+    let mut fitted = Camera::default();
+    fitted.fit((-3.0, 2.0), (40.0, 12.0));
+    vec![Camera::default(), zoomed, panned, fitted]
+}
+
+fn assert_close(a: (f32, f32), b: (f32, f32), scale: f32) {
+    let tolerance = TOLERANCE * scale.max(1.0);
+    assert!((a.0 - b.0).abs() <= tolerance && (a.1 - b.1).abs() <= tolerance, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn screen_round_trips_through_world() {
+    for camera in cameras() {
+        for window_size in WINDOW_SIZES {
+            for screen in [(0.0, 0.0), (window_size.0, window_size.1), (123.0, 456.0), (0.5 * window_size.0, 7.0)] {
+                let world = screen_to_world(screen, window_size, &camera);
+                assert_close(world_to_screen(world, window_size, &camera), screen, window_size.0.max(window_size.1));
+            }
+        }
+    }
+}
+
+#[test]
+fn world_round_trips_through_screen() {
+    for camera in cameras() {
+        for window_size in WINDOW_SIZES {
+            for world in [(0.0, 0.0), (-25.0, 25.0), (3.5, -12.25), (100.0, 40.0)] {
+                let screen = world_to_screen(world, window_size, &camera);
+                assert_close(screen_to_world(screen, window_size, &camera), world, 100.0);
+            }
+        }
+    }
+}
+
+#[test]
+fn default_view_matches_the_shader() {
+    // The default view shows 25 world units from the centre to each edge, with y pointing up.
+    let camera = Camera::default();
+    let window_size = (800.0, 600.0);
+    assert_close(screen_to_world((400.0, 300.0), window_size, &camera), (0.0, 0.0), 1.0);
+    assert_close(screen_to_world((800.0, 0.0), window_size, &camera), (25.0, 25.0), 1.0);
+    assert_close(screen_to_world((0.0, 600.0), window_size, &camera), (-25.0, -25.0), 1.0);
+    assert_eq!(camera.screen_to_world((10.0, 20.0), window_size), screen_to_world((10.0, 20.0), window_size, &camera));
+}
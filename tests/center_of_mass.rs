@@ -0,0 +1,50 @@
+// The centre of mass has to weight each node by its own mass, and holding it has to keep a cloth
+// in place against a sideways push.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 5;
+const TOLERANCE: f32 = 1e-4;
+
+fn middle(grid: &Grid) -> Vec2 {
+    let (min, max) = grid.bounding_box().unwrap();
+    (min + max) * 0.5
+}
+
+#[test]
+fn symmetric_grid_is_centred() {
+    for parallel in [false, true] {
+        let config = SimulationConfig { parallel, ..SimulationConfig::default() };
+        let grid = Grid::with_config(SIZE, SIZE, config);
+        let center = grid.center_of_mass().unwrap();
+        assert!((center - middle(&grid)).length() < TOLERANCE, "centre {:?} with parallel {}", center, parallel);
+        assert!(center.x.abs() < TOLERANCE);
+    }
+}
+
+#[test]
+fn heavier_nodes_pull_the_centre() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    let corner = grid.get_index(SIZE - 1, SIZE - 1);
+    grid.set_mass(corner, 100.0 * grid.config.mass);
+    let center = grid.center_of_mass().unwrap();
+    let middle = middle(&grid);
+    assert!(center.x > middle.x && center.y > middle.y, "centre {:?} not pulled towards {:?}", center, grid.positions[corner]);
+}
+
+#[test]
+fn held_centre_resists_wind() {
+    let config = SimulationConfig { wind: Vec2::new(1.0, 0.5), hold_center_of_mass: true, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors[..1]);
+    let start = grid.center_of_mass().unwrap();
+    for _ in 0..200 {
+        grid.advance(0.01, true, false);
+    }
+    let end = grid.center_of_mass().unwrap();
+    assert!((end - start).length() < 1e-3, "centre drifted from {:?} to {:?}", start, end);
+}
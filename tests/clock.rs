@@ -0,0 +1,45 @@
+// Every grid keeps one clock of steps and simulated time, which substeps share out and a reset
+// sets back to zero, and a scene reads it from its grids.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Scene, Simulation, SimulationConfig};
+
+const SIZE: usize = 4;
+const DELTA_T: f32 = 0.01;
+const STEPS: usize = 10;
+const SUBSTEPS: usize = 3;
+const TOLERANCE: f32 = 1e-5;
+
+#[test]
+fn substeps_count_as_steps_covering_the_same_time() {
+    let config = SimulationConfig { substeps: SUBSTEPS, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    for _ in 0..STEPS {
+        grid.advance(DELTA_T, true, false);
+    }
+    assert_eq!(grid.step_count, (STEPS * SUBSTEPS) as u64);
+    assert!((grid.time - STEPS as f32 * DELTA_T).abs() < TOLERANCE, "{}", grid.time);
+}
+
+#[test]
+fn reset_zeroes_the_clock() {
+    let mut scene = Scene::new(vec![Grid::new(SIZE, SIZE), Grid::new(SIZE, SIZE)]);
+    assert_eq!(scene.clock(), (0, 0.0));
+    for _ in 0..STEPS {
+        scene.advance(DELTA_T, true, false);
+    }
+    let (steps, time) = scene.clock();
+    assert_eq!(steps, STEPS as u64);
+    assert!((time - STEPS as f32 * DELTA_T).abs() < TOLERANCE, "{}", time);
+    assert!(scene.grids.iter().all(|grid| (grid.step_count, grid.time) == (steps, time)));
+
+    scene.grids.iter_mut().for_each(Grid::reset);
+    assert_eq!(scene.clock(), (0, 0.0));
+}
+
+#[test]
+fn empty_scene_has_not_started() {
+    assert_eq!(Scene::new(vec![]).clock(), (0, 0.0));
+}
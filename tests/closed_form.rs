@@ -0,0 +1,58 @@
+// A lone node under a constant force, stepped by each integrator and compared with the closed-form
+// motion x = x0 + v0·t + ½·a·t².
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Integrator, Simulation, Vec2};
+
+const DELTA_T: f32 = 0.01;
+const STEPS: usize = 100;
+const POSITION: Vec2 = Vec2::new(1.0, -2.0);
+const VELOCITY: Vec2 = Vec2::new(0.5, 3.0);
+const FORCE: Vec2 = Vec2::new(-0.2, 0.7);
+
+// Steps a node with `integrator` and returns its position and velocity after each step.
+fn trajectory(integrator: Integrator) -> (Vec<(Vec2, Vec2)>, f32) {
+    let mut grid = Grid::single_node(POSITION, VELOCITY, FORCE, DELTA_T);
+    grid.config.integrator = integrator;
+    let mass = grid.masses[0];
+    let states = (0..STEPS)
+        .map(|_| {
+            grid.advance(DELTA_T, false, false);
+            (grid.positions[0], grid.velocities[0])
+        })
+        .collect();
+    (states, mass)
+}
+
+fn assert_close(actual: Vec2, expected: Vec2, what: &str, step: usize) {
+    let tolerance = 1e-5 * expected.length().max(1.0);
+    assert!((actual - expected).length() <= tolerance,
+            "{} after step {}: got ({}, {}), expected ({}, {})", what, step, actual.x, actual.y, expected.x, expected.y);
+}
+
+#[test]
+fn rk4_matches_the_closed_form() {
+    let (states, mass) = trajectory(Integrator::Rk4);
+    let acceleration = FORCE / mass;
+    for (step, &(position, velocity)) in states.iter().enumerate() {
+        let t = (step + 1) as f32 * DELTA_T;
+        assert_close(position, POSITION + VELOCITY * t + acceleration * (0.5 * t * t), "position", step);
+        assert_close(velocity, VELOCITY + acceleration * t, "velocity", step);
+    }
+}
+
+// Both first-order schemes update the velocity before moving with it, so each step's displacement
+// runs a step ahead of the exact one: x = x0 + v0·t + ½·a·t·(t + dt).
+#[test]
+fn first_order_schemes_match_their_discrete_form() {
+    for integrator in [Integrator::SymplecticEuler, Integrator::Verlet] {
+        let (states, mass) = trajectory(integrator);
+        let acceleration = FORCE / mass;
+        for (step, &(position, velocity)) in states.iter().enumerate() {
+            let t = (step + 1) as f32 * DELTA_T;
+            assert_close(position, POSITION + VELOCITY * t + acceleration * (0.5 * t * (t + DELTA_T)), "position", step);
+            assert_close(velocity, VELOCITY + acceleration * t, "velocity", step);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+// Two connected nodes at the same point have no direction between them for a spring to pull along,
+// so the spring is skipped until they separate instead of dividing by a zero length.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Integrator, Simulation, SimulationConfig};
+
+const SIZE: usize = 4;
+const DELTA_T: f32 = 0.01;
+const STEPS: usize = 20;
+const INTEGRATORS: [Integrator; 4] = [Integrator::SymplecticEuler, Integrator::Verlet, Integrator::Rk4, Integrator::PositionBased { iterations: 3 }];
+
+// A grid with nodes 5 and 6, neighbours in the second row, moved onto the same point.
+fn collapsed(config: SimulationConfig) -> Grid {
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    grid.get_bending_neighbors();
+    grid.bending = true;
+    grid.positions[6] = grid.positions[5];
+    grid.prev_positions[6] = grid.prev_positions[5];
+    grid
+}
+
+fn assert_finite(grid: &Grid, what: &str) {
+    for (index, position) in grid.positions.iter().enumerate() {
+        assert!(position.x.is_finite() && position.y.is_finite(), "{}: node {} at {:?}", what, index, position);
+    }
+}
+
+#[test]
+fn coincident_neighbours_exert_no_spring_force_on_each_other() {
+    let mut grid = Grid::new(2, 1);
+    grid.get_neighbors();
+    grid.positions[1] = grid.positions[0];
+    let forces = grid.debug_forces(0, false, false);
+    assert!(forces.spring.x.is_finite() && forces.spring.y.is_finite(), "{:?}", forces.spring);
+    assert_eq!(forces.spring.length(), 0.0);
+}
+
+#[test]
+fn coincident_neighbours_stay_finite() {
+    for integrator in INTEGRATORS {
+        for edge_springs in [false, true] {
+            let mut grid = collapsed(SimulationConfig { integrator, edge_springs, ..SimulationConfig::default() });
+            for _ in 0..STEPS {
+                grid.advance(DELTA_T, true, false);
+            }
+            assert_finite(&grid, &format!("{:?} with edge springs {}", integrator, edge_springs));
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// Neighbour counts and rest lengths for each connectivity on a small grid.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Connectivity, Grid};
+
+const SIZE: usize = 3;
+const EPSILON: f32 = 1e-5;
+
+fn build(connectivity: Connectivity) -> (Grid, Vec<usize>) {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.build_neighbours(connectivity);
+    let counts = grid.neighbours.iter().map(Vec::len).collect();
+    (grid, counts)
+}
+
+#[test]
+fn four_way_joins_sides() {
+    let (grid, counts) = build(Connectivity::Four);
+    assert_eq!(counts[grid.get_index(0, 0)], 2);
+    assert_eq!(counts[grid.get_index(1, 0)], 3);
+    assert_eq!(counts[grid.get_index(1, 1)], 4);
+    assert_eq!(counts.iter().sum::<usize>(), 2 * 12);
+}
+
+#[test]
+fn eight_way_adds_diagonals() {
+    let (grid, counts) = build(Connectivity::Eight);
+    assert_eq!(counts[grid.get_index(0, 0)], 3);
+    assert_eq!(counts[grid.get_index(1, 0)], 5);
+    assert_eq!(counts[grid.get_index(1, 1)], 8);
+    assert_eq!(counts.iter().sum::<usize>(), 2 * (12 + 8));
+
+    let relax_distance = grid.config.spring_relax_distance;
+    let corner = grid.get_index(0, 0);
+    let diagonal = grid.get_index(1, 1);
+    let &(_, rest) = grid.neighbours[corner].iter().find(|&&(index, _)| index == diagonal).unwrap();
+    assert!((rest - relax_distance * 2.0f32.sqrt()).abs() < EPSILON);
+}
+
+#[test]
+fn custom_joins_what_it_accepts() {
+    // Rows only, and asked one way round: springs still have to come out mutual.
+    let (grid, counts) = build(Connectivity::Custom(|a, b| a.1 == b.1 && b.0 == a.0 + 1));
+    assert_eq!(counts[grid.get_index(0, 0)], 1);
+    assert_eq!(counts[grid.get_index(1, 1)], 2);
+    assert_eq!(counts.iter().sum::<usize>(), 2 * 6);
+    assert!(grid.is_connected(grid.get_index(1, 0), grid.get_index(0, 0)));
+
+    // Knight's moves reach two nodes from each corner of a 3x3 grid and none from the middle.
+    let (grid, counts) = build(Connectivity::Custom(|a, b| {
+        let (dx, dy) = (a.0.abs_diff(b.0), a.1.abs_diff(b.1));
+        (dx, dy) == (1, 2) || (dx, dy) == (2, 1)
+    }));
+    assert_eq!(counts[grid.get_index(0, 0)], 2);
+    assert_eq!(counts[grid.get_index(1, 1)], 0);
+    let relax_distance = grid.config.spring_relax_distance;
+    assert!(grid.neighbours[0].iter().all(|&(_, rest)| (rest - relax_distance * 5.0f32.sqrt()).abs() < EPSILON));
+}
+
+#[test]
+fn rebuilds_keep_the_connectivity() {
+    let (mut grid, _) = build(Connectivity::Eight);
+    grid.get_neighbors();
+    assert_eq!(grid.neighbours[grid.get_index(1, 1)].len(), 8);
+}
@@ -0,0 +1,81 @@
+// Grids with a side of 0 or 1 node have no cells, and maybe no springs or no nodes at all, but
+// everything should still work on them without panicking.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Connectivity, Grid, Integrator, Simulation, Vec2};
+
+const DELTA_T: f32 = 0.01;
+const SIZES: [(usize, usize); 6] = [(0, 0), (0, 5), (5, 0), (1, 1), (1, 5), (5, 1)];
+
+fn exercise(mut grid: Grid) {
+    let (width, height) = (grid.width, grid.height);
+    let nodes = width * height;
+    assert_eq!(grid.positions.len(), nodes);
+
+    grid.build_neighbours(Connectivity::Eight);
+    grid.get_bending_neighbors();
+    grid.bending = true;
+    // Springs only run along a side with more than one node, and there are no cells for diagonals.
+    let springs: usize = grid.neighbours.iter().map(Vec::len).sum::<usize>() / 2;
+    let mut expected = if width == 0 || height == 0 { 0 } else { width * (height - 1) + height * (width - 1) };
+    // A tube closes each row into a ring once it's wide enough to.
+    if grid.wrap_x && width > 2 {
+        expected += height;
+    }
+    assert_eq!(springs, expected, "{}x{} springs", width, height);
+
+    assert!(grid.create_triangles().is_empty(), "{}x{} has no cells to fill", width, height);
+    assert!(grid.intact_triangles().is_empty());
+    assert_eq!(grid.create_points().len(), nodes);
+    assert_eq!(grid.create_grid().len(), springs * 2);
+    assert_eq!(grid.create_velocity_field(1.0).len(), nodes * 2);
+    assert_eq!(grid.center_of_mass().is_some(), nodes > 0);
+    assert_eq!(grid.bounding_box().is_some(), nodes > 0);
+    assert_eq!(grid.nearest_node(Vec2::ZERO).is_some(), nodes > 0);
+    grid.node_strains();
+    grid.node_signed_strains();
+    grid.top_corners();
+    grid.relax(5);
+    grid.set_rest_from_current();
+    grid.apply_impulse(Vec2::new(0.0, 1.0), DELTA_T);
+    grid.apply_ripple(Vec2::ZERO, Default::default(), DELTA_T);
+    grid.config.self_collision = true;
+
+    for integrator in [Integrator::SymplecticEuler, Integrator::Verlet, Integrator::Rk4, Integrator::PositionBased { iterations: 3 }] {
+        grid.config.integrator = integrator;
+        for _ in 0..10 {
+            grid.advance(DELTA_T, true, true);
+        }
+    }
+    assert_eq!(grid.has_nonfinite(), None);
+    grid.total_energy();
+    grid.state_hash();
+    grid.reset();
+    grid.add_bottom_row();
+    assert_eq!(grid.height, height + 1);
+    // The last row is never removed.
+    assert_eq!(grid.remove_bottom_row(), height > 0);
+    assert_eq!(grid.height, height.max(1));
+}
+
+#[test]
+fn degenerate_sizes_do_not_panic() {
+    for (width, height) in SIZES {
+        exercise(Grid::new(width, height));
+    }
+}
+
+#[test]
+fn degenerate_shear_grids_do_not_panic() {
+    for (width, height) in SIZES {
+        exercise(Grid::new_with_shear(width, height));
+    }
+}
+
+#[test]
+fn degenerate_tubes_do_not_panic() {
+    for (width, height) in SIZES {
+        exercise(Grid::tube(width, height));
+    }
+}
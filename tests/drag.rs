@@ -0,0 +1,32 @@
+// Quadratic air drag takes energy out of a moving grid on top of the spring damping, so a grid
+// with it comes to rest sooner than the same grid without it.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig};
+
+const SIZE: usize = 6;
+const DELTA_T: f32 = 0.01;
+const MAX_STEPS: usize = 5000;
+// Kinetic energy below which the grid counts as settled.
+const AT_REST: f32 = 1e-4;
+
+// Steps until a free grid laid out stretched stops moving, or None if it hasn't by MAX_STEPS.
+fn steps_to_settle(quadratic_drag: bool) -> Option<usize> {
+    let config = SimulationConfig { spacing: 1.5, quadratic_drag, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    // Let it start moving before looking for rest.
+    grid.advance(DELTA_T, false, false);
+    (1..=MAX_STEPS).find(|_| {
+        grid.advance(DELTA_T, false, false);
+        grid.total_energy().0 < AT_REST
+    })
+}
+
+#[test]
+fn drag_settles_sooner() {
+    let with_drag = steps_to_settle(true).expect("the grid with drag never settled");
+    let without_drag = steps_to_settle(false).expect("the grid without drag never settled");
+    assert!(with_drag < without_drag, "{} steps with drag, {} without", with_drag, without_drag);
+}
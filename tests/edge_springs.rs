@@ -0,0 +1,71 @@
+// Working each spring out once from an edge list has to give the same forces, and so the same
+// motion, as working it out from both ends' neighbour lists, up to the order the sums are taken in.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 8;
+const DELTA_T: f32 = 0.01;
+const STEPS: usize = 200;
+const TOLERANCE: f32 = 1e-3;
+
+fn grid(edge_springs: bool, parallel: bool) -> Grid {
+    let config = SimulationConfig { edge_springs, parallel, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    grid.get_bending_neighbors();
+    grid.bending = true;
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    grid
+}
+
+fn assert_close(a: Vec2, b: Vec2, what: &str) {
+    assert!((a - b).length() <= TOLERANCE * (1.0 + a.length()), "{}: {:?} against {:?}", what, a, b);
+}
+
+#[test]
+fn edge_forces_match_per_node_forces() {
+    let mut per_node = grid(false, false);
+    for _ in 0..50 {
+        per_node.advance(DELTA_T, true, false);
+    }
+    let mut per_edge = grid(true, false);
+    per_edge.positions = per_node.positions.clone();
+    per_edge.velocities = per_node.velocities.clone();
+
+    for index in 0..per_node.positions.len() {
+        let expected = per_node.debug_forces(index, true, false).spring;
+        let actual = per_edge.debug_forces(index, true, false).spring;
+        assert_ne!(expected, Vec2::ZERO);
+        assert_close(actual, expected, &format!("node {}", index));
+    }
+}
+
+#[test]
+fn trajectories_match_per_node_springs() {
+    for parallel in [false, true] {
+        let (mut per_node, mut per_edge) = (grid(false, parallel), grid(true, parallel));
+        for _ in 0..STEPS {
+            per_node.advance(DELTA_T, true, false);
+            per_edge.advance(DELTA_T, true, false);
+        }
+        for (index, (&expected, &actual)) in per_node.positions.iter().zip(&per_edge.positions).enumerate() {
+            assert_close(actual, expected, &format!("node {} (parallel: {})", index, parallel));
+        }
+    }
+}
+
+#[test]
+fn overstretched_edge_tears_once() {
+    let config = SimulationConfig { edge_springs: true, tear_threshold: 1.5, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(2, 1, config);
+    grid.get_neighbors();
+    grid.set_fixed(&[0]);
+    let position = grid.positions[0] + Vec2::new(3.0, 0.0);
+    grid.drag_node(1, position);
+    grid.advance(DELTA_T, false, false);
+    assert!(!grid.is_connected(0, 1) && !grid.is_connected(1, 0));
+    assert_eq!(grid.torn_springs.len(), 1);
+}
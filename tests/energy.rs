@@ -0,0 +1,64 @@
+// Spring energy is zero when every spring sits at its relax distance, and a grid built with its
+// spacing equal to that distance starts that way without being relaxed. Set moving without gravity
+// or damping, the explicit integrators have to hold its energy steady up to their own error.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Connectivity, Grid, Integrator, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 8;
+const TOLERANCE: f32 = 1e-6;
+const DELTA_T: f32 = 0.005;
+const STEPS: usize = 100;
+const INTEGRATORS: [Integrator; 3] = [Integrator::SymplecticEuler, Integrator::Verlet, Integrator::Rk4];
+// Share of the starting energy a run may drift by either way.
+const ENERGY_DRIFT: f32 = 0.01;
+
+fn total(grid: &Grid) -> f32 {
+    let (kinetic, potential) = grid.total_energy();
+    kinetic + potential
+}
+
+#[test]
+fn fresh_grid_at_its_relax_distance_has_no_spring_energy() {
+    for connectivity in [Connectivity::Four, Connectivity::Eight] {
+        let config = SimulationConfig { spacing: 1.0, spring_relax_distance: 1.0, ..SimulationConfig::default() };
+        let mut grid = Grid::with_config(SIZE, SIZE, config);
+        grid.build_neighbours(connectivity);
+        grid.get_bending_neighbors();
+        grid.bending = true;
+        let (kinetic, potential) = grid.total_energy();
+        assert_eq!(kinetic, 0.0);
+        assert!(potential.abs() < TOLERANCE, "{} spring energy with {:?}", potential, connectivity);
+    }
+}
+
+#[test]
+fn fresh_grid_off_its_relax_distance_has_spring_energy() {
+    let config = SimulationConfig { spacing: 1.2, spring_relax_distance: 1.0, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    assert!(grid.total_energy().1 > TOLERANCE);
+}
+
+#[test]
+fn integrators_keep_energy_bounded() {
+    for integrator in INTEGRATORS {
+        let mut grid = Grid::with_config(SIZE, SIZE, SimulationConfig { integrator, damping_coefficient: 0.0, ..SimulationConfig::default() });
+        grid.get_neighbors();
+        // A shear wave across the columns, with the previous positions to match for Verlet.
+        for index in 0..grid.positions.len() {
+            let velocity = Vec2::new(0.0, ((index / SIZE) as f32 * 0.9).sin());
+            grid.velocities[index] = velocity;
+            grid.prev_positions[index] = grid.positions[index] - velocity * DELTA_T;
+        }
+        let initial = total(&grid);
+        assert!(initial > TOLERANCE);
+        for step in 0..STEPS {
+            grid.advance(DELTA_T, false, false);
+            let energy = total(&grid);
+            assert!(energy.is_finite() && (energy / initial - 1.0).abs() <= ENERGY_DRIFT,
+                    "{:?}: energy {} after step {}, starting from {}", integrator, energy, step, initial);
+        }
+    }
+}
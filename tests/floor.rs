@@ -0,0 +1,31 @@
+// A node dropped onto a floor that doesn't bounce settles on it and stays there.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, Vec2};
+
+const DELTA_T: f32 = 0.001;
+// Two seconds, long enough to fall the metre and settle.
+const STEPS: usize = 2000;
+const FLOOR_Y: f32 = -1.0;
+const DROP_HEIGHT: f32 = 1.0;
+
+#[test]
+fn dropped_node_comes_to_rest_on_the_floor() {
+    let mut grid = Grid::single_node(Vec2::new(0.0, FLOOR_Y + DROP_HEIGHT), Vec2::ZERO, Vec2::ZERO, DELTA_T);
+    grid.config.floor_y = FLOOR_Y;
+    grid.config.restitution = 0.0;
+    let mut landed = false;
+    for step in 0..STEPS {
+        grid.advance(DELTA_T, true, false);
+        let (position, velocity) = (grid.positions[0], grid.velocities[0]);
+        assert!(position.y >= FLOOR_Y, "fell through the floor to {} on step {}", position.y, step);
+        landed |= position.y == FLOOR_Y;
+        if landed {
+            assert_eq!(position.y, FLOOR_Y, "left the floor on step {}", step);
+            assert!(velocity.y.abs() < 1e-6, "moving at {:?} on step {}", velocity, step);
+        }
+    }
+    assert!(landed, "never reached the floor");
+    assert_eq!(grid.positions[0].x, 0.0);
+}
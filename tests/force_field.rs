@@ -0,0 +1,58 @@
+// A force field is added to the step's forces: a constant one has to move the cloth exactly as
+// gravity does, and it's evaluated at each node's position and the simulation time.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, Vec2};
+
+const SIZE: usize = 6;
+const STEPS: usize = 100;
+const DELTA_T: f32 = 0.01;
+
+fn hanging_grid() -> Grid {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    grid
+}
+
+#[test]
+fn constant_field_reproduces_gravity() {
+    let mut with_gravity = hanging_grid();
+    let mut with_field = hanging_grid();
+    let weight = with_field.config.gravity * with_field.config.mass;
+    with_field.apply_external_force_field(move |_, _| weight);
+
+    for _ in 0..STEPS {
+        with_gravity.advance(DELTA_T, true, false);
+        with_field.advance(DELTA_T, false, false);
+    }
+    assert_eq!(with_field.positions, with_gravity.positions);
+    assert_eq!(with_field.velocities, with_gravity.velocities);
+    assert_eq!(with_field.debug_forces(SIZE, false, false).field, weight);
+}
+
+#[test]
+fn field_sees_position_and_time() {
+    let force = Vec2::new(0.0, 1.0);
+    let start = 0.5;
+    // Only pushes right of the origin, and only from `start` on.
+    let field = move |position: Vec2, time: f32| if position.x > 0.0 && time >= start { force } else { Vec2::ZERO };
+    let mut left = Grid::single_node(Vec2::new(-1.0, 0.0), Vec2::ZERO, Vec2::ZERO, DELTA_T);
+    let mut right = Grid::single_node(Vec2::new(1.0, 0.0), Vec2::ZERO, Vec2::ZERO, DELTA_T);
+    left.apply_external_force_field(field);
+    right.apply_external_force_field(field);
+
+    while right.time < start - DELTA_T / 2.0 {
+        right.advance(DELTA_T, false, false);
+        left.advance(DELTA_T, false, false);
+    }
+    assert_eq!(right.positions[0], Vec2::new(1.0, 0.0));
+    for _ in 0..STEPS {
+        right.advance(DELTA_T, false, false);
+        left.advance(DELTA_T, false, false);
+    }
+    assert!(right.positions[0].y > 0.0);
+    assert_eq!(left.positions[0], Vec2::new(-1.0, 0.0));
+}
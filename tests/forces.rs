@@ -0,0 +1,41 @@
+// The force breakdown has to add up to exactly what the next step applies.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 6;
+const DELTA_T: f32 = 0.01;
+
+#[test]
+fn breakdown_matches_the_step() {
+    let config = SimulationConfig {
+        parallel: false,
+        quadratic_drag: true,
+        wind: Vec2::new(0.3, -0.1),
+        seed: 11,
+        ..SimulationConfig::default()
+    };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    grid.get_bending_neighbors();
+    grid.bending = true;
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    for _ in 0..30 {
+        grid.advance(DELTA_T, true, true);
+    }
+
+    let breakdowns: Vec<_> = (0..grid.positions.len()).map(|index| grid.debug_forces(index, true, true)).collect();
+    let velocities = grid.velocities.clone();
+    grid.advance(DELTA_T, true, true);
+    for (index, forces) in breakdowns.iter().enumerate() {
+        if grid.fixed[index] {
+            continue;
+        }
+        let expected = velocities[index] + forces.total() / grid.masses[index] * DELTA_T;
+        assert_eq!(grid.velocities[index], expected, "node {} with {:?}", index, forces);
+        assert_ne!(forces.spring, Vec2::ZERO);
+        assert_ne!(forces.external, Vec2::ZERO);
+    }
+}
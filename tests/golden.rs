@@ -0,0 +1,62 @@
+// Tripwire for unintended physics changes: a fixed simulation has to end in exactly the recorded
+// state. When the physics is changed on purpose, run with `--nocapture` and update the golden
+// hashes to the ones printed.
+//
+// The random external force is left off, since its values depend on the rand version rather than
+// on this crate.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Connectivity, Grid, Integrator, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 8;
+const STEPS: usize = 500;
+const DELTA_T: f32 = 0.01;
+const IMPULSE: Vec2 = Vec2::new(1.0, 2.0);
+
+// Each integrator with gravity on, then with it off, so both paths through the force pass are pinned.
+const GOLDEN: [(Integrator, bool, u64); 8] = [
+    (Integrator::SymplecticEuler, true, 0x3012_b212_5934_0740),
+    (Integrator::Verlet, true, 0xf324_ee85_f45d_ebb9),
+    (Integrator::Rk4, true, 0x1a1d_8298_de7f_99df),
+    (Integrator::PositionBased { iterations: 10 }, true, 0x1c21_0b84_626d_9776),
+    (Integrator::SymplecticEuler, false, 0xc1b5_0b62_2f88_e544),
+    (Integrator::Verlet, false, 0xebc0_661e_0f82_2c4a),
+    (Integrator::Rk4, false, 0xe35c_b747_19f8_fabc),
+    (Integrator::PositionBased { iterations: 10 }, false, 0x969c_2706_427e_df0e),
+];
+
+fn run(integrator: Integrator, gravity: bool) -> Grid {
+    let config = SimulationConfig { integrator, parallel: false, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.connectivity = Connectivity::Eight;
+    grid.get_neighbors();
+    grid.get_bending_neighbors();
+    grid.bending = true;
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    grid.apply_impulse(IMPULSE, DELTA_T);
+    for _ in 0..STEPS {
+        grid.advance(DELTA_T, gravity, false);
+    }
+    grid
+}
+
+#[test]
+fn state_matches_the_golden_hash() {
+    for (integrator, gravity, golden) in GOLDEN {
+        let hash = run(integrator, gravity).state_hash();
+        println!("{:?} with gravity {}: {:#018x}", integrator, gravity, hash);
+        assert_eq!(hash, golden, "state hash changed for {:?} with gravity {}", integrator, gravity);
+    }
+}
+
+#[test]
+fn equal_runs_hash_alike() {
+    assert_eq!(run(Integrator::default(), true).state_hash(), run(Integrator::default(), true).state_hash());
+    assert_ne!(run(Integrator::default(), true).state_hash(), run(Integrator::default(), false).state_hash());
+    let mut grid = run(Integrator::default(), true);
+    let hash = grid.state_hash();
+    grid.velocities[0].x += 1.0;
+    assert_ne!(grid.state_hash(), hash);
+}
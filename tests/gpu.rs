@@ -0,0 +1,62 @@
+// The GPU step is handed its springs as flat link lists, which have to hold every spring the CPU
+// step would use, and the grid only goes to it when the GPU can do everything the grid needs.
+// Comparing the two steps themselves needs an OpenGL 4.3 context, which `gpu::verify` checks
+// whenever --gpu starts.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::gpu::{self, SpringLinks};
+use soft_body_sim_rust::{Grid, Integrator, SimulationConfig, Vec2};
+
+const SIZE: usize = 5;
+
+fn grid(config: SimulationConfig) -> Grid {
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    grid.get_bending_neighbors();
+    grid
+}
+
+// Each node's links as (other node, relax distance, whether it's a bending spring), in order.
+fn links_of(links: &SpringLinks, index: usize) -> Vec<(usize, f32, bool)> {
+    let range = links.offsets[index] as usize..links.offsets[index + 1] as usize;
+    range.map(|link| (links.nodes[link] as usize, links.relax_distances[link], links.bending[link] != 0)).collect()
+}
+
+#[test]
+fn links_hold_every_spring() {
+    let mut grid = grid(SimulationConfig::default());
+    for bending in [false, true] {
+        grid.bending = bending;
+        let links = SpringLinks::from_grid(&grid);
+        assert_eq!(links.offsets.len(), grid.positions.len() + 1);
+        for index in 0..grid.positions.len() {
+            let structural = grid.neighbours[index].iter().map(|&(other, relax)| (other, relax, false));
+            let bending_links = grid.bending_neighbours[index].iter().map(|&(other, relax)| (other, relax, true));
+            let expected: Vec<_> = if bending { structural.chain(bending_links).collect() } else { structural.collect() };
+            assert_eq!(links_of(&links, index), expected, "node {} with bending {}", index, bending);
+        }
+    }
+}
+
+#[test]
+fn default_grid_can_run_on_the_gpu() {
+    assert_eq!(gpu::unsupported(&grid(SimulationConfig::default()), false), None);
+}
+
+#[test]
+fn features_the_gpu_lacks_keep_the_grid_on_the_cpu() {
+    assert!(gpu::unsupported(&grid(SimulationConfig::default()), true).is_some());
+    let configs = [
+        SimulationConfig { integrator: Integrator::Verlet, ..SimulationConfig::default() },
+        SimulationConfig { quadratic_drag: true, ..SimulationConfig::default() },
+        SimulationConfig { tear_threshold: 2.0, ..SimulationConfig::default() },
+        SimulationConfig { self_collision: true, ..SimulationConfig::default() },
+    ];
+    for config in configs {
+        assert!(gpu::unsupported(&grid(config), false).is_some(), "{:?}", config);
+    }
+    let mut with_sphere = grid(SimulationConfig::default());
+    with_sphere.add_sphere(Vec2::ZERO, 1.0);
+    assert!(gpu::unsupported(&with_sphere, false).is_some());
+}
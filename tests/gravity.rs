@@ -0,0 +1,39 @@
+// A free grid has to fall further over the same time the stronger gravity is, and rise when
+// gravity points up.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 4;
+const STEPS: usize = 50;
+const DELTA_T: f32 = 0.01;
+
+// Downward strengths in m/s^2, from upward through the Moon and Earth to Jupiter.
+const STRENGTHS: [f32; 4] = [-9.81, 1.6, 9.81, 24.8];
+
+// How far the grid's centre of mass drops after `STEPS` steps from rest.
+fn drop(strength: f32) -> f32 {
+    let config = SimulationConfig { gravity: Vec2::new(0.0, -strength), ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    let mean_y = |grid: &Grid| grid.positions.iter().map(|position| position.y).sum::<f32>() / grid.positions.len() as f32;
+    let start = mean_y(&grid);
+    for _ in 0..STEPS {
+        grid.advance(DELTA_T, true, false);
+    }
+    start - mean_y(&grid)
+}
+
+#[test]
+fn stronger_gravity_falls_faster() {
+    let drops: Vec<f32> = STRENGTHS.iter().map(|&strength| drop(strength)).collect();
+    for pair in drops.windows(2) {
+        assert!(pair[0] < pair[1], "drops {:?} for strengths {:?}", drops, STRENGTHS);
+    }
+}
+
+#[test]
+fn negative_gravity_rises() {
+    assert!(drop(-9.81) < 0.0);
+}
@@ -0,0 +1,57 @@
+// A torn spring has to come back once its ends are pushed together again, but not while they're
+// still near the tearing point.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const TEAR_THRESHOLD: f32 = 1.5;
+const DELTA_T: f32 = 0.01;
+
+// Two nodes one relax distance apart, the first pinned so only the second moves.
+fn pair(heal_threshold: f32) -> Grid {
+    let config = SimulationConfig { tear_threshold: TEAR_THRESHOLD, heal: true, heal_threshold, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(2, 1, config);
+    grid.get_neighbors();
+    grid.set_fixed(&[0]);
+    grid
+}
+
+fn move_to(grid: &mut Grid, distance: f32) {
+    let position = grid.positions[0] + Vec2::new(distance, 0.0);
+    grid.drag_node(1, position);
+    grid.advance(DELTA_T, false, false);
+}
+
+#[test]
+fn torn_spring_heals_when_compressed() {
+    let mut grid = pair(1.0);
+    move_to(&mut grid, 3.0);
+    assert!(!grid.is_connected(0, 1), "spring should tear when stretched past the threshold");
+    assert_eq!(grid.torn_springs.len(), 1);
+
+    move_to(&mut grid, 0.8);
+    assert!(grid.is_connected(0, 1) && grid.is_connected(1, 0), "spring should heal once compressed");
+    assert!(grid.torn_springs.is_empty());
+}
+
+#[test]
+fn heal_threshold_stays_below_tear_threshold() {
+    // Asked to heal beyond the tearing point, it still leaves a gap between the two.
+    let mut grid = pair(2.0 * TEAR_THRESHOLD);
+    move_to(&mut grid, 3.0);
+    assert!(!grid.is_connected(0, 1));
+
+    move_to(&mut grid, 1.45);
+    assert!(!grid.is_connected(0, 1), "spring healed inside the hysteresis band");
+}
+
+#[test]
+fn reset_forgets_torn_springs() {
+    let mut grid = pair(1.0);
+    grid.save_initial_state();
+    move_to(&mut grid, 3.0);
+    grid.reset();
+    assert!(grid.is_connected(0, 1));
+    assert!(grid.torn_springs.is_empty());
+}
@@ -0,0 +1,83 @@
+// Each starting velocity pattern gives the free nodes what it describes, leaves pinned nodes at
+// rest, and sets the previous positions so Verlet starts moving the same way.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Vec2};
+
+// Odd, so the middle of the grid falls on a node.
+const SIZE: usize = 5;
+const DELTA_T: f32 = 0.01;
+const SPEED: f32 = 2.0;
+const TOLERANCE: f32 = 1e-5;
+
+fn pinned_grid() -> Grid {
+    let mut grid = Grid::new(SIZE, SIZE);
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    grid
+}
+
+fn assert_close(actual: Vec2, expected: Vec2, what: &str) {
+    assert!((actual - expected).length() < TOLERANCE, "{}: {:?} instead of {:?}", what, actual, expected);
+}
+
+fn assert_consistent(grid: &Grid) {
+    for index in 0..grid.positions.len() {
+        // Compared as positions, since dividing their small difference by the step loses too much.
+        let expected = grid.positions[index] - grid.velocities[index] * DELTA_T;
+        assert_close(grid.prev_positions[index], expected, &format!("previous position of node {}", index));
+        if grid.fixed[index] {
+            assert_eq!(grid.velocities[index], Vec2::ZERO, "pinned node {}", index);
+        }
+    }
+}
+
+#[test]
+fn uniform_velocity_moves_every_free_node_alike() {
+    let mut grid = pinned_grid();
+    let velocity = Vec2::new(0.5, -1.5);
+    grid.set_uniform_velocity(velocity, DELTA_T);
+    for index in (0..grid.positions.len()).filter(|&index| !grid.fixed[index]) {
+        assert_eq!(grid.velocities[index], velocity, "node {}", index);
+    }
+    assert_consistent(&grid);
+}
+
+#[test]
+fn radial_velocity_points_away_from_the_middle() {
+    let mut grid = pinned_grid();
+    grid.set_radial_velocity(SPEED, DELTA_T);
+    let middle = grid.get_index(SIZE / 2, SIZE / 2);
+    assert_eq!(grid.velocities[middle], Vec2::ZERO);
+    assert_close(grid.velocities[grid.get_index(SIZE - 1, SIZE / 2)], Vec2::new(SPEED, 0.0), "right edge");
+    assert_close(grid.velocities[grid.get_index(SIZE / 2, 0)], Vec2::new(0.0, -SPEED), "bottom edge");
+    let diagonal = SPEED / 2.0f32.sqrt();
+    assert_close(grid.velocities[grid.get_index(0, 0)], Vec2::new(-diagonal, -diagonal), "bottom left corner");
+    assert_consistent(&grid);
+}
+
+#[test]
+fn shear_velocity_grows_with_height() {
+    let mut grid = pinned_grid();
+    grid.set_shear_velocity(SPEED, DELTA_T);
+    for y in 0..SIZE {
+        let expected = Vec2::new(SPEED * (y as f32 - (SIZE / 2) as f32) / (SIZE / 2) as f32, 0.0);
+        // The top corners are pinned, so check the row's middle node.
+        assert_close(grid.velocities[grid.get_index(SIZE / 2, y)], expected, &format!("row {}", y));
+    }
+    assert_consistent(&grid);
+}
+
+#[test]
+fn patterns_survive_a_reset_once_saved() {
+    let mut grid = pinned_grid();
+    grid.set_radial_velocity(SPEED, DELTA_T);
+    grid.save_initial_state();
+    let velocities = grid.velocities.clone();
+    grid.velocities.fill(Vec2::ZERO);
+    grid.prev_positions.clone_from(&grid.positions);
+    grid.reset();
+    assert_eq!(grid.velocities, velocities);
+    assert_consistent(&grid);
+}
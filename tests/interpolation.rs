@@ -0,0 +1,29 @@
+// Nodes are drawn `render_alpha` of the way from their previous positions to their current ones.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, Vec2};
+
+const SIZE: usize = 4;
+const DELTA_T: f32 = 0.01;
+
+fn drawn(grid: &mut Grid, alpha: f32) -> Vec<Vec2> {
+    grid.render_alpha = alpha;
+    grid.create_points().into_iter().map(|vertex| vertex.position.into()).collect()
+}
+
+#[test]
+fn points_are_drawn_between_steps() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    for _ in 0..5 {
+        grid.advance(DELTA_T, true, false);
+    }
+    let (previous, current) = (grid.prev_positions.clone(), grid.positions.clone());
+    assert_ne!(previous, current);
+    assert_eq!(drawn(&mut grid, 0.0), previous);
+    assert_eq!(drawn(&mut grid, 1.0), current);
+    for ((halfway, before), after) in drawn(&mut grid, 0.5).into_iter().zip(previous).zip(current) {
+        assert!((halfway - (before + after) * 0.5).length() < 1e-5);
+    }
+}
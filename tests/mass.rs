@@ -0,0 +1,63 @@
+// Each node's mass scales how much its forces move it, so weighing a sheet's bottom row down keeps
+// it hanging straighter under a sideways wind, and masses the step can't divide by are refused.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 5;
+const DELTA_T: f32 = 0.01;
+// Long enough for the damped sheet to settle at its angle to the wind.
+const STEPS: usize = 3000;
+// About half the weight of a node at the default mass.
+const WIND: Vec2 = Vec2::new(0.05, 0.0);
+
+// How far the bottom row ends up blown sideways from where it started, with that row's nodes
+// `weight` times the usual mass and the whole top row pinned.
+fn bottom_row_deflection(weight: f32) -> f32 {
+    let mut grid = Grid::with_config(SIZE, SIZE, SimulationConfig { wind: WIND, ..SimulationConfig::default() });
+    grid.get_neighbors();
+    let top: Vec<usize> = (0..SIZE).map(|x| grid.get_index(x, SIZE - 1)).collect();
+    grid.set_fixed(&top);
+    let bottom: Vec<usize> = (0..SIZE).map(|x| grid.get_index(x, 0)).collect();
+    for &index in &bottom {
+        grid.set_mass(index, weight * grid.config.mass);
+    }
+    let start: f32 = bottom.iter().map(|&index| grid.positions[index].x).sum();
+    for _ in 0..STEPS {
+        grid.advance(DELTA_T, true, false);
+    }
+    let end: f32 = bottom.iter().map(|&index| grid.positions[index].x).sum();
+    (end - start) / SIZE as f32
+}
+
+#[test]
+fn heavier_bottom_row_hangs_straighter() {
+    let (light, heavy) = (bottom_row_deflection(1.0), bottom_row_deflection(10.0));
+    assert!(light > 0.0, "the wind didn't move the sheet");
+    assert!(heavy < 0.5 * light, "deflected {} with a heavy bottom row against {} without", heavy, light);
+}
+
+#[test]
+#[should_panic(expected = "finite and positive")]
+fn zero_mass_is_refused() {
+    Grid::new(SIZE, SIZE).set_mass(0, 0.0);
+}
+
+#[test]
+#[should_panic(expected = "finite and positive")]
+fn negative_mass_is_refused() {
+    Grid::new(SIZE, SIZE).set_mass(0, -1.0);
+}
+
+#[test]
+#[should_panic(expected = "finite and positive")]
+fn infinite_mass_is_refused() {
+    Grid::new(SIZE, SIZE).set_mass(0, f32::INFINITY);
+}
+
+#[test]
+#[should_panic(expected = "finite and positive")]
+fn nan_mass_is_refused() {
+    Grid::new(SIZE, SIZE).set_mass(0, f32::NAN);
+}
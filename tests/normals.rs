@@ -0,0 +1,63 @@
+// The filled mesh is lit by normals of the height its compressed springs would buckle to, so an
+// untouched sheet faces the viewer everywhere and a squeezed one tilts towards its edges.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Vec2};
+
+const SIZE: usize = 9;
+const TOLERANCE: f32 = 1e-5;
+
+fn grid() -> Grid {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    grid
+}
+
+fn length((x, y, z): (f32, f32, f32)) -> f32 {
+    (x * x + y * y + z * z).sqrt()
+}
+
+#[test]
+fn sheet_at_rest_faces_the_viewer() {
+    let grid = grid();
+    assert!(grid.node_heights().iter().all(|&height| height == 0.0));
+    for (index, normal) in grid.vertex_normals(&grid.intact_triangles()).into_iter().enumerate() {
+        assert_eq!(normal, (0.0, 0.0, 1.0), "node {}", index);
+    }
+}
+
+#[test]
+fn squeezed_middle_tilts_its_neighbours_outwards() {
+    let mut grid = grid();
+    let center = grid.get_index(SIZE / 2, SIZE / 2);
+    // Pull the middle column's neighbours in towards it, so only springs around it are compressed.
+    for (x, shift) in [(SIZE / 2 - 1, 0.4), (SIZE / 2 + 1, -0.4)] {
+        for y in 0..SIZE {
+            let index = grid.get_index(x, y);
+            grid.positions[index] += Vec2::new(shift, 0.0);
+            grid.prev_positions[index] = grid.positions[index];
+        }
+    }
+    let heights = grid.node_heights();
+    assert!(heights[center] > 0.0);
+    assert_eq!(heights[grid.get_index(0, SIZE / 2)], 0.0);
+
+    let normals = grid.vertex_normals(&grid.intact_triangles());
+    for normal in &normals {
+        assert!((length(*normal) - 1.0).abs() < TOLERANCE, "{:?}", normal);
+        assert!(normal.2 > 0.0, "{:?}", normal);
+    }
+    // The ridge runs along the middle column, so either side of it slopes away from it.
+    let left = normals[grid.get_index(SIZE / 2 - 2, SIZE / 2)];
+    let right = normals[grid.get_index(SIZE / 2 + 2, SIZE / 2)];
+    assert!(left.0 < 0.0, "{:?}", left);
+    assert!(right.0 > 0.0, "{:?}", right);
+}
+
+#[test]
+fn node_on_no_face_faces_the_viewer() {
+    let grid = grid();
+    let normals = grid.vertex_normals(&[]);
+    assert!(normals.iter().all(|&normal| normal == (0.0, 0.0, 1.0)));
+}
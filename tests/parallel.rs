@@ -0,0 +1,77 @@
+// Running the passes on the rayon pool only changes who does the work, not the result, so a
+// parallel run has to follow the serial one. Sums over the whole grid, such as the centre of mass,
+// are reduced in a different order, so the two may differ in the last bits but no further.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 10;
+const DELTA_T: f32 = 0.005;
+const STEPS: usize = 300;
+const EPSILON: f32 = 1e-5;
+
+fn grid(config: SimulationConfig) -> Grid {
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    grid
+}
+
+// Steps a serial and a parallel copy of the grid `build` makes and checks every node agrees.
+fn assert_parallel_matches_serial(what: &str, config: SimulationConfig, build: impl Fn(SimulationConfig) -> Grid) {
+    let mut serial = build(SimulationConfig { parallel: false, ..config });
+    let mut parallel = build(SimulationConfig { parallel: true, ..config });
+    for _ in 0..STEPS {
+        serial.advance(DELTA_T, true, false);
+        parallel.advance(DELTA_T, true, false);
+    }
+    for (index, (&expected, &actual)) in serial.positions.iter().zip(&parallel.positions).enumerate() {
+        assert!(expected.x.is_finite() && expected.y.is_finite(), "{}: node {} at {:?}", what, index, expected);
+        assert!(
+            (actual - expected).length() <= EPSILON * (1.0 + expected.length()),
+            "{}: node {} at {:?} in parallel, {:?} serially",
+            what,
+            index,
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn hanging_grid_matches() {
+    assert_parallel_matches_serial("hanging", SimulationConfig::default(), |config| {
+        let mut grid = grid(config);
+        let anchors = grid.top_corners().unwrap();
+        grid.set_fixed(&anchors);
+        grid
+    });
+}
+
+#[test]
+fn falling_grid_with_collisions_matches() {
+    let config = SimulationConfig {
+        floor_y: 0.0,
+        self_collision: true,
+        hold_center_of_mass: true,
+        ..SimulationConfig::default()
+    };
+    assert_parallel_matches_serial("falling", config, |config| {
+        let mut grid = grid(config);
+        grid.add_sphere(Vec2::new(4.5, 4.0), 2.0);
+        grid
+    });
+}
+
+#[test]
+fn bounding_box_matches() {
+    let (mut serial, mut parallel) = (
+        grid(SimulationConfig { parallel: false, ..SimulationConfig::default() }),
+        grid(SimulationConfig::default()),
+    );
+    for _ in 0..STEPS {
+        serial.advance(DELTA_T, true, false);
+        parallel.advance(DELTA_T, true, false);
+    }
+    assert_eq!(serial.bounding_box(), parallel.bounding_box());
+}
@@ -0,0 +1,51 @@
+// Position based steps correct each spring by its constraint stiffness, so the cloth stretches
+// further the lower it is, and stay finite however stiff the spring coefficients behind it are.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Integrator, Simulation, SimulationConfig};
+
+const SIZE: usize = 6;
+const STEPS: usize = 300;
+const DELTA_T: f32 = 0.01;
+const PBD: Integrator = Integrator::PositionBased { iterations: 10 };
+
+// Hangs a grid from its top corners for `STEPS` steps with gravity on and returns it.
+fn hang(config: SimulationConfig) -> Grid {
+    let mut grid = Grid::with_config(SIZE, SIZE, SimulationConfig { integrator: PBD, ..config });
+    grid.get_neighbors();
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    for step in 0..STEPS {
+        grid.advance(DELTA_T, true, false);
+        assert!(grid.has_nonfinite().is_none(), "non-finite state after {} steps with {:?}", step + 1, grid.config);
+    }
+    grid
+}
+
+// How far the middle of the bottom row has dropped below the anchors.
+fn sag(grid: &Grid) -> f32 {
+    grid.positions[grid.get_index(0, SIZE - 1)].y - grid.positions[grid.get_index(SIZE / 2, 0)].y
+}
+
+#[test]
+fn extreme_stiffness_stays_finite() {
+    for spring_coefficient in [1e6, 1e12, f32::MAX] {
+        for constraint_stiffness in [0.0, 0.5, 1.0, 10.0] {
+            hang(SimulationConfig { spring_coefficient, constraint_stiffness, ..SimulationConfig::default() });
+        }
+    }
+    // Rows many orders of magnitude stiffer than columns scale the column corrections right down.
+    hang(SimulationConfig { stiffness_x: Some(1e12), stiffness_y: Some(1e-6), ..SimulationConfig::default() });
+}
+
+#[test]
+fn softer_constraints_sag_further() {
+    let sags: Vec<f32> = [1.0, 0.5, 0.1]
+        .into_iter()
+        .map(|constraint_stiffness| sag(&hang(SimulationConfig { constraint_stiffness, ..SimulationConfig::default() })))
+        .collect();
+    for pair in sags.windows(2) {
+        assert!(pair[0] < pair[1], "sags {:?}", sags);
+    }
+}
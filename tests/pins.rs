@@ -0,0 +1,58 @@
+// Each --pin preset names the nodes it should, on full grids, masked ones and single rows.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::cli::{Options, Pins};
+use soft_body_sim_rust::{Grid, PinPreset};
+
+const WIDTH: usize = 5;
+const HEIGHT: usize = 4;
+
+#[test]
+fn presets_on_a_full_grid() {
+    let grid = Grid::new(WIDTH, HEIGHT);
+    let top = HEIGHT - 1;
+    let index = |x, y| grid.get_index(x, y);
+    assert_eq!(grid.preset_pins(PinPreset::TopCorners), vec![index(0, top), index(WIDTH - 1, top)]);
+    assert_eq!(grid.preset_pins(PinPreset::TopEdge), (0..WIDTH).map(|x| index(x, top)).collect::<Vec<_>>());
+    assert_eq!(grid.preset_pins(PinPreset::FourCorners),
+               vec![index(0, 0), index(0, top), index(WIDTH - 1, 0), index(WIDTH - 1, top)]);
+    assert_eq!(grid.preset_pins(PinPreset::Free), vec![]);
+    // With an even height two nodes are equally near the middle, and the lower one is taken.
+    assert_eq!(grid.preset_pins(PinPreset::Center), vec![index(WIDTH / 2, (HEIGHT - 1) / 2)]);
+    assert_eq!(grid.preset_pins(PinPreset::default()), grid.top_corners().unwrap().to_vec());
+}
+
+#[test]
+fn presets_skip_missing_nodes() {
+    // The top row's ends are cut away, so the corners move in to the nodes that are left.
+    let mut present = vec![true; WIDTH * HEIGHT];
+    let top = HEIGHT - 1;
+    present[top] = false;
+    present[(WIDTH - 1) * HEIGHT + top] = false;
+    let grid = Grid::with_presence(WIDTH, HEIGHT, present);
+    let index = |x, y| grid.get_index(x, y);
+    assert_eq!(grid.preset_pins(PinPreset::TopCorners), vec![index(1, top), index(WIDTH - 2, top)]);
+    assert_eq!(grid.preset_pins(PinPreset::TopEdge).len(), WIDTH - 2);
+    for pin in grid.preset_pins(PinPreset::FourCorners) {
+        assert!(grid.present[pin]);
+    }
+}
+
+#[test]
+fn presets_on_degenerate_grids() {
+    let row = Grid::new(WIDTH, 1);
+    assert_eq!(row.preset_pins(PinPreset::FourCorners), row.preset_pins(PinPreset::TopCorners));
+    let single = Grid::new(1, 1);
+    assert_eq!(single.preset_pins(PinPreset::FourCorners), vec![0]);
+    assert_eq!(Grid::new(0, 0).preset_pins(PinPreset::Center), vec![]);
+}
+
+#[test]
+fn pin_flag_picks_a_preset() {
+    let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string())).pins;
+    assert_eq!(parse(&[]), None);
+    assert_eq!(parse(&["--pin", "top-edge"]), Some(Pins::Preset(PinPreset::TopEdge)));
+    assert_eq!(parse(&["--pin=none"]), Some(Pins::Preset(PinPreset::Free)));
+    assert_eq!(parse(&["--pin", "center", "--pin", "sideways"]), Some(Pins::Preset(PinPreset::Center)));
+}
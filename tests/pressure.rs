@@ -0,0 +1,71 @@
+// A closed loop whose springs are relaxed at the wrong size has to be pushed back toward its target
+// area by the pressure alone.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, Vec2};
+
+const NODES: usize = 16;
+const STEPS: usize = 500;
+const DELTA_T: f32 = 0.01;
+const PRESSURE_COEFFICIENT: f32 = 5.0;
+
+// A single ring relaxed at `scale` times its size, with its original area as the target.
+fn rescaled_ring(scale: f32, pressure_coefficient: f32) -> (Grid, f32) {
+    let mut grid = Grid::tube(NODES, 1);
+    grid.get_neighbors();
+    let target = grid.enclosed_area().unwrap();
+    let (min, max) = grid.bounding_box().unwrap();
+    grid.transform(scale, 0.0, (min + max) * 0.5);
+    grid.set_rest_from_current();
+    grid.config.pressure_coefficient = pressure_coefficient;
+    grid.config.target_area = target;
+    (grid, target)
+}
+
+fn settle(grid: &mut Grid) -> f32 {
+    for _ in 0..STEPS {
+        grid.advance(DELTA_T, false, false);
+    }
+    assert_eq!(grid.has_nonfinite(), None);
+    grid.enclosed_area().unwrap()
+}
+
+#[test]
+fn collapsed_loop_reinflates() {
+    let (mut grid, target) = rescaled_ring(0.5, PRESSURE_COEFFICIENT);
+    let collapsed = grid.enclosed_area().unwrap();
+    assert!(collapsed < 0.3 * target);
+
+    // Pressure points away from the middle of the ring.
+    let (min, max) = grid.bounding_box().unwrap();
+    let center = (min + max) * 0.5;
+    for index in 0..NODES {
+        let pressure = grid.debug_forces(index, false, false).pressure;
+        assert!(pressure.dot(grid.positions[index] - center) > 0.0, "node {} pushed {:?}", index, pressure);
+    }
+
+    let area = settle(&mut grid);
+    assert!((area - target).abs() < 0.1 * target, "area {} for target {}", area, target);
+}
+
+#[test]
+fn overinflated_loop_shrinks() {
+    let (mut grid, target) = rescaled_ring(1.5, PRESSURE_COEFFICIENT);
+    let area = settle(&mut grid);
+    assert!((area - target).abs() < 0.1 * target, "area {} for target {}", area, target);
+}
+
+#[test]
+fn without_pressure_the_loop_keeps_its_size() {
+    let (mut grid, _) = rescaled_ring(0.5, 0.0);
+    let collapsed = grid.enclosed_area().unwrap();
+    assert_eq!(grid.debug_forces(0, false, false).pressure, Vec2::ZERO);
+    let area = settle(&mut grid);
+    assert!((area - collapsed).abs() < 0.01 * collapsed, "area {} from {}", area, collapsed);
+}
+
+#[test]
+fn open_grids_enclose_nothing() {
+    assert_eq!(Grid::new(NODES, 2).enclosed_area(), None);
+}
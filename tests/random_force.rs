@@ -0,0 +1,44 @@
+// The random external force is reproducible from its seed, and nearby seeds give unrelated noise
+// rather than the same noise handed along to the next node.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 5;
+const STEPS: usize = 20;
+const DELTA_T: f32 = 0.01;
+const SEED: u64 = 42;
+
+fn grid(seed: u64) -> Grid {
+    let mut grid = Grid::with_config(SIZE, SIZE, SimulationConfig { seed, ..SimulationConfig::default() });
+    grid.get_neighbors();
+    grid
+}
+
+// The random force on every node on the next step.
+fn noise(grid: &Grid) -> Vec<Vec2> {
+    (0..grid.positions.len()).map(|index| grid.debug_forces(index, false, true).external).collect()
+}
+
+#[test]
+fn two_grids_with_one_seed_run_alike() {
+    let (mut first, mut second) = (grid(SEED), grid(SEED));
+    for _ in 0..STEPS {
+        assert_eq!(noise(&first), noise(&second));
+        first.advance(DELTA_T, true, true);
+        second.advance(DELTA_T, true, true);
+    }
+    assert_eq!(first.positions, second.positions);
+    assert_eq!(first.velocities, second.velocities);
+}
+
+#[test]
+fn neighbouring_seeds_are_not_shifted_copies() {
+    let (current, next) = (noise(&grid(SEED)), noise(&grid(SEED + 1)));
+    assert!(current.iter().any(|&force| force != Vec2::ZERO));
+    // Adding the seed to the node index would give node i under the next seed node i + 1's force.
+    let shifted = (0..current.len() - 1).filter(|&index| next[index] == current[index + 1]).count();
+    assert_eq!(shifted, 0);
+    assert_ne!(current, next);
+}
@@ -0,0 +1,82 @@
+// A pre-stretched grid pinned at its top corners should only ever lose spring energy while it's
+// being relaxed.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Connectivity, Grid, SimulationConfig, Vec2};
+
+const SIZE: usize = 8;
+const ITERATIONS: usize = 300;
+// Initial spacing as a multiple of the relax distance.
+const STRETCH: f32 = 1.5;
+
+fn stretched_grid(connectivity: Connectivity) -> Grid {
+    let config = SimulationConfig { spacing: STRETCH, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.build_neighbours(connectivity);
+    grid.get_bending_neighbors();
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    grid
+}
+
+#[test]
+fn spring_energy_decreases_monotonically() {
+    for connectivity in [Connectivity::Four, Connectivity::Eight] {
+        let mut grid = stretched_grid(connectivity);
+        let (_, initial) = grid.total_energy();
+        let mut previous = initial;
+        for iteration in 0..ITERATIONS {
+            grid.relax(1);
+            let (_, potential) = grid.total_energy();
+            assert!(
+                potential <= previous,
+                "spring energy rose from {} to {} on iteration {} ({:?})",
+                previous,
+                potential,
+                iteration,
+                connectivity
+            );
+            previous = potential;
+        }
+        assert!(previous < 0.5 * initial, "spring energy only fell from {} to {}", initial, previous);
+    }
+}
+
+#[test]
+fn relaxing_leaves_the_grid_at_rest() {
+    let mut grid = stretched_grid(Connectivity::Four);
+    grid.relax(ITERATIONS);
+    assert!(grid.velocities.iter().all(|velocity| *velocity == Vec2::ZERO));
+    assert_eq!(grid.step_count, 0);
+}
+
+#[test]
+fn relaxing_runs_nothing_but_the_springs() {
+    // Already at rest, so any movement comes from something other than the springs.
+    let config = SimulationConfig {
+        floor_y: 100.0,
+        hold_center_of_mass: true,
+        wind: Vec2::new(5.0, 0.0),
+        ..SimulationConfig::default()
+    };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    grid.apply_external_force_field(|_, _| Vec2::new(0.0, 50.0));
+    grid.set_kinematic(0, |time| Vec2::new(time, 0.0));
+    let positions = grid.positions.clone();
+    grid.relax(ITERATIONS);
+    assert_eq!(grid.positions, positions);
+    assert_eq!(grid.time, 0.0);
+}
+
+#[test]
+fn relaxing_never_tears() {
+    let mut grid = stretched_grid(Connectivity::Four);
+    // Every spring starts past this, so a step would tear them all.
+    grid.config.tear_threshold = 1.2;
+    let springs = grid.neighbours.clone();
+    grid.relax(ITERATIONS);
+    assert_eq!(grid.neighbours, springs);
+    assert!(grid.torn_springs.is_empty());
+}
@@ -0,0 +1,47 @@
+// An input log has to read back exactly what was recorded, points included, so a replay puts the
+// same inputs back between the same steps.
+
+extern crate soft_body_sim_rust;
+
+use std::fs;
+use std::io;
+
+use soft_body_sim_rust::replay::{self, Input, InputRecorder};
+use soft_body_sim_rust::Vec2;
+
+fn inputs() -> Vec<(u64, Input)> {
+    vec![
+        (0, Input::Key("tab".to_string())),
+        (3, Input::Key("PageUp".to_string())),
+        (3, Input::Grab(Vec2::new(0.1, -2.75))),
+        (4, Input::Move(Vec2::new(1.0 / 3.0, f32::MIN_POSITIVE))),
+        (9, Input::Release),
+        (12, Input::Ripple(Vec2::new(-7.5, 1e-7))),
+        (15, Input::Paint { position: Vec2::new(2.0, 3.0), fixed: true }),
+        (15, Input::Paint { position: Vec2::new(2.0, 3.0), fixed: false }),
+        (20, Input::TogglePin(Vec2::new(123.456, -0.001))),
+    ]
+}
+
+#[test]
+fn recorded_inputs_load_back_unchanged() {
+    let path = std::env::temp_dir().join("soft_body_replay_round_trip.log");
+    let mut recorder = InputRecorder::create(&path, "started with: --width 5").unwrap();
+    for (step, input) in inputs() {
+        recorder.record(step, &input).unwrap();
+    }
+    drop(recorder);
+    let loaded = replay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, inputs());
+}
+
+#[test]
+fn unreadable_line_is_reported() {
+    let path = std::env::temp_dir().join("soft_body_replay_invalid.log");
+    fs::write(&path, "# header\n\n3 key g\n4 grab 1.0\n").unwrap();
+    let error = replay::load(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("line 4"), "{}", error);
+}
@@ -0,0 +1,49 @@
+// Resetting returns the grid to its saved state, and to the springs it was built with when nothing
+// was saved.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Connectivity, Grid, Simulation, Vec2};
+
+const SIZE: usize = 4;
+const DELTA_T: f32 = 0.01;
+
+fn spring_count(grid: &Grid) -> usize {
+    grid.neighbours.iter().map(Vec::len).sum()
+}
+
+#[test]
+fn reset_without_a_save_keeps_the_built_springs() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    let springs = grid.neighbours.clone();
+    assert!(spring_count(&grid) > 0);
+    grid.advance(DELTA_T, true, false);
+    grid.reset();
+    assert_eq!(grid.neighbours, springs);
+}
+
+#[test]
+fn reset_keeps_springs_rebuilt_after_a_save() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    grid.save_initial_state();
+    grid.build_neighbours(Connectivity::Eight);
+    let springs = grid.neighbours.clone();
+    grid.reset();
+    assert_eq!(grid.neighbours, springs);
+}
+
+#[test]
+fn reset_restores_positions_and_springs_removed_since() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    let positions = grid.positions.clone();
+    let springs = spring_count(&grid);
+    grid.remove_spring(0, 1);
+    grid.translate(Vec2::new(1.0, 0.0));
+    grid.positions[0] += Vec2::new(0.0, 1.0);
+    grid.reset();
+    assert_eq!(spring_count(&grid), springs);
+    assert_eq!(grid.positions[0], positions[0] + Vec2::new(1.0, 0.0));
+}
@@ -0,0 +1,79 @@
+// Growing and shrinking a grid by a row has to keep every per-node buffer the same length as the
+// node count, and leave the grid able to step.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation};
+
+const WIDTH: usize = 5;
+const HEIGHT: usize = 4;
+
+fn assert_consistent(grid: &Grid) {
+    let size = grid.width * grid.height;
+    assert_eq!(grid.positions.len(), size);
+    assert_eq!(grid.velocities.len(), size);
+    assert_eq!(grid.prev_positions.len(), size);
+    assert_eq!(grid.fixed.len(), size);
+    assert_eq!(grid.present.len(), size);
+    assert_eq!(grid.masses.len(), size);
+    assert_eq!(grid.neighbours.len(), size);
+    assert_eq!(grid.bending_neighbours.len(), size);
+    assert!(grid.neighbours.iter().flatten().all(|&(neighbor_index, _)| neighbor_index < size));
+}
+
+fn hanging_grid() -> Grid {
+    let mut grid = Grid::new(WIDTH, HEIGHT);
+    grid.get_neighbors();
+    grid.get_bending_neighbors();
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    grid.save_initial_state();
+    grid
+}
+
+#[test]
+fn adding_then_removing_a_row_restores_the_grid() {
+    let mut grid = hanging_grid();
+    let positions = grid.positions.clone();
+    let fixed = grid.fixed.clone();
+    let springs: usize = grid.neighbours.iter().map(Vec::len).sum();
+
+    grid.add_bottom_row();
+    assert_eq!(grid.height, HEIGHT + 1);
+    assert_consistent(&grid);
+    // The new row sits one spacing below the old bottom row, and the anchors move with their nodes.
+    assert_eq!(grid.positions[1], positions[0]);
+    assert!(grid.positions[0].y < positions[0].y);
+    assert_eq!(grid.top_corners().map(|[a, b]| (grid.fixed[a], grid.fixed[b])), Some((true, true)));
+
+    assert!(grid.remove_bottom_row());
+    assert_eq!(grid.height, HEIGHT);
+    assert_consistent(&grid);
+    assert_eq!(grid.positions, positions);
+    assert_eq!(grid.fixed, fixed);
+    assert_eq!(grid.neighbours.iter().map(Vec::len).sum::<usize>(), springs);
+}
+
+#[test]
+fn resized_grid_steps_and_resets() {
+    let mut grid = hanging_grid();
+    grid.advance(1e-2, true, true);
+    grid.add_bottom_row();
+    grid.add_bottom_row();
+    grid.advance(1e-2, true, true);
+    assert!(grid.remove_bottom_row());
+    grid.advance(1e-2, true, true);
+    grid.reset();
+    assert_consistent(&grid);
+    grid.advance(1e-2, true, true);
+    assert!(grid.positions.iter().all(|position| position.x.is_finite() && position.y.is_finite()));
+}
+
+#[test]
+fn the_last_row_is_kept() {
+    let mut grid = Grid::rope(WIDTH);
+    grid.get_neighbors();
+    assert!(!grid.remove_bottom_row());
+    assert_eq!(grid.height, 1);
+    assert_consistent(&grid);
+}
@@ -0,0 +1,38 @@
+// Making the current shape the relaxed one has to leave every spring at rest.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Connectivity, Grid, Simulation, SimulationConfig};
+
+const SIZE: usize = 6;
+const DELTA_T: f32 = 0.01;
+
+#[test]
+fn current_shape_becomes_relaxed() {
+    let config = SimulationConfig { spacing: 1.3, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.build_neighbours(Connectivity::Eight);
+    grid.get_bending_neighbors();
+    grid.bending = true;
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    for _ in 0..50 {
+        grid.advance(DELTA_T, true, false);
+    }
+    assert!(grid.total_energy().1 > 1e-3, "the sagging grid should start out strained");
+
+    grid.set_rest_from_current();
+    assert!(grid.total_energy().1 < 1e-8);
+    for index in 0..grid.positions.len() {
+        let forces = grid.debug_forces(index, false, false);
+        assert!(forces.spring.length() < 1e-3, "node {} still pulled by {:?}", index, forces.spring);
+    }
+
+    // Every link still has its mutual partner with the same length.
+    for (index, links) in grid.neighbours.iter().enumerate() {
+        for &(other, rest) in links {
+            let &(_, back) = grid.neighbours[other].iter().find(|&&(link, _)| link == index).unwrap();
+            assert_eq!(rest, back);
+        }
+    }
+}
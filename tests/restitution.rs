@@ -0,0 +1,71 @@
+// A node dropped onto the floor or a sphere rebounds to the height its restitution allows: it
+// sticks at 0, comes back up to where it fell from at 1, and reaches a quarter of the way at 0.5,
+// since the rebound height goes with the square of the rebound speed.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, Vec2};
+
+const DELTA_T: f32 = 0.001;
+// Long enough to fall, bounce and pass the top of the rebound at full restitution.
+const STEPS: usize = 1500;
+const DROP_HEIGHT: f32 = 1.0;
+// Of the drop height, allowing for the step the node lands partway through.
+const TOLERANCE: f32 = 0.05;
+const SPHERE_RADIUS: f32 = 2.0;
+
+#[derive(Copy, Clone)]
+enum Surface {
+    Floor,
+    Sphere,
+}
+
+// Drops a node from `DROP_HEIGHT` above a surface at y = 0 and returns the highest it gets after
+// first touching it.
+fn rebound_height(surface: Surface, restitution: f32) -> f32 {
+    let mut grid = Grid::single_node(Vec2::new(0.0, DROP_HEIGHT), Vec2::ZERO, Vec2::ZERO, DELTA_T);
+    grid.config.restitution = restitution;
+    match surface {
+        Surface::Floor => grid.config.floor_y = 0.0,
+        // The top of the sphere sits right under the node.
+        Surface::Sphere => grid.add_sphere(Vec2::new(0.0, -SPHERE_RADIUS), SPHERE_RADIUS),
+    }
+    let mut peak = None;
+    for _ in 0..STEPS {
+        grid.advance(DELTA_T, true, false);
+        let y = grid.positions[0].y;
+        match peak {
+            None if y <= 0.0 => peak = Some(y),
+            Some(highest) => peak = Some(y.max(highest)),
+            None => {}
+        }
+    }
+    peak.expect("never reached the surface")
+}
+
+fn assert_rebounds_to(surface: Surface, restitution: f32, fraction: f32) {
+    let height = rebound_height(surface, restitution);
+    assert!((height - fraction * DROP_HEIGHT).abs() <= TOLERANCE * DROP_HEIGHT,
+            "rebounded to {} at restitution {} instead of {}", height, restitution, fraction * DROP_HEIGHT);
+}
+
+#[test]
+fn floor_rebound_follows_restitution() {
+    assert_rebounds_to(Surface::Floor, 0.0, 0.0);
+    assert_rebounds_to(Surface::Floor, 1.0, 1.0);
+    assert_rebounds_to(Surface::Floor, 0.5, 0.25);
+}
+
+#[test]
+fn sphere_rebound_follows_restitution() {
+    assert_rebounds_to(Surface::Sphere, 0.0, 0.0);
+    assert_rebounds_to(Surface::Sphere, 1.0, 1.0);
+    assert_rebounds_to(Surface::Sphere, 0.5, 0.25);
+}
+
+#[test]
+fn zero_restitution_sticks() {
+    for surface in [Surface::Floor, Surface::Sphere] {
+        assert_eq!(rebound_height(surface, 0.0), 0.0);
+    }
+}
@@ -0,0 +1,47 @@
+// Ropes and tubes built from a config are laid out with its spacing and keep its settings, as
+// sheets from `Grid::with_config` do.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, SimulationConfig};
+
+const NODES: usize = 12;
+const SPACING: f32 = 1.5;
+const MASS: f32 = 0.2;
+const TOLERANCE: f32 = 1e-4;
+
+fn config() -> SimulationConfig {
+    SimulationConfig { spacing: SPACING, mass: MASS, ..SimulationConfig::default() }
+}
+
+fn assert_spacing(grid: &Grid, pairs: impl Iterator<Item = (usize, usize)>) {
+    for (a, b) in pairs {
+        let distance = (grid.positions[b] - grid.positions[a]).length();
+        assert!((distance - SPACING).abs() < TOLERANCE, "nodes {} and {} are {} apart", a, b, distance);
+    }
+}
+
+#[test]
+fn rope_uses_the_config() {
+    let grid = Grid::rope_with_config(NODES, config());
+    assert_eq!(grid.positions.len(), NODES);
+    assert_eq!(grid.config.spacing, SPACING);
+    assert!(grid.masses.iter().all(|&mass| mass == MASS));
+    assert_spacing(&grid, (1..NODES).map(|index| (index - 1, index)));
+}
+
+#[test]
+fn tube_uses_the_config() {
+    let grid = Grid::tube_with_config(NODES, 1, config());
+    assert_eq!(grid.config.spacing, SPACING);
+    assert!(grid.masses.iter().all(|&mass| mass == MASS));
+    // Around the ring, including the seam from the last node back to the first.
+    assert_spacing(&grid, (0..NODES).map(|index| (index, (index + 1) % NODES)));
+}
+
+#[test]
+fn default_shapes_use_the_default_config() {
+    let spacing = SimulationConfig::default().spacing;
+    assert_eq!(Grid::rope(NODES).config.spacing, spacing);
+    assert_eq!(Grid::tube(NODES, 2).config.spacing, spacing);
+}
@@ -0,0 +1,69 @@
+// A saved grid loads back in the state it was saved in, simulated with the config it's loaded
+// with, and a snapshot that doesn't match its own dimensions is refused rather than loaded.
+#![cfg(feature = "snapshot")]
+
+extern crate soft_body_sim_rust;
+
+use std::fs;
+use std::io;
+
+use soft_body_sim_rust::snapshot;
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig, Vec2};
+
+const SIZE: usize = 4;
+const DELTA_T: f32 = 0.01;
+
+// A grid moved away from its starting state, so a reloaded default grid wouldn't pass for it.
+fn stepped_grid() -> Grid {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    grid.set_fixed(&[0]);
+    for _ in 0..10 {
+        grid.advance(DELTA_T, true, false);
+    }
+    grid
+}
+
+fn config() -> SimulationConfig {
+    SimulationConfig { mass: 2.5, restitution: 0.5, ..SimulationConfig::default() }
+}
+
+fn assert_same_state(loaded: &Grid, saved: &Grid) {
+    assert_eq!((loaded.width, loaded.height), (saved.width, saved.height));
+    assert_eq!(loaded.positions, saved.positions);
+    assert_eq!(loaded.velocities, saved.velocities);
+    assert_eq!(loaded.fixed, saved.fixed);
+}
+
+#[test]
+fn snapshot_round_trips_with_the_given_config() {
+    let grid = stepped_grid();
+    let mut loaded = Grid::from_snapshot(grid.to_snapshot(), config()).unwrap();
+    assert_same_state(&loaded, &grid);
+    assert_eq!(loaded.config.mass, config().mass);
+    assert_eq!(loaded.config.restitution, config().restitution);
+    assert!(loaded.masses.iter().all(|&mass| mass == config().mass));
+
+    // Reset returns to the loaded state rather than to a fresh grid.
+    loaded.positions[1] += Vec2::new(1.0, 0.0);
+    loaded.reset();
+    assert_same_state(&loaded, &grid);
+}
+
+#[test]
+fn saved_file_loads_back() {
+    let grid = stepped_grid();
+    let path = std::env::temp_dir().join("soft_body_snapshot_round_trip.json");
+    snapshot::save(&grid, &path).unwrap();
+    let loaded = snapshot::load(&path, config());
+    fs::remove_file(&path).unwrap();
+    assert_same_state(&loaded.unwrap(), &grid);
+}
+
+#[test]
+fn inconsistent_snapshot_is_refused() {
+    let mut snapshot = stepped_grid().to_snapshot();
+    snapshot.velocities.pop();
+    let error = Grid::from_snapshot(snapshot, config()).err().expect("loaded an inconsistent snapshot");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,47 @@
+// A grid laid out wider apart than its springs' relax distance starts stretched, so left free it
+// pulls itself in; laid out closer together it pushes itself out.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Simulation, SimulationConfig};
+
+const SIZE: usize = 6;
+const DELTA_T: f32 = 0.01;
+const STEPS: usize = 200;
+
+fn width(grid: &Grid) -> f32 {
+    let (min, max) = grid.bounding_box().unwrap();
+    max.x - min.x
+}
+
+// The laid out and stepped widths of a free grid with `spacing`, without gravity.
+fn widths(spacing: f32) -> (f32, f32) {
+    let config = SimulationConfig { spacing, ..SimulationConfig::default() };
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    let initial = width(&grid);
+    for _ in 0..STEPS {
+        grid.advance(DELTA_T, false, false);
+    }
+    (initial, width(&grid))
+}
+
+#[test]
+fn layout_uses_the_spacing() {
+    let (initial, _) = widths(1.5);
+    assert!((initial - 1.5 * (SIZE - 1) as f32).abs() < 1e-4, "{}", initial);
+}
+
+#[test]
+fn wide_spacing_shrinks() {
+    let (initial, stepped) = widths(1.5);
+    let relaxed = (SIZE - 1) as f32;
+    assert!(stepped < initial, "width went from {} to {}", initial, stepped);
+    assert!(stepped < 0.5 * (initial + relaxed), "width {} barely moved from {} towards {}", stepped, initial, relaxed);
+}
+
+#[test]
+fn narrow_spacing_grows() {
+    let (initial, stepped) = widths(0.7);
+    assert!(stepped > initial, "width went from {} to {}", initial, stepped);
+}
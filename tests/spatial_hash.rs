@@ -0,0 +1,77 @@
+// Radius queries find exactly the nodes a brute force search over every position would, wherever
+// they sit relative to the cell boundaries.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::spatial_hash::SpatialHash;
+use soft_body_sim_rust::Vec2;
+
+const CELL_SIZE: f32 = 0.5;
+
+fn brute_force(point: Vec2, radius: f32, positions: &[Vec2]) -> Vec<usize> {
+    (0..positions.len()).filter(|&index| (positions[index] - point).length_squared() <= radius * radius).collect()
+}
+
+fn query(hash: &SpatialHash, point: Vec2, radius: f32, positions: &[Vec2]) -> Vec<usize> {
+    let mut found = hash.query_radius(point, radius, positions);
+    found.sort_unstable();
+    found
+}
+
+// A deterministic scatter over both signs of each axis, denser than a cell.
+fn scattered() -> Vec<Vec2> {
+    (0..200)
+        .map(|index| {
+            let t = index as f32;
+            Vec2::new((t * 0.618_034).fract() * 6.0 - 3.0, (t * 0.414_214).fract() * 6.0 - 3.0)
+        })
+        .collect()
+}
+
+#[test]
+fn scattered_queries_match_a_brute_force_search() {
+    let positions = scattered();
+    let mut hash = SpatialHash::new(CELL_SIZE);
+    hash.rebuild(&positions);
+    for &(point, radius) in &[
+        (Vec2::ZERO, 1.0),
+        (Vec2::new(-2.3, 1.7), 0.3),
+        (Vec2::new(1.25, -0.75), 2.0),
+        (Vec2::new(-2.9, -2.9), 0.8),
+    ] {
+        assert_eq!(query(&hash, point, radius, &positions), brute_force(point, radius, &positions),
+                   "around {:?} within {}", point, radius);
+    }
+}
+
+#[test]
+fn points_exactly_on_the_radius_are_found() {
+    // One on each axis at exactly the radius, each in a different cell from the query point.
+    let positions = vec![Vec2::new(1.0, 0.0), Vec2::new(-1.0, 0.0), Vec2::new(0.0, 1.0), Vec2::new(0.0, -1.0)];
+    let mut hash = SpatialHash::new(CELL_SIZE);
+    hash.rebuild(&positions);
+    assert_eq!(query(&hash, Vec2::ZERO, 1.0, &positions), vec![0, 1, 2, 3]);
+    assert!(query(&hash, Vec2::ZERO, 0.99, &positions).is_empty());
+}
+
+#[test]
+fn negative_cells_are_searched() {
+    // Just either side of the cell boundary at -CELL_SIZE, which floors into different cells.
+    let positions = vec![Vec2::new(-0.49, -0.49), Vec2::new(-0.51, -0.51), Vec2::new(-1.6, -1.6), Vec2::new(0.2, 0.2)];
+    let mut hash = SpatialHash::new(CELL_SIZE);
+    hash.rebuild(&positions);
+    let point = Vec2::new(-0.5, -0.5);
+    assert_eq!(query(&hash, point, 0.1, &positions), vec![0, 1]);
+    assert_eq!(query(&hash, point, 1.0, &positions), vec![0, 1, 3]);
+}
+
+#[test]
+fn degenerate_cell_sizes_still_answer_queries() {
+    let positions = scattered();
+    for cell_size in [0.0, -1.0, f32::NAN] {
+        let mut hash = SpatialHash::new(cell_size);
+        hash.rebuild(&positions);
+        let point = Vec2::new(0.5, -0.5);
+        assert_eq!(query(&hash, point, 0.5, &positions), brute_force(point, 0.5, &positions), "cell size {}", cell_size);
+    }
+}
@@ -0,0 +1,60 @@
+// Nodes that end a step inside a sphere are projected out along the line from its centre onto the
+// surface, losing the velocity that carried them in, and a node at the centre leaves straight up.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Obstacle, Simulation, Sphere, Vec2};
+
+const DELTA_T: f32 = 0.01;
+const CENTER: Vec2 = Vec2::new(1.0, -1.0);
+const RADIUS: f32 = 2.0;
+const TOLERANCE: f32 = 1e-6;
+
+fn sphere() -> Obstacle {
+    Obstacle::Sphere(Sphere { center: CENTER, radius: RADIUS })
+}
+
+#[test]
+fn node_leaves_along_the_line_from_the_centre() {
+    let cases = [
+        (CENTER + Vec2::new(0.5, 0.0), CENTER + Vec2::new(RADIUS, 0.0), Vec2::new(1.0, 0.0)),
+        (CENTER + Vec2::new(0.0, -1.9), CENTER + Vec2::new(0.0, -RADIUS), Vec2::new(0.0, -1.0)),
+        (CENTER + Vec2::new(0.6, 0.8), CENTER + Vec2::new(1.2, 1.6), Vec2::new(0.6, 0.8)),
+        // The centre has no direction of its own, so it goes up.
+        (CENTER, CENTER + Vec2::new(0.0, RADIUS), Vec2::new(0.0, 1.0)),
+    ];
+    for (position, surface, normal) in cases {
+        let (actual_surface, actual_normal) = sphere().push_out(position).expect("inside the sphere");
+        assert!((actual_surface - surface).length() < TOLERANCE, "from {:?}: {:?}", position, actual_surface);
+        assert!((actual_normal - normal).length() < TOLERANCE, "from {:?}: {:?}", position, actual_normal);
+    }
+}
+
+#[test]
+fn nodes_outside_or_on_the_surface_stay_put() {
+    for position in [CENTER + Vec2::new(3.0, 0.0), CENTER + Vec2::new(0.0, RADIUS), CENTER + Vec2::new(-1.5, -1.5)] {
+        assert_eq!(sphere().push_out(position), None, "from {:?}", position);
+    }
+}
+
+#[test]
+fn step_moves_a_node_onto_the_surface_and_stops_it_going_in() {
+    let mut grid = Grid::single_node(CENTER + Vec2::new(0.0, 1.95), Vec2::new(0.0, -1.0), Vec2::ZERO, DELTA_T);
+    grid.add_sphere(CENTER, RADIUS);
+    grid.advance(DELTA_T, false, false);
+    assert_eq!(grid.positions[0], CENTER + Vec2::new(0.0, RADIUS));
+    assert_eq!(grid.velocities[0], Vec2::ZERO);
+}
+
+#[test]
+fn step_keeps_the_velocity_along_the_surface() {
+    let mut grid = Grid::single_node(CENTER + Vec2::new(1.95, 0.0), Vec2::new(-1.0, 0.5), Vec2::ZERO, DELTA_T);
+    grid.add_sphere(CENTER, RADIUS);
+    grid.advance(DELTA_T, false, false);
+    let offset = grid.positions[0] - CENTER;
+    assert!((offset.length() - RADIUS).abs() < TOLERANCE, "{} from the centre", offset.length());
+    let normal = offset / offset.length();
+    let velocity = grid.velocities[0];
+    assert!(velocity.dot(normal).abs() < TOLERANCE, "{:?} still heads in", velocity);
+    assert!(velocity.y > 0.4, "{:?} lost its tangential part", velocity);
+}
@@ -0,0 +1,94 @@
+// Every spring model pulls a stretched spring in and pushes a compressed one out, harder the
+// further it's strained, and stores the energy its force integrates to.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, SimulationConfig, SpringModel, Vec2};
+
+const COEFFICIENT: f32 = 10.0;
+const RELAX_DISTANCE: f32 = 1.0;
+const MODELS: [SpringModel; 3] = [
+    SpringModel::Linear,
+    SpringModel::Biphasic { threshold: 0.1, stiffening: 10.0 },
+    SpringModel::Cubic { coefficient: 5.0 },
+];
+
+// Extensions from well compressed to well stretched, as fractions of the relax distance.
+fn extensions() -> impl Iterator<Item = f32> {
+    (-50..=50).map(|step| step as f32 * 0.01 * RELAX_DISTANCE)
+}
+
+#[test]
+fn force_has_the_sign_of_the_extension() {
+    for model in MODELS {
+        assert_eq!(model.force(COEFFICIENT, 0.0, RELAX_DISTANCE), 0.0);
+        for extension in extensions().filter(|&extension| extension != 0.0) {
+            let force = model.force(COEFFICIENT, extension, RELAX_DISTANCE);
+            assert_eq!(force.signum(), extension.signum(), "{:?} at {}", model, extension);
+        }
+    }
+}
+
+#[test]
+fn force_grows_with_extension() {
+    for model in MODELS {
+        let forces: Vec<f32> = extensions().map(|extension| model.force(COEFFICIENT, extension, RELAX_DISTANCE)).collect();
+        for pair in forces.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} isn't increasing: {:?}", model, pair);
+        }
+    }
+}
+
+#[test]
+fn nonlinear_models_stiffen_when_stretched() {
+    let slope = |model: SpringModel, extension: f32| {
+        let step = 1e-3;
+        (model.force(COEFFICIENT, extension + step, RELAX_DISTANCE) - model.force(COEFFICIENT, extension - step, RELAX_DISTANCE)) / (2.0 * step)
+    };
+    for model in MODELS {
+        let (relaxed, stretched) = (slope(model, 0.0), slope(model, 0.4));
+        assert!((relaxed - COEFFICIENT).abs() < 1e-2, "{:?} has slope {} at rest", model, relaxed);
+        match model {
+            SpringModel::Linear => assert!((stretched - relaxed).abs() < 1e-2),
+            _ => assert!(stretched > 2.0 * relaxed, "{:?} only reaches slope {}", model, stretched),
+        }
+    }
+    // Biphasic fabric stays soft in compression.
+    assert!((slope(MODELS[1], -0.4) - COEFFICIENT).abs() < 1e-2);
+}
+
+#[test]
+fn energy_integrates_the_force() {
+    for model in MODELS {
+        assert_eq!(model.energy(COEFFICIENT, 0.0, RELAX_DISTANCE), 0.0);
+        let step = 1e-3;
+        for extension in extensions() {
+            // The difference can't be taken across the biphasic model's knee, where the slope jumps.
+            if let SpringModel::Biphasic { threshold, .. } = model {
+                if (extension - threshold * RELAX_DISTANCE).abs() <= step {
+                    continue;
+                }
+            }
+            let slope = (model.energy(COEFFICIENT, extension + step, RELAX_DISTANCE)
+                - model.energy(COEFFICIENT, extension - step, RELAX_DISTANCE)) / (2.0 * step);
+            let force = model.force(COEFFICIENT, extension, RELAX_DISTANCE);
+            assert!((slope - force).abs() < 1e-2 * force.abs().max(1.0), "{:?} at {}: {} vs {}", model, extension, slope, force);
+        }
+    }
+}
+
+#[test]
+fn grid_springs_use_the_model() {
+    for model in MODELS {
+        let config = SimulationConfig { spring_model: model, ..SimulationConfig::default() };
+        let mut grid = Grid::with_config(2, 1, config);
+        grid.get_neighbors();
+        let stretch = 0.3;
+        grid.positions[1] = grid.positions[0] + Vec2::new(grid.config.spring_relax_distance + stretch, 0.0);
+        let expected = model.force(grid.config.spring_coefficient, stretch, grid.config.spring_relax_distance);
+        let spring = grid.debug_forces(0, false, false).spring;
+        assert!((spring.x - expected).abs() < 1e-4 && spring.y == 0.0, "{:?} pulls {:?}, expected {}", model, spring, expected);
+        let potential = grid.total_energy().1;
+        assert!((potential - model.energy(grid.config.spring_coefficient, stretch, grid.config.spring_relax_distance)).abs() < 1e-4);
+    }
+}
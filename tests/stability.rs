@@ -0,0 +1,123 @@
+// Sweeps stiffness, damping and timestep across wide ranges on a small hanging grid. Every
+// combination has to stay finite, and the ones inside the documented stable envelope have to stay
+// well below the speed limit rather than being held together by it.
+//
+// Stable envelope for the explicit integrators, with m the node mass, k the spring coefficient, c
+// the damping coefficient and dt the step:
+//   k * dt^2 / m <= 0.1 and c * dt / m <= 1
+// Outside it the springs or the damper overshoot each step and the velocity clamp is all that stops
+// the grid from blowing up. Position based dynamics doesn't depend on k, so only the damping bound
+// applies to it.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::{Grid, Integrator, Simulation, SimulationConfig};
+
+const SIZE: usize = 6;
+const STEPS: usize = 200;
+const SEED: u64 = 7;
+
+const STIFFNESSES: [f32; 6] = [1.0, 10.0, 1e2, 1e3, 1e4, 1e6];
+const DAMPINGS: [f32; 4] = [0.0, 0.03, 1.0, 10.0];
+const TIMESTEPS: [f32; 4] = [1e-4, 1e-3, 1e-2, 1e-1];
+const INTEGRATORS: [Integrator; 4] = [
+    Integrator::SymplecticEuler,
+    Integrator::Verlet,
+    Integrator::Rk4,
+    Integrator::PositionBased { iterations: 10 },
+];
+
+// Fraction of `max_velocity` a stable run must stay under.
+const STABLE_SPEED_FRACTION: f32 = 0.5;
+
+// Runs a grid pinned at its top corners with gravity and the seeded random force on, and returns
+// the highest node speed seen. Panics as soon as any position or velocity stops being finite.
+fn run(config: SimulationConfig, delta_t: f32) -> f32 {
+    let mut grid = Grid::with_config(SIZE, SIZE, config);
+    grid.get_neighbors();
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+
+    let mut max_speed: f32 = 0.0;
+    for step in 0..STEPS {
+        grid.advance(delta_t, true, true);
+        let finite = grid
+            .positions
+            .iter()
+            .chain(&grid.velocities)
+            .all(|value| value.x.is_finite() && value.y.is_finite());
+        assert!(finite, "non-finite state after {} steps of dt {} with {:?}", step + 1, delta_t, config);
+        max_speed = grid.velocities.iter().map(|velocity| velocity.length()).fold(max_speed, f32::max);
+    }
+    max_speed
+}
+
+fn configs() -> impl Iterator<Item = (SimulationConfig, f32)> {
+    INTEGRATORS.into_iter().flat_map(|integrator| {
+        STIFFNESSES.into_iter().flat_map(move |spring_coefficient| {
+            DAMPINGS.into_iter().flat_map(move |damping_coefficient| {
+                TIMESTEPS.into_iter().map(move |delta_t| {
+                    let config = SimulationConfig {
+                        spring_coefficient,
+                        damping_coefficient,
+                        integrator,
+                        seed: SEED,
+                        ..SimulationConfig::default()
+                    };
+                    (config, delta_t)
+                })
+            })
+        })
+    })
+}
+
+fn in_stable_envelope(config: &SimulationConfig, delta_t: f32) -> bool {
+    let damping_ok = config.damping_coefficient * delta_t / config.mass <= 1.0;
+    let springs_ok = match config.integrator {
+        Integrator::PositionBased { .. } => true,
+        _ => config.spring_coefficient * delta_t * delta_t / config.mass <= 0.1,
+    };
+    damping_ok && springs_ok
+}
+
+#[test]
+fn every_config_stays_finite() {
+    for (config, delta_t) in configs() {
+        let max_speed = run(config, delta_t);
+        assert!(
+            max_speed <= config.max_velocity * (1.0 + 1e-4),
+            "speed {} above the limit with dt {} and {:?}",
+            max_speed,
+            delta_t,
+            config
+        );
+    }
+}
+
+#[test]
+fn stable_envelope_stays_below_the_speed_limit() {
+    for (config, delta_t) in configs().filter(|(config, delta_t)| in_stable_envelope(config, *delta_t)) {
+        let max_speed = run(config, delta_t);
+        assert!(
+            max_speed < config.max_velocity * STABLE_SPEED_FRACTION,
+            "speed {} inside the stable envelope with dt {} and {:?}",
+            max_speed,
+            delta_t,
+            config
+        );
+    }
+}
+
+#[test]
+fn equal_seeds_reproduce_the_same_run() {
+    let config = SimulationConfig { seed: SEED, ..SimulationConfig::default() };
+    let positions = || {
+        let mut grid = Grid::with_config(SIZE, SIZE, config);
+        grid.get_neighbors();
+        for _ in 0..STEPS {
+            grid.advance(1e-2, true, true);
+        }
+        grid.positions
+    };
+    assert_eq!(positions(), positions());
+}
@@ -0,0 +1,47 @@
+// Every structural spring is counted once, in the bin its signed strain falls in.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::grid::STRAIN_HISTOGRAM_RANGE;
+use soft_body_sim_rust::{Connectivity, Grid, Vec2};
+
+const SIZE: usize = 5;
+const BINS: usize = 10;
+
+fn springs(grid: &Grid) -> usize {
+    grid.neighbours.iter().map(Vec::len).sum::<usize>() / 2
+}
+
+#[test]
+fn relaxed_grid_counts_every_spring_at_rest() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    let counts = grid.strain_histogram(BINS);
+    assert_eq!(counts.len(), BINS);
+    assert_eq!(counts[BINS / 2], springs(&grid), "{:?}", counts);
+}
+
+#[test]
+fn uniformly_stretched_grid_lands_in_one_bin() {
+    let strain = 0.15;
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.build_neighbours(Connectivity::Eight);
+    grid.transform(1.0 + strain, 0.0, Vec2::ZERO);
+
+    let counts = grid.strain_histogram(BINS);
+    let bin_width = 2.0 * STRAIN_HISTOGRAM_RANGE / BINS as f32;
+    let expected = ((strain + STRAIN_HISTOGRAM_RANGE) / bin_width) as usize;
+    assert_eq!(counts[expected], springs(&grid), "{:?}", counts);
+    assert_eq!(counts.iter().sum::<usize>(), springs(&grid));
+}
+
+#[test]
+fn strains_past_the_range_land_in_the_end_bins() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    grid.transform(3.0, 0.0, Vec2::ZERO);
+    assert_eq!(grid.strain_histogram(BINS)[BINS - 1], springs(&grid));
+    grid.transform(0.1, 0.0, Vec2::ZERO);
+    assert_eq!(grid.strain_histogram(BINS)[0], springs(&grid));
+    assert!(grid.strain_histogram(0).is_empty());
+}
@@ -0,0 +1,42 @@
+// The end-of-run summary reports what it was given, leaving out what the run couldn't measure.
+
+extern crate soft_body_sim_rust;
+
+use std::time::Duration;
+
+use soft_body_sim_rust::benchmark::{self, RunSummary, TimingSummary};
+
+#[test]
+fn report_lists_every_measurement() {
+    let durations = [Duration::from_micros(300), Duration::from_micros(100), Duration::from_micros(200)];
+    let summary = RunSummary {
+        steps: 12,
+        elapsed: Duration::from_millis(2500),
+        updates: TimingSummary::from_durations(&durations),
+        sim_time: Some(0.12),
+        peak_memory: Some(3 * 1024 * 1024),
+        energy: Some(1.5),
+    };
+    let report = summary.report();
+    assert!(report.starts_with("Ran 12 steps in 2.50s\n"), "{}", report);
+    // 600us over 12 steps.
+    assert!(report.contains("mean step            50.000 us"), "{}", report);
+    assert!(report.contains("mean update         200.000 us over 3 updates"), "{}", report);
+    assert!(report.contains("median update       200.000 us"), "{}", report);
+    assert!(report.contains("simulated             0.120 s"), "{}", report);
+    assert!(report.contains("peak memory             3.0 MiB"), "{}", report);
+    assert!(report.contains("final energy       1.500000\n"), "{}", report);
+}
+
+#[test]
+fn report_leaves_out_what_was_not_measured() {
+    let summary = RunSummary { steps: 0, elapsed: Duration::ZERO, updates: None, sim_time: None, peak_memory: None,
+                               energy: None };
+    assert_eq!(summary.report(), "Ran 0 steps in 0.00s\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn peak_memory_is_reported_on_linux() {
+    assert!(benchmark::peak_memory().is_some_and(|bytes| bytes > 0));
+}
@@ -0,0 +1,21 @@
+// Every simulation builds its own pool, so setting one up again, as the thread scaling benchmark
+// does for each thread count, has to work just like the first time.
+
+extern crate soft_body_sim_rust;
+
+use soft_body_sim_rust::benchmark;
+use soft_body_sim_rust::{Grid, Simulation};
+
+const SIZE: usize = 4;
+const DELTA_T: f32 = 0.01;
+
+#[test]
+fn pools_can_be_set_up_more_than_once() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    for threads in [2, 2, 1] {
+        let pool = benchmark::build_thread_pool(threads).expect("failed to build a thread pool");
+        pool.install(|| grid.advance(DELTA_T, true, false));
+    }
+    assert_eq!(grid.step_count, 3);
+}
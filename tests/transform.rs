@@ -0,0 +1,62 @@
+// Rotating and scaling a grid and then undoing it has to put every node back where it was.
+
+extern crate soft_body_sim_rust;
+
+use std::f32::consts::FRAC_PI_2;
+
+use soft_body_sim_rust::{Grid, Simulation, Vec2};
+
+const SIZE: usize = 6;
+const EPSILON: f32 = 1e-4;
+
+fn moved_grid() -> Grid {
+    let mut grid = Grid::new(SIZE, SIZE);
+    grid.get_neighbors();
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    for _ in 0..20 {
+        grid.advance(0.01, true, false);
+    }
+    grid
+}
+
+fn assert_close(a: &[Vec2], b: &[Vec2]) {
+    for (index, (a, b)) in a.iter().zip(b).enumerate() {
+        assert!((*a - *b).length() < EPSILON, "node {} at {:?} instead of {:?}", index, a, b);
+    }
+}
+
+#[test]
+fn rotation_round_trips() {
+    let center = Vec2::new(1.5, 7.0);
+    for angle in [0.3, -1.2, 2.5, 6.0] {
+        let mut grid = moved_grid();
+        let (positions, velocities) = (grid.positions.clone(), grid.velocities.clone());
+        grid.transform(1.0, angle, center);
+        grid.transform(1.0, -angle, center);
+        assert_close(&grid.positions, &positions);
+        assert_close(&grid.velocities, &velocities);
+    }
+}
+
+#[test]
+fn scale_and_rotation_round_trip() {
+    let mut grid = moved_grid();
+    let positions = grid.positions.clone();
+    grid.transform(2.0, 0.7, Vec2::ZERO);
+    grid.transform(0.5, -0.7, Vec2::ZERO);
+    assert_close(&grid.positions, &positions);
+}
+
+#[test]
+fn quarter_turn_moves_pinned_nodes() {
+    let mut grid = Grid::new(SIZE, SIZE);
+    let anchors = grid.top_corners().unwrap();
+    grid.set_fixed(&anchors);
+    let center = grid.positions[0];
+    let offset = grid.positions[anchors[1]] - center;
+    grid.transform(1.0, FRAC_PI_2, center);
+    let expected = center + Vec2::new(-offset.y, offset.x);
+    assert!((grid.positions[anchors[1]] - expected).length() < EPSILON);
+    assert!(grid.fixed[anchors[1]]);
+}